@@ -1,101 +1,1055 @@
 // src/app.rs
 
-use crate::parsers::{parse_file, ParsedFile};
-use hex;
+use crate::parsers::{detect_format, parse_file, FileFormat, ParsedFile, Prefetch, Structure, MAGIC_LEN};
+use crate::utils::{
+    address_digits, decode_leb128, diff_ranges, find_all, find_pattern, fit_bytes_per_line, format_address, format_c_array, format_hex, format_plain_dump, inspect_bytes, overview_cell, parse_clipboard_bytes,
+    parse_hex_pattern, parse_number, region_stats, selection_between, sparse_holes, AddressStyle, AsciiRender, BlockSelection, Bookmark, ColorScheme, ChecksumAlgo, ChecksumState, CopyFormat, DigestAlgo, DigestState, DisplayOptions, DisplayRadix, Endianness, HighlightTerm,
+    Highlights, Inspection, LineChecksum, PrintablePolicy, RegionStats, OverviewCell, TERM_COLORS,
+};
+use memmap2::Mmap;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rand::rngs::StdRng;
+use rand::RngExt;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::Line;
+use std::collections::HashMap;
 use std::error::Error;
 use std::fs::File;
-use twoway::find_bytes;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
 use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
 
 /// Application modes
 pub enum AppMode {
     Normal,
     Search,
     Goto,
+    Compare, // Typing a hex pattern compared live against the top of the view
     Help,
+    Sections,  // Choosing an ELF section to jump to
+    Bookmarks, // Choosing a bookmark to jump to or delete
+    Edit,      // Typing overwrites the byte under the cursor
 }
 
 /// Types of searches
+#[derive(Clone, Copy)]
 pub enum SearchType {
     Ascii,
     Hex,
+    Regex, // Byte regex over the file (e.g. `\d{4}-\d{2}`)
 }
 
 /// Available themes
 pub enum Theme {
     Light,
     Dark,
+    Custom(ColorScheme), // Loaded from the theme file (--theme custom)
+}
+
+/// Horizontal placement of the hex dump within the content width
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ContentAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// Colors resolved from the active theme, shared by every block in the UI
+#[derive(Clone, Copy)]
+pub struct ThemeColors {
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl Theme {
+    /// Resolves the theme's base colors
+    pub fn colors(&self) -> ThemeColors {
+        match self {
+            Theme::Light => ThemeColors { fg: Color::Black, bg: Color::White },
+            Theme::Dark => ThemeColors { fg: Color::White, bg: Color::Black },
+            Theme::Custom(scheme) => ThemeColors { fg: scheme.foreground, bg: scheme.background },
+        }
+    }
+
+    /// Resolves the colors of the hex dump
+    pub fn dump_colors(&self) -> ColorScheme {
+        match self {
+            Theme::Custom(scheme) => *scheme,
+            _ => ColorScheme::default(),
+        }
+    }
+}
+
+/// Returns where a custom theme is read from: `~/.config/hex-viewer/theme.toml`
+pub fn custom_theme_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(Path::new(&home).join(".config").join("hex-viewer").join("theme.toml"))
+}
+
+/// Reads a custom color scheme from a TOML file of `field = "color"` lines
+pub fn load_color_scheme(path: &Path) -> Result<ColorScheme, Box<dyn Error>> {
+    // The error's message alone, without the multi-line source excerpt
+    let scheme = toml::from_str(&std::fs::read_to_string(path)?).map_err(|e| e.message().to_string())?;
+    Ok(scheme)
+}
+
+impl ThemeColors {
+    /// Swaps foreground and background
+    pub fn inverted(self) -> Self {
+        ThemeColors { fg: self.bg, bg: self.fg }
+    }
+
+    /// Base style for blocks drawn with these colors
+    pub fn style(&self) -> Style {
+        Style::default().fg(self.fg).bg(self.bg)
+    }
+}
+
+/// Lines read ahead of the visible window for files read on demand
+pub const DEFAULT_PREFETCH_LINES: usize = 64;
+
+/// Largest selection shown as hex in the message bar, unless --readout-limit says otherwise
+pub const DEFAULT_READOUT_LIMIT: usize = 64;
+
+/// How long informational messages stay in the message bar by default
+pub const DEFAULT_MESSAGE_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// Scroll events closer together than this count as a held key
+const SCROLL_REPEAT_WINDOW: Duration = Duration::from_millis(80);
+/// Consecutive rapid scroll events before the step size doubles
+const SCROLL_EVENTS_PER_STEP: usize = 8;
+
+/// Positions kept for Back navigation
+const NAV_HISTORY_LIMIT: usize = 100;
+
+/// Line widths cycled through by the quick width key
+const COMMON_WIDTHS: [usize; 6] = [8, 16, 24, 32, 48, 64];
+
+/// Narrowest and widest lines reachable with `+`/`-`
+const MIN_BYTES_PER_LINE: usize = 4;
+const MAX_BYTES_PER_LINE: usize = 64;
+
+/// Lines of context kept around each match in the filter view
+const FILTER_CONTEXT_LINES: usize = 1;
+
+/// Bytes sampled from each overview row of a file read on demand
+const OVERVIEW_SAMPLE_LEN: usize = 4096;
+
+/// File path that reads the input from stdin instead
+pub const STDIN_PATH: &str = "-";
+/// Name shown for input read from stdin
+const STDIN_NAME: &str = "<stdin>";
+/// Suffix of the sidecar file that keeps a file's bookmarks between sessions
+const BOOKMARK_SUFFIX: &str = ".hexbookmarks";
+/// Appended to a file's path for the copy kept when saving over it with `--backup`
+const BACKUP_SUFFIX: &str = ".bak";
+/// Appended to a file's path for the copy written while saving, before it replaces the file
+const SAVE_TEMP_SUFFIX: &str = ".hexsave";
+/// Appended to a file's path for the copy of unsaved edits kept by --autosave
+const AUTOSAVE_SUFFIX: &str = ".autosave";
+
+/// How long the file must go unchanged before a watched file is reloaded
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Bytes compared per read when diffing two files
+const DIFF_CHUNK_LEN: usize = 1 << 20;
+
+/// Largest selection copied to the clipboard
+const COPY_LIMIT: usize = 16 << 20;
+
+/// Bytes per line of a copied C array unless the command gives a width
+const C_ARRAY_BYTES_PER_LINE: usize = 12;
+
+/// Lines formatted per read when exporting a dump
+const EXPORT_CHUNK_LINES: usize = 4096;
+
+/// Bytes searched per read when a file is not in memory or mapped
+const SEARCH_CHUNK_LEN: usize = 1 << 20;
+/// Pause in typing after which the search query typed so far is searched
+const INCREMENTAL_SEARCH_DELAY: Duration = Duration::from_millis(150);
+/// Shortest query searched as it's typed; shorter ones match nearly everywhere
+const INCREMENTAL_SEARCH_MIN_LEN: usize = 2;
+/// Largest file searched as the query is typed; bigger ones wait for Enter
+const INCREMENTAL_SEARCH_MAX_SIZE: usize = 64 << 20;
+/// Bytes read at a time when checksumming a selection or the whole file
+const CHECKSUM_CHUNK_LEN: usize = 1 << 20;
+
+/// Inputs that determine the formatted hex dump. When none of them change
+/// between frames, the cached lines are reused as-is.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct RenderKey {
+    pub scroll_offset: usize,
+    pub bytes_per_line: usize,
+    pub visible_height: usize,
+    pub view_version: u64,
+}
+
+/// Watches the open file for changes made by other programs
+pub struct FileWatcher {
+    _watcher: RecommendedWatcher, // Watching stops when this is dropped
+    events: Receiver<notify::Result<notify::Event>>,
+    reload_at: Option<Instant>, // When the latest burst of changes will have settled
+}
+
+/// A second file shown beside the first for comparison
+pub struct SecondFile {
+    pub path: String,
+    pub parsed_file: ParsedFile,
+    pub size: usize,
+}
+
+/// The whole file and the view of it, put aside while one of its sections is viewed
+/// alone. Bookmarks inside the section move with it, at section-relative offsets.
+struct IsolatedSection {
+    name: String,
+    offset: usize, // File offset of the section's first byte
+    parsed_file: ParsedFile,
+    file_size: usize,
+    base_address: usize,
+    bookmarks: Vec<Bookmark>, // Bookmarks outside the section
+    search_results: Vec<Range<usize>>,
+    search_groups: Vec<Vec<Range<usize>>>,
+    last_search: Option<(SearchType, String)>,
+    highlight_terms: Vec<HighlightTerm>,
+    holes: Vec<Range<usize>>,
+    back_history: Vec<usize>,
+    forward_history: Vec<usize>,
 }
 
 /// Application state
 pub struct App {
     pub running: bool,
     pub file_path: String,
-    pub parsed_file: ParsedFile, // Either Generic(Vec<u8>) or Lazy(File)
+    pub parsed_file: ParsedFile, // In memory, mapped, or read on demand
+    pub prefetch: Prefetch,      // Read-ahead for files read on demand
     pub scroll_offset: usize,
     pub bytes_per_line: usize,
+    pub scroll_acceleration: bool, // Speed up scrolling while a key is held
+    pub last_scroll_at: Option<Instant>,
+    pub scroll_velocity: usize, // Consecutive rapid scroll events so far
     pub mode: AppMode,
     pub input_buffer: String,
     pub search_results: Vec<Range<usize>>, // Changed to store ranges
+    pub search_groups: Vec<Vec<Range<usize>>>, // Bytes each capture group of a regex search matched, by group
+    last_search: Option<(SearchType, String)>, // Query behind the search results, rerun when the file grows
+    pub highlight_terms: Vec<HighlightTerm>, // Extra patterns added with :hl, each in its own color
+    search_at: Option<Instant>, // When the query being typed will be searched
+    pub current_match: Option<usize>, // Index of the match last reached with n/N
+    pub diff_ranges: Vec<Range<usize>>, // Bytes that differ from a comparison source
+    pub second_file: Option<SecondFile>, // File shown beside this one, when comparing two files
+    pub overlay: bool, // Second file's differing bytes are shown in this file's cells instead of beside them
+    pub file_diffs: Vec<Range<usize>>, // Bytes that differ between the two files
+    current_diff: Option<usize>, // Index of the differing region last moved to with ]/[
+    pub agree_ranges: Vec<Range<usize>>, // Bytes equal to a typed comparison pattern
+    pub compare_start: usize,            // Offset the typed pattern is compared at
+    pub inspected: Option<Range<usize>>, // Bytes consumed by the last LEB128 decode
+    pub holes: Vec<Range<usize>>, // Unallocated extents of a sparse file (--holes)
     pub search_type: SearchType,
+    pub default_search: SearchType, // Search type entered with '/'
+    pub case_insensitive: bool, // ASCII searches fold ASCII letters
+    pub search_alignment: Option<usize>, // Only keep matches starting at a multiple of this
     pub file_size: usize,
+    pub file_format: FileFormat, // Format named by the file's magic bytes
     pub theme: Theme,
+    pub invert_colors: bool, // Swap fg/bg on top of the active theme
+    pub custom_scheme: Option<ColorScheme>, // Custom theme 't' cycles to, once loaded
     pub message: Option<String>, // New field for temporary messages
+    pub message_is_error: bool,
+    pub message_at: Option<Instant>,        // When the current message was set
+    pub message_timeout: Option<Duration>, // None keeps messages until dismissed
+    pub sticky_errors: bool,                // Errors stay until dismissed even with a timeout
+    pub rng: StdRng, // Picks offsets for random sampling; seeded with --seed
+    pub back_history: Vec<usize>,    // Byte offsets left by discrete jumps, newest last
+    pub forward_history: Vec<usize>, // Offsets undone by Back, newest last
+    pub symbols: HashMap<String, usize>, // Symbol addresses loaded from --map
+    pub symbol_base: usize,              // Address of file offset 0 for symbols
+    pub printable: PrintablePolicy,
+    pub ascii_render: AsciiRender,
+    pub uppercase_hex: bool,
+    pub checksum: Option<LineChecksum>, // Per-line checksum column, off when None
+    pub address_style: AddressStyle,
+    pub address_width: Option<usize>, // Hex digits in the address gutter (--addr-width); sized to the file if None
+    pub base_address: usize, // Address of file offset 0 in the gutter and Goto (--base), e.g. a dump's load address
+    pub group_size: usize, // Bytes per group in the hex column (--group-size); 0 doesn't group
+    pub radix: DisplayRadix, // Base of the byte cells in the hex column
+    pub compact_runs: bool, // Show runs of a low byte as `.×N` in the ASCII column
+    pub content_align: ContentAlign,
+    pub content_width: Option<usize>, // Width the dump is aligned within; None uses the pane
+    pub text_mode: bool, // Show fixed-width text records (bytes_per_line wide) instead of hex
+    pub unavailable: Option<String>, // Set once reading fails (file removed, device unplugged); stops further reads
+    pub uniform_byte: Option<u8>, // Set when the file is one repeated byte
+    pub dirty: bool,              // The in-memory bytes have edits not yet saved
+    pub edit_ascii: bool,         // Edit mode types characters rather than hex digits
+    pub edit_low_nibble: bool,    // The cursor byte's high nibble was typed; the low one is next
+    pub backup_on_save: bool,     // Copy a file to `<file>.bak` before saving over it
+    pub autosave_after: Option<Duration>, // Idle time after an edit before the edits are copied to `<file>.autosave`
+    last_edit_at: Option<Instant>, // When the bytes were last edited, until the edits are auto-saved
+    pub show_raw: bool,           // Render the hex dump even for uniform files
+    pub filter_lines: Option<Vec<usize>>, // Lines shown in the filter view; scroll_offset indexes into it
+    pub reversed_line: Option<usize>, // File line temporarily displayed in reverse byte order
+    pub show_stats: bool, // Statistics panel for the visible bytes
+    pub cursor: usize, // Absolute offset of the selected byte
+    pub selection: Option<Range<usize>>, // Selected bytes; the cursor is always one end
+    selection_anchor: usize, // The end of the selection that stays put while extending it
+    pub block_mode: bool, // The selection is a rectangle of columns across lines, like vim's visual block ('V')
+    pub show_inspector: bool, // Side panel reading the bytes at the cursor as numbers
+    pub section_index: usize, // Highlighted entry of the ELF section list
+    pub bookmarks: Vec<Bookmark>, // Sorted by offset, at most one per offset
+    pub readout_limit: usize, // Largest selection 'b' shows in the message bar (--readout-limit)
+    pub bookmark_index: usize,    // Highlighted entry of the bookmark list
+    pub endianness: Endianness, // Byte order of the inspector's multi-byte values
+    pub stats_cache: Option<(Range<usize>, RegionStats)>,
+    pub metadata_area: Rect, // Where the metadata bar was last drawn, for mouse hit-testing
+    pub content_area: Rect, // Where the hex dump was last drawn
+    pub content_margin: usize, // Alignment padding before each dump line in the last frame
+    pub show_overview: bool, // Narrow overview pane beside the hex dump
+    pub overview_area: Rect, // Where the overview pane was last drawn
+    pub show_pins: bool,     // Sidebar listing the bookmarks, beside the hex dump
+    pub pins_area: Rect,     // Where the pins sidebar was last drawn
+    pub overview_cache: Option<(usize, Vec<Option<OverviewCell>>)>, // Row count and the cell of each row
+    pub last_visible_height: usize, // Content rows shown in the last frame
+    pub clipboard: Option<arboard::Clipboard>, // Opened on first use and kept so Linux selections persist
+    pub view_version: u64, // Bumped whenever state feeding the hex dump changes
+    pub render_cache: Option<(RenderKey, Vec<Line<'static>>)>,
+    lazy_threshold: usize, // Size above which the file is mapped rather than read in; kept for reloads
+    pub watcher: Option<FileWatcher>, // Set with --watch
+    pub follow: bool, // Keep the view at the end of the file as it grows, like `tail -f`
+    width_anchor: Option<(usize, usize)>, // Top byte kept across line width changes, and the scroll offset it gave
+    pending_digest: Option<DigestAlgo>, // Digest to compute once its "hashing" message is drawn
+    pub auto_width: bool, // Fit bytes per line to the content pane each frame (--auto-width)
+    isolated: Option<IsolatedSection>, // Set while a single section is viewed alone
 }
 
 impl App {
     /// Initializes a new App instance
-    pub fn new(file_path: String, bytes_per_line: usize, theme: Theme) -> Result<Self, Box<dyn Error>> {
-        let metadata = std::fs::metadata(&file_path)?;
-        let file_size = metadata.len() as usize;
-
-        // Define a threshold for lazy loading (e.g., 10 MB)
-        let threshold = 10 * 1024 * 1024;
-
-        let parsed_file = if file_size > threshold {
-            ParsedFile::Lazy(File::open(&file_path)?)
+    pub fn new(
+        file_path: String,
+        bytes_per_line: usize,
+        theme: Theme,
+        printable: PrintablePolicy,
+        window_offset: u64,
+        window_length: Option<usize>,
+        lazy_threshold: usize,
+    ) -> Result<Self, Box<dyn Error>> {
+        if file_path == STDIN_PATH {
+            let input = io::stdin().lock();
+            return Self::from_reader(input, bytes_per_line, theme, printable, window_offset, window_length, lazy_threshold);
+        }
+        let (parsed_file, file_size) = if window_offset > 0 || window_length.is_some() {
+            // Expose only a byte range; seeking to the end also sizes block devices,
+            // whose metadata reports a length of zero
+            let mut file = File::open(&file_path)?;
+            let end = file.seek(SeekFrom::End(0))?;
+            if window_offset > end {
+                return Err(format!(
+                    "Offset {:#x} is beyond the end of the file ({:#x} bytes)",
+                    window_offset, end
+                )
+                .into());
+            }
+            let available = (end - window_offset) as usize;
+            let len = window_length.map_or(available, |len| usize::min(len, available));
+            (ParsedFile::Window { file, base: window_offset, len }, len)
         } else {
-            parse_file(&file_path)? // parse_file already returns ParsedFile
+            let file_size = std::fs::metadata(&file_path)?.len() as usize;
+            (open_file(&file_path, file_size, lazy_threshold)?, file_size)
         };
+        Ok(Self::from_parsed(file_path, parsed_file, file_size, bytes_per_line, theme, printable, lazy_threshold))
+    }
+
+    /// Initializes an App showing piped input, shown as `<stdin>`. A pipe can't be
+    /// seeked or mapped, so the input is always read into memory.
+    fn from_reader(
+        mut input: impl Read,
+        bytes_per_line: usize,
+        theme: Theme,
+        printable: PrintablePolicy,
+        window_offset: u64,
+        window_length: Option<usize>,
+        lazy_threshold: usize,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        if window_offset > data.len() as u64 {
+            return Err(format!(
+                "Offset {:#x} is beyond the end of the input ({:#x} bytes)",
+                window_offset,
+                data.len()
+            )
+            .into());
+        }
+        data.drain(..window_offset as usize);
+        if let Some(len) = window_length {
+            data.truncate(len);
+        }
+        let file_size = data.len();
+        let parsed_file = ParsedFile::Generic(data);
+        Ok(Self::from_parsed(STDIN_NAME.to_string(), parsed_file, file_size, bytes_per_line, theme, printable, lazy_threshold))
+    }
+
+    /// Initializes an App for a file that has been opened
+    fn from_parsed(
+        file_path: String,
+        mut parsed_file: ParsedFile,
+        file_size: usize,
+        bytes_per_line: usize,
+        theme: Theme,
+        printable: PrintablePolicy,
+        lazy_threshold: usize,
+    ) -> Self {
+        let uniform_byte = parsed_file.uniform_byte(file_size);
+        let file_format = detect_format(&parsed_file.read_range(0, MAGIC_LEN).unwrap_or_default());
 
-        Ok(Self {
+        let mut app = Self {
             running: true,
             file_path,
             parsed_file,
+            prefetch: Prefetch::new(DEFAULT_PREFETCH_LINES),
             scroll_offset: 0,
-            bytes_per_line,
+            bytes_per_line: bytes_per_line.max(1), // Zero would divide by zero on the first frame
+            scroll_acceleration: false,
+            last_scroll_at: None,
+            scroll_velocity: 0,
             mode: AppMode::Normal,
             input_buffer: String::new(),
             search_results: Vec::new(),
+            search_groups: Vec::new(),
+            last_search: None,
+            highlight_terms: Vec::new(),
+            search_at: None,
+            current_match: None,
+            diff_ranges: Vec::new(),
+            second_file: None,
+            overlay: false,
+            file_diffs: Vec::new(),
+            current_diff: None,
+            agree_ranges: Vec::new(),
+            compare_start: 0,
+            inspected: None,
+            holes: Vec::new(),
             search_type: SearchType::Ascii,
+            default_search: SearchType::Ascii,
+            case_insensitive: false,
+            search_alignment: None,
             file_size,
+            file_format,
             theme,
+            invert_colors: false,
+            custom_scheme: None,
             message: None, // Initialize message as None
-        })
+            message_is_error: false,
+            message_at: None,
+            message_timeout: Some(DEFAULT_MESSAGE_TIMEOUT),
+            sticky_errors: false,
+            rng: rand::make_rng(),
+            back_history: Vec::new(),
+            forward_history: Vec::new(),
+            symbols: HashMap::new(),
+            symbol_base: 0,
+            printable,
+            ascii_render: AsciiRender::Dots,
+            uppercase_hex: false,
+            checksum: None,
+            address_style: AddressStyle::Linear,
+            address_width: None,
+            base_address: 0,
+            group_size: 0,
+            radix: DisplayRadix::default(),
+            compact_runs: false,
+            content_align: ContentAlign::Left,
+            content_width: None,
+            text_mode: false,
+            unavailable: None,
+            uniform_byte,
+            dirty: false,
+            autosave_after: None,
+            last_edit_at: None,
+            edit_ascii: false,
+            edit_low_nibble: false,
+            backup_on_save: false,
+            show_raw: false,
+            filter_lines: None,
+            reversed_line: None,
+            show_stats: false,
+            cursor: 0,
+            selection: None,
+            selection_anchor: 0,
+            block_mode: false,
+            show_inspector: false,
+            section_index: 0,
+            bookmarks: Vec::new(),
+            readout_limit: DEFAULT_READOUT_LIMIT,
+            bookmark_index: 0,
+            endianness: Endianness::Little,
+            stats_cache: None,
+            metadata_area: Rect::default(),
+            content_area: Rect::default(),
+            content_margin: 0,
+            show_overview: false,
+            overview_area: Rect::default(),
+            show_pins: false,
+            pins_area: Rect::default(),
+            overview_cache: None,
+            last_visible_height: 0,
+            clipboard: None,
+            view_version: 0,
+            render_cache: None,
+            lazy_threshold,
+            watcher: None,
+            follow: false,
+            width_anchor: None,
+            auto_width: false,
+            isolated: None,
+            pending_digest: None,
+        };
+        app.report_unparsed_format();
+        app
     }
 
-    /// Scrolls up by one line
-    pub fn scroll_up(&mut self) {
-        if self.scroll_offset > 0 {
-            self.scroll_offset -= 1;
+    /// Warns when the magic bytes name an executable format whose headers don't
+    /// parse, since the file is then shown as plain bytes with no sections
+    fn report_unparsed_format(&mut self) {
+        let parsed = !matches!(self.parsed_file, ParsedFile::Generic(_)) || self.file_path == STDIN_NAME;
+        if matches!(self.file_format, FileFormat::Elf | FileFormat::Pe | FileFormat::Png) && !parsed {
+            self.set_error(format!(
+                "The file starts like {} but its headers don't parse; showing the raw bytes.",
+                self.file_format.name()
+            ));
+        }
+    }
+
+    /// Shows an informational message
+    pub fn set_message(&mut self, text: impl Into<String>) {
+        self.message = Some(text.into());
+        self.message_is_error = false;
+        self.message_at = Some(Instant::now());
+    }
+
+    /// Shows an error message, which stays until dismissed if errors are sticky
+    pub fn set_error(&mut self, text: impl Into<String>) {
+        self.message = Some(text.into());
+        self.message_is_error = true;
+        self.message_at = Some(Instant::now());
+    }
+
+    /// Clears the message once its timeout has passed
+    pub fn expire_message(&mut self) {
+        let (Some(at), Some(timeout)) = (self.message_at, self.message_timeout) else {
+            return;
+        };
+        if self.message.is_some()
+            && !(self.message_is_error && self.sticky_errors)
+            && at.elapsed() >= timeout
+        {
+            self.message = None;
         }
     }
 
-    /// Scrolls down by one line
+    /// Scrolls up by one line (more while accelerating)
+    pub fn scroll_up(&mut self) {
+        let step = self.scroll_step();
+        self.scroll_offset = self.scroll_offset.saturating_sub(step);
+    }
+
+    /// Scrolls down by one line (more while accelerating)
     pub fn scroll_down(&mut self) {
-        if self.scroll_offset < self.max_scroll_offset() {
-            self.scroll_offset += 1;
+        let step = self.scroll_step();
+        self.scroll_offset = usize::min(self.scroll_offset + step, self.max_scroll_offset());
+    }
+
+    /// Moves the cursor `bytes` forward or back, clamped to the file, scrolling to keep
+    /// it on screen. With `select`, the selection grows or shrinks to follow the cursor;
+    /// otherwise it is cleared. A cursor left off screen by scrolling or a jump first
+    /// snaps to the top of the view instead of dragging the view back to it.
+    pub fn move_cursor(&mut self, forward: bool, bytes: usize, select: bool) {
+        let last = self.file_size.saturating_sub(1);
+        let on_screen = self.visible_range().contains(&self.cursor);
+        if !on_screen {
+            self.cursor = usize::min(self.top_line() * self.bytes_per_line, last);
+        }
+        if !select {
+            self.selection = None;
+        } else if self.selection.is_none() {
+            self.selection_anchor = self.cursor;
+        }
+        if on_screen {
+            self.cursor = if forward {
+                usize::min(self.cursor.saturating_add(bytes), last)
+            } else {
+                self.cursor.saturating_sub(bytes)
+            };
+        }
+        if select {
+            self.selection = Some(selection_between(self.selection_anchor, self.cursor));
+        }
+        self.scroll_to_cursor();
+        self.invalidate_view();
+    }
+
+    /// Moves the cursor up or down a line (more while accelerating)
+    pub fn move_cursor_lines(&mut self, down: bool, select: bool) {
+        let lines = self.scroll_step();
+        self.move_cursor(down, lines * self.bytes_per_line, select);
+    }
+
+    /// Puts the cursor on the byte under a mouse press and starts a new selection there.
+    /// Returns false if the press missed the dump.
+    pub fn press_content(&mut self, column: u16, row: u16) -> bool {
+        let Some(offset) = self.byte_at(column, row) else {
+            return false;
+        };
+        self.cursor = offset;
+        self.selection_anchor = offset;
+        self.selection = None;
+        self.invalidate_view();
+        true
+    }
+
+    /// Extends the selection from the mouse press to the byte under a drag
+    pub fn drag_content(&mut self, column: u16, row: u16) {
+        if let Some(offset) = self.byte_at(column, row) {
+            self.cursor = offset;
+            self.selection = Some(selection_between(self.selection_anchor, offset));
+            self.invalidate_view();
+        }
+    }
+
+    /// Starts watching the file so the view reloads when it changes on disk
+    pub fn watch(&mut self) -> notify::Result<()> {
+        if self.file_path == STDIN_NAME {
+            return Err(notify::Error::generic("stdin cannot be watched"));
+        }
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(Path::new(&self.file_path), RecursiveMode::NonRecursive)?;
+        self.watcher = Some(FileWatcher { _watcher: watcher, events, reload_at: None });
+        Ok(())
+    }
+
+    /// Reloads a watched file once reported changes have settled for `WATCH_DEBOUNCE`,
+    /// so a burst of writes costs one reload. Called on every pass of the event loop.
+    pub fn poll_watch(&mut self) {
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+        let now = Instant::now();
+        for event in watcher.events.try_iter().flatten() {
+            if matches!(event.kind, EventKind::Modify(_) | EventKind::Create(_)) {
+                watcher.reload_at = Some(now + WATCH_DEBOUNCE);
+            }
+        }
+        if watcher.reload_at.is_some_and(|at| now >= at) {
+            watcher.reload_at = None;
+            self.reload();
+        }
+    }
+
+    /// Turns follow mode on (jumping to the end) or off
+    pub fn toggle_follow(&mut self) {
+        self.follow = !self.follow;
+        if self.follow {
+            self.scroll_to_bottom();
+            self.set_message("Following the end of the file. Scroll up to stop.");
+        } else {
+            self.set_message("Stopped following the end of the file.");
+        }
+    }
+
+    /// Ends follow mode once the view is moved off the end of the file.
+    /// Called after each handled event.
+    pub fn update_follow(&mut self) {
+        if self.follow && self.scroll_offset < self.max_scroll_offset() {
+            self.follow = false;
+            self.set_message("Stopped following the end of the file.");
+        }
+    }
+
+    /// Without a watcher, follow mode checks the file's size on every pass of the
+    /// event loop and reloads it when it changes
+    pub fn poll_growth(&mut self) {
+        if !self.follow || self.watcher.is_some() || matches!(self.parsed_file, ParsedFile::Window { .. }) {
+            return;
+        }
+        if let Ok(metadata) = std::fs::metadata(&self.file_path) {
+            if metadata.len() as usize != self.file_size {
+                self.reload();
+            }
+        }
+    }
+
+    /// Re-reads the file after it changed on disk. The view stays where it was
+    /// unless the file shrank below it, or follow mode keeps it at the end.
+    pub fn reload(&mut self) {
+        if self.dirty {
+            self.set_error("Not reloading over unsaved edits.");
+            return;
+        }
+        let old_size = self.file_size;
+        // A window is a fixed range read on demand, so it only needs its caches dropped
+        if !matches!(self.parsed_file, ParsedFile::Window { .. }) {
+            let reopened = std::fs::metadata(&self.file_path)
+                .map_err(|e| e.into())
+                .and_then(|metadata| {
+                    let size = metadata.len() as usize;
+                    open_file(&self.file_path, size, self.lazy_threshold).map(|file| (file, size))
+                });
+            match reopened {
+                Ok((parsed_file, size)) => {
+                    self.parsed_file = parsed_file;
+                    self.file_size = size;
+                    self.unavailable = None;
+                }
+                Err(e) => {
+                    self.set_error(format!("Cannot reload {}: {}", self.file_path, e));
+                    return;
+                }
+            }
+        }
+        self.prefetch.clear();
+        self.uniform_byte = self.parsed_file.uniform_byte(self.file_size);
+        self.file_format = detect_format(&self.read_bytes(0, MAGIC_LEN));
+        self.report_unparsed_format();
+        self.overview_cache = None;
+        self.stats_cache = None;
+        self.clamp_scroll_offset();
+        self.cursor = usize::min(self.cursor, self.file_size.saturating_sub(1));
+        self.selection = self
+            .selection
+            .take()
+            .map(|range| range.start.min(self.file_size)..range.end.min(self.file_size))
+            .filter(|range| !range.is_empty());
+        self.refresh_search(old_size);
+        if let Err(e) = self.diff_files() {
+            self.set_error(format!("Cannot compare after reload: {}", e));
+        }
+        if self.follow {
+            self.scroll_offset = self.max_scroll_offset();
+        }
+        self.invalidate_view();
+    }
+
+    /// Opens a second file to show beside this one and finds where the two differ
+    pub fn open_second_file(&mut self, path: String, lazy_threshold: usize) -> Result<(), Box<dyn Error>> {
+        let size = std::fs::metadata(&path)?.len() as usize;
+        let parsed_file = open_file(&path, size, lazy_threshold)?;
+        self.second_file = Some(SecondFile { path, parsed_file, size });
+        self.diff_files()?;
+        Ok(())
+    }
+
+    /// Compares the two files a chunk at a time, recording the runs of differing
+    /// bytes. Where one file is longer, its extra bytes all count as differing.
+    fn diff_files(&mut self) -> io::Result<()> {
+        let Some(second) = &mut self.second_file else {
+            return Ok(());
+        };
+        let longest = usize::max(self.file_size, second.size);
+        let mut diffs: Vec<Range<usize>> = Vec::new();
+        let mut pos = 0;
+        while pos < longest {
+            let a = self.parsed_file.read_range(pos, DIFF_CHUNK_LEN)?;
+            let b = second.parsed_file.read_range(pos, DIFF_CHUNK_LEN)?;
+            if a.is_empty() && b.is_empty() {
+                break; // Both files ended early, e.g. truncated since they were sized
+            }
+            for range in diff_ranges(&a, &b) {
+                let range = pos + range.start..pos + range.end;
+                // Join runs that continue across a chunk boundary
+                match diffs.last_mut() {
+                    Some(last) if last.end == range.start => last.end = range.end,
+                    _ => diffs.push(range),
+                }
+            }
+            pos += usize::max(a.len(), b.len());
+        }
+        self.file_diffs = diffs;
+        self.invalidate_view();
+        Ok(())
+    }
+
+    /// Reads the second file's bytes for `lines` lines from `top_line`
+    pub fn second_file_data(&mut self, top_line: usize, lines: usize) -> Vec<u8> {
+        let Some(second) = &mut self.second_file else {
+            return Vec::new();
+        };
+        match second.parsed_file.read_range(top_line * self.bytes_per_line, lines * self.bytes_per_line) {
+            Ok(data) => data,
+            Err(e) => {
+                let message = format!("Cannot read {}: {}", second.path, e);
+                self.set_error(message);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Switches between showing the second file beside this one and overlaying it:
+    /// each byte that differs is shown with the second file's after it, as `de→ad`.
+    /// Bytes past the second file's end show `→--`; its bytes past this file's end
+    /// have no cell to go in, so the message says how many there are.
+    pub fn toggle_overlay(&mut self) {
+        let Some(second) = &self.second_file else {
+            self.set_error("No second file to overlay; pass one on the command line".to_string());
+            return;
+        };
+        let second_size = second.size;
+        self.overlay = !self.overlay;
+        let message = if !self.overlay {
+            "Second file shown beside this one".to_string()
+        } else if second_size > self.file_size {
+            format!("Second file overlaid; its last {} bytes are past this file's end", second_size - self.file_size)
+        } else if second_size < self.file_size {
+            format!("Second file overlaid; bytes from 0x{} on show --, past its end", self.hex(second_size, 1))
+        } else {
+            "Second file overlaid".to_string()
+        };
+        self.set_message(message);
+        self.invalidate_view();
+    }
+
+    /// Returns the differing bytes to highlight: those of a clipboard or typed
+    /// comparison while one is shown, otherwise those between the two files
+    pub fn shown_diffs(&self) -> &[Range<usize>] {
+        if self.diff_ranges.is_empty() {
+            &self.file_diffs
+        } else {
+            &self.diff_ranges
+        }
+    }
+
+    /// Moves the cursor to the next differing region after it, wrapping to the first
+    pub fn next_diff(&mut self) {
+        let index = match self.current_diff {
+            // Step past the region the cursor was put on, even one past the end of this file
+            Some(i) if self.diff_cursor(i) == Some(self.cursor) => i + 1,
+            _ => self.shown_diffs().partition_point(|range| range.start <= self.cursor),
+        };
+        self.go_to_diff(index);
+    }
+
+    /// Moves the cursor to the previous differing region before it, wrapping to the last
+    pub fn prev_diff(&mut self) {
+        let index = match self.current_diff {
+            Some(i) if self.diff_cursor(i) == Some(self.cursor) => i.checked_sub(1),
+            _ => self.shown_diffs().partition_point(|range| range.start < self.cursor).checked_sub(1),
+        };
+        self.go_to_diff(index.unwrap_or(self.shown_diffs().len().saturating_sub(1)));
+    }
+
+    /// Returns where the cursor goes for a differing region: its first byte, or the
+    /// last byte of this file for a region only the longer second file has
+    fn diff_cursor(&self, index: usize) -> Option<usize> {
+        let range = self.shown_diffs().get(index)?;
+        Some(usize::min(range.start, self.file_size.saturating_sub(1)))
+    }
+
+    /// Puts the cursor on a differing region, scrolling its line to the top unless it
+    /// is already on screen. An index past the end wraps to the first.
+    fn go_to_diff(&mut self, index: usize) {
+        let count = self.shown_diffs().len();
+        if count == 0 {
+            self.set_message("No differences.");
+            return;
+        }
+        let index = index % count;
+        let range = self.shown_diffs()[index].clone();
+        let cursor = self.diff_cursor(index).unwrap_or(0);
+        if self.filter_lines.is_some() || !self.visible_range().contains(&cursor) {
+            self.jump_to_line(cursor / self.bytes_per_line);
+        }
+        self.cursor = cursor;
+        self.current_diff = Some(index);
+        self.invalidate_view();
+        self.set_message(format!(
+            "Difference {}/{} at 0x{} ({} byte(s))",
+            index + 1,
+            count,
+            self.hex(range.start, 1),
+            range.len()
+        ));
+    }
+
+    /// Switches the selection between a run of bytes and a block of columns
+    pub fn toggle_block_mode(&mut self) {
+        self.block_mode = !self.block_mode;
+        self.invalidate_view();
+        if self.block_mode {
+            self.set_message("Block selection: shift+arrows select the same columns on each line.");
+        } else {
+            self.set_message("Linear selection.");
+        }
+    }
+
+    /// Returns the selection as a block, when block mode is on
+    pub fn block_selection(&self) -> Option<BlockSelection> {
+        let range = self.selection.as_ref().filter(|_| self.block_mode)?;
+        Some(BlockSelection::from_range(range, self.bytes_per_line))
+    }
+
+    /// Returns the selected bytes as file ranges in order: one per line of a block,
+    /// the selection itself, or the byte under the cursor if nothing is selected
+    fn selected_ranges(&self) -> Vec<Range<usize>> {
+        match self.block_selection() {
+            Some(block) => block
+                .row_ranges(self.bytes_per_line)
+                .map(|range| range.start.min(self.file_size)..range.end.min(self.file_size))
+                .filter(|range| !range.is_empty())
+                .collect(),
+            None => vec![self.selection.clone().unwrap_or(self.cursor..self.cursor + 1)],
+        }
+    }
+
+    /// Reads the bytes of `ranges` one after another
+    fn read_ranges(&mut self, ranges: &[Range<usize>]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for range in ranges {
+            bytes.extend(self.read_bytes(range.start, range.len()));
+        }
+        bytes
+    }
+
+    /// Clears the selection
+    pub fn clear_selection(&mut self) {
+        if self.selection.take().is_some() {
+            self.invalidate_view();
+        }
+    }
+
+    /// Returns the file offset of the byte drawn at a screen position in the hex or
+    /// ASCII column of the last frame. Only the plain hex view maps; the filter and
+    /// text views lay out their rows differently.
+    pub fn byte_at(&self, column: u16, row: u16) -> Option<usize> {
+        if self.ruler_rows() == 0 {
+            return None; // Not showing the hex dump
+        }
+        // The dump is drawn inside a border, below the ruler
+        let inner = Rect {
+            x: self.content_area.x + 1,
+            y: self.content_area.y + 2,
+            width: self.content_area.width.saturating_sub(2),
+            height: self.content_area.height.saturating_sub(3),
+        };
+        if !inner.contains((column, row).into()) {
+            return None;
+        }
+        let line = self.scroll_offset + (row - inner.y) as usize;
+        let start = line * self.bytes_per_line;
+        if start >= self.file_size {
+            return None;
+        }
+        let len = usize::min(self.bytes_per_line, self.file_size - start);
+        // Columns are counted from the start of the line, after the alignment margin
+        let x = ((column - inner.x) as usize).checked_sub(self.content_margin)?;
+        let options = self.display_options();
+        let hex_start = format_address(start, options).len() + 2; // Address, ':' and a space
+        let ascii_start = hex_start + options.hex_width(self.bytes_per_line) + 2;
+        // ASCII cells are one column wide except for escapes and compacted runs
+        let ascii_mappable = self.ascii_render != AsciiRender::Escape && !self.compact_runs;
+        let index = if (hex_start..hex_start + options.hex_width(len)).contains(&x) {
+            // Count the cells that start at or before the column; a group separator
+            // maps to the byte before it
+            (1..len).take_while(|&i| hex_start + options.hex_width(i) + usize::from(options.group_break(i)) <= x).count()
+        } else if ascii_mappable && (ascii_start..ascii_start + len).contains(&x) {
+            x - ascii_start
+        } else {
+            return None;
+        };
+        // A reversed line is drawn last byte first
+        let index = if self.reversed_line == Some(line) { len - 1 - index } else { index };
+        Some(start + index)
+    }
+
+    /// Scrolls the least distance that puts the cursor's line on screen
+    fn scroll_to_cursor(&mut self) {
+        let line = self.cursor / self.bytes_per_line;
+        let rows = self.last_visible_height.max(1);
+        if line < self.scroll_offset {
+            self.scroll_offset = line;
+        } else if line >= self.scroll_offset + rows {
+            self.scroll_offset = usize::min(line + 1 - rows, self.max_scroll_offset());
+        }
+    }
+
+    /// Returns the lines one page holds: the content rows shown in the last frame, less
+    /// any filter view separators
+    fn page_len(&self) -> usize {
+        match self.filter_lines {
+            Some(_) => self.filter_lines_on_screen(self.last_visible_height).len().max(1),
+            None => self.last_visible_height.max(1),
+        }
+    }
+
+    /// Scrolls up by one page
+    pub fn page_up(&mut self) {
+        self.scroll_offset = self.scroll_offset.saturating_sub(self.page_len());
+    }
+
+    /// Scrolls down by one page, stopping at the last line
+    pub fn page_down(&mut self) {
+        let page = self.page_len();
+        self.scroll_offset = usize::min(self.scroll_offset + page, self.max_scroll_offset());
+    }
+
+    /// Jumps to the first line (of the filter view, when it is active)
+    pub fn scroll_to_top(&mut self) {
+        if self.scroll_offset != 0 {
+            self.record_jump();
+            self.scroll_offset = 0;
+        }
+    }
+
+    /// Jumps to the last line (of the filter view, when it is active)
+    pub fn scroll_to_bottom(&mut self) {
+        let last = self.max_scroll_offset();
+        if self.scroll_offset != last {
+            self.record_jump();
+            self.scroll_offset = last;
+        }
+    }
+
+    /// Returns how many lines the next scroll event moves. With acceleration on,
+    /// events arriving in quick succession (a held key) step 1, then 2, then 4 lines;
+    /// a pause resets the step to 1.
+    fn scroll_step(&mut self) -> usize {
+        if !self.scroll_acceleration {
+            return 1;
+        }
+        let now = Instant::now();
+        let rapid = self
+            .last_scroll_at
+            .is_some_and(|last| now.duration_since(last) <= SCROLL_REPEAT_WINDOW);
+        self.last_scroll_at = Some(now);
+        self.scroll_velocity = if rapid { self.scroll_velocity + 1 } else { 0 };
+        match self.scroll_velocity {
+            v if v < SCROLL_EVENTS_PER_STEP => 1,
+            v if v < 2 * SCROLL_EVENTS_PER_STEP => 2,
+            _ => 4,
         }
     }
 
+    /// Toggles scroll acceleration for held keys
+    pub fn toggle_scroll_acceleration(&mut self) {
+        self.scroll_acceleration = !self.scroll_acceleration;
+        self.scroll_velocity = 0;
+        self.set_message(format!(
+            "Scroll acceleration {}",
+            if self.scroll_acceleration { "on" } else { "off" }
+        ));
+    }
+
     /// Calculates the maximum scroll offset based on file size and bytes per line
     pub fn max_scroll_offset(&self) -> usize {
-        let total_lines = (self.file_size + self.bytes_per_line - 1) / self.bytes_per_line;
-        if total_lines == 0 {
-            0
-        } else {
-            total_lines.saturating_sub(1)
+        if let Some(lines) = &self.filter_lines {
+            return lines.len().saturating_sub(1);
         }
+        self.max_scroll_offset_unfiltered()
+    }
+
+    /// Returns the last file line, ignoring the filter view
+    fn max_scroll_offset_unfiltered(&self) -> usize {
+        self.file_size.div_ceil(self.bytes_per_line).saturating_sub(1)
     }
 
     /// Ensures that scroll_offset is within valid bounds
@@ -106,89 +1060,3274 @@ impl App {
         }
     }
 
-    /// Performs search based on the current search type and input buffer
-    pub fn perform_search(&mut self) {
-        self.search_results.clear();
-        if self.input_buffer.is_empty() {
-            self.message = Some("Search query cannot be empty.".to_string());
-            return;
+    /// Returns the file line shown at the top of the view, accounting for the filter view
+    pub fn top_line(&self) -> usize {
+        match &self.filter_lines {
+            Some(lines) => lines.get(self.scroll_offset).copied().unwrap_or(0),
+            None => self.scroll_offset,
         }
-        match self.search_type {
-            SearchType::Ascii => {
-                let query = self.input_buffer.clone();
-                let query_bytes = query.as_bytes();
-                let data = self.parsed_file.data();
-
-                // Use twoway for efficient searching
-                let mut pos = 0;
-                while pos + query_bytes.len() <= data.len() {
-                    if let Some(idx) = find_bytes(&data[pos..], query_bytes) {
-                        let absolute_start = pos + idx;
-                        let absolute_end = absolute_start + query_bytes.len();
-                        self.search_results.push(absolute_start..absolute_end);
-                        pos = absolute_end;
-                    } else {
-                        break;
-                    }
-                }
-            }
-            SearchType::Hex => {
-                let query = self.input_buffer.replace(" ", "");
-                if query.is_empty() {
-                    self.message = Some("Hex search query cannot be empty.".to_string());
-                    return;
-                }
-                match hex::decode(&query) {
-                    Ok(query_bytes) => {
-                        let data = self.parsed_file.data();
-
-                        // Use twoway for efficient searching
-                        let mut pos = 0;
-                        while pos + query_bytes.len() <= data.len() {
-                            if let Some(idx) = find_bytes(&data[pos..], &query_bytes) {
-                                let absolute_start = pos + idx;
-                                let absolute_end = absolute_start + query_bytes.len();
-                                self.search_results.push(absolute_start..absolute_end);
-                                pos = absolute_end;
-                            } else {
-                                break;
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        self.message = Some("Invalid hexadecimal input for search.".to_string());
-                    }
-                }
+    }
+
+    /// Returns the filter view lines that fit in `rows` screen rows from the top of the
+    /// view, where a `--` row separates lines that aren't adjacent in the file. Empty
+    /// outside the filter view.
+    pub fn filter_lines_on_screen(&self, rows: usize) -> &[usize] {
+        let Some(lines) = &self.filter_lines else {
+            return &[];
+        };
+        let lines = &lines[usize::min(self.scroll_offset, lines.len())..];
+        let mut used = 0;
+        let mut shown = 0;
+        for (i, &line) in lines.iter().enumerate() {
+            let separator = usize::from(i > 0 && lines[i - 1] + 1 != line);
+            if used + separator >= rows {
+                break;
             }
+            used += separator + 1;
+            shown += 1;
         }
+        &lines[..shown]
+    }
 
-        // Provide feedback if no matches are found
+    /// Toggles the filter view, which shows only lines containing search matches
+    pub fn toggle_filter(&mut self) {
+        if self.filter_lines.is_some() {
+            self.exit_filter();
+            return;
+        }
         if self.search_results.is_empty() {
-            self.message = Some("No matches found for the search query.".to_string());
+            self.set_message("Run a search before filtering to matching lines.");
+            return;
+        }
+
+        let last_line = self.file_size.div_ceil(self.bytes_per_line).saturating_sub(1);
+        let mut lines = Vec::new();
+        for range in &self.search_results {
+            let first = (range.start / self.bytes_per_line).saturating_sub(FILTER_CONTEXT_LINES);
+            let last = usize::min(
+                range.end.saturating_sub(1) / self.bytes_per_line + FILTER_CONTEXT_LINES,
+                last_line,
+            );
+            lines.extend(first..=last);
         }
+        lines.sort_unstable();
+        lines.dedup();
+
+        // Keep the view near where it was: start at the first filtered line at or after the top
+        let top = self.scroll_offset;
+        self.scroll_offset = lines.partition_point(|&line| line < top).min(lines.len() - 1);
+        self.filter_lines = Some(lines);
+        self.invalidate_view();
     }
 
-    /// Jumps to a specific offset provided by the user
-    pub fn jump_to_offset(&mut self) {
-        if let Ok(offset) = usize::from_str_radix(&self.input_buffer, 16) {
-            let max_offset = self.max_scroll_offset();
-            let target_line = offset / self.bytes_per_line;
-            self.scroll_offset = usize::min(target_line, max_offset);
-        } else {
-            self.message = Some("Invalid hexadecimal offset input.".to_string());
+    /// Leaves the filter view, keeping the current top line in place
+    pub fn exit_filter(&mut self) {
+        if self.filter_lines.is_some() {
+            self.scroll_offset = self.top_line();
+            self.filter_lines = None;
+            self.invalidate_view();
         }
     }
 
-    /// Toggles between Light and Dark themes
-    pub fn toggle_theme(&mut self) {
-        self.theme = match self.theme {
-            Theme::Light => Theme::Dark,
-            Theme::Dark => Theme::Light,
+    /// Toggles reverse byte order display for the line at the top of the view
+    pub fn toggle_line_reverse(&mut self) {
+        let line = self.top_line();
+        self.reversed_line = match self.reversed_line {
+            Some(reversed) if reversed == line => None,
+            _ => Some(line),
         };
+        self.invalidate_view();
     }
 
-    /// Retrieves the data to display based on the current scroll offset and visible height
-    pub fn get_display_data(&mut self, visible_height: usize) -> Vec<u8> {
-        self.parsed_file.get_chunk(self.scroll_offset, self.bytes_per_line, visible_height)
+    /// Drops the reversed-line flip once the view has moved away from that line
+    pub fn clear_stale_line_reverse(&mut self) {
+        if self.reversed_line.is_some_and(|line| line != self.top_line()) {
+            self.reversed_line = None;
+            self.invalidate_view();
+        }
+    }
+
+    /// Changes the number of bytes per line, keeping the byte at the top of the
+    /// view in place. The filter view is rebuilt for the new line width.
+    pub fn set_bytes_per_line(&mut self, bytes_per_line: usize) {
+        let bytes_per_line = bytes_per_line.max(1);
+        if bytes_per_line == self.bytes_per_line {
+            return;
+        }
+        let filtered = self.filter_lines.is_some();
+        self.exit_filter();
+        // Reuse the byte from the last change while the view hasn't moved since, so
+        // repeated changes don't drift by the rounding to a line start each time
+        let top_byte = match self.width_anchor {
+            Some((byte, scroll)) if scroll == self.scroll_offset => byte,
+            _ => self.scroll_offset * self.bytes_per_line,
+        };
+        self.bytes_per_line = bytes_per_line;
+        self.scroll_offset = usize::min(top_byte / bytes_per_line, self.max_scroll_offset());
+        self.width_anchor = Some((top_byte, self.scroll_offset));
+        self.reversed_line = None;
+        if filtered {
+            self.toggle_filter();
+        }
+        self.invalidate_view();
+    }
+
+    /// Steps to the next common line width (8, 16, 24, 32, 48, 64), wrapping around
+    pub fn cycle_bytes_per_line(&mut self) {
+        self.auto_width = false; // A manual width overrides fitting to the pane
+        let next = COMMON_WIDTHS
+            .into_iter()
+            .find(|&width| width > self.bytes_per_line)
+            .unwrap_or(COMMON_WIDTHS[0]);
+        self.set_bytes_per_line(next);
+        self.set_message(format!("{} bytes per line", self.bytes_per_line));
+    }
+
+    /// With auto width on, sets bytes per line to the most that fit in `width` columns.
+    /// Text records keep their record width.
+    pub fn fit_to_width(&mut self, width: usize) {
+        if !self.auto_width || self.text_mode {
+            return;
+        }
+        let fitted = fit_bytes_per_line(width, self.file_size, self.display_options());
+        self.set_bytes_per_line(fitted);
+    }
+
+    /// Widens or narrows lines by one byte within `MIN_BYTES_PER_LINE..=MAX_BYTES_PER_LINE`.
+    /// A width set outside that range on the command line can only move toward it.
+    pub fn adjust_bytes_per_line(&mut self, wider: bool) {
+        self.auto_width = false; // A manual width overrides fitting to the pane
+        let current = self.bytes_per_line;
+        let next = if wider {
+            usize::min(current + 1, MAX_BYTES_PER_LINE.max(current))
+        } else {
+            usize::max(current - 1, MIN_BYTES_PER_LINE.min(current))
+        };
+        self.set_bytes_per_line(next);
+        self.set_message(format!("{} bytes per line", self.bytes_per_line));
+    }
+
+    /// Formats a value as zero-padded hex in the configured case
+    pub fn hex(&self, value: usize, width: usize) -> String {
+        format_hex(value, width, self.uppercase_hex)
+    }
+
+    /// Toggles between lowercase and uppercase hex digits
+    pub fn toggle_uppercase_hex(&mut self) {
+        self.uppercase_hex = !self.uppercase_hex;
+        self.invalidate_view();
+    }
+
+    /// Toggles between the hex dump and the fixed-width text record view
+    pub fn toggle_text_mode(&mut self) {
+        self.text_mode = !self.text_mode;
+        self.invalidate_view();
+    }
+
+    /// Cycles the ASCII column through its alternate renderings
+    pub fn cycle_ascii_render(&mut self) {
+        self.ascii_render = self.ascii_render.next();
+        self.set_message(format!("ASCII column: {}", self.ascii_render.name()));
+        self.invalidate_view();
+    }
+
+    /// Toggles collapsing runs of a low byte in the ASCII column
+    pub fn toggle_compact_runs(&mut self) {
+        self.compact_runs = !self.compact_runs;
+        self.set_message(format!(
+            "Compact byte runs in the ASCII column {}",
+            if self.compact_runs { "on" } else { "off" }
+        ));
+        self.invalidate_view();
+    }
+
+    /// Cycles the checksum column: off, XOR, sum
+    pub fn cycle_checksum(&mut self) {
+        self.checksum = match self.checksum {
+            None => Some(LineChecksum::Xor),
+            Some(LineChecksum::Xor) => Some(LineChecksum::Sum),
+            Some(LineChecksum::Sum) => None,
+        };
+        self.set_message(format!(
+            "Checksum column: {}",
+            self.checksum.map_or("off", LineChecksum::name)
+        ));
+        self.invalidate_view();
+    }
+
+    /// Collects the display settings passed to the hex dump formatter
+    pub fn display_options(&self) -> DisplayOptions {
+        DisplayOptions {
+            printable: self.printable,
+            ascii_render: self.ascii_render,
+            reversed_line: self.reversed_line,
+            uppercase: self.uppercase_hex,
+            checksum: self.checksum,
+            compact_runs: self.compact_runs,
+            address_style: self.address_style,
+            address_width: self.address_width.unwrap_or_else(|| address_digits(self.base_address.saturating_add(self.file_size))),
+            base_address: self.base_address,
+            group_size: self.group_size,
+            radix: self.radix,
+            overlay: self.overlay && self.second_file.is_some(),
+            colors: self.theme.dump_colors(),
+        }
+    }
+
+    /// Cycles the hex column through hex, octal, decimal and binary bytes
+    pub fn cycle_radix(&mut self) {
+        self.radix = match self.radix {
+            DisplayRadix::Hex => DisplayRadix::Octal,
+            DisplayRadix::Octal => DisplayRadix::Decimal,
+            DisplayRadix::Decimal => DisplayRadix::Binary,
+            DisplayRadix::Binary => DisplayRadix::Hex,
+        };
+        self.set_message(format!("Bytes shown in {}", self.radix.name()));
+        self.invalidate_view();
+    }
+
+    /// Collects the byte ranges the hex dump highlights
+    pub fn highlights(&self) -> Highlights<'_> {
+        Highlights {
+            matches: &self.search_results,
+            groups: &self.search_groups,
+            diffs: self.shown_diffs(),
+            holes: &self.holes,
+            inspected: self.inspected.as_ref(),
+            agreements: &self.agree_ranges,
+            selection: self.selection.as_ref(),
+            block_width: self.block_mode.then_some(self.bytes_per_line),
+            cursor: (self.file_size > 0).then_some(self.cursor),
+            bookmarks: &self.bookmarks,
+            terms: &self.highlight_terms,
+            overlay: None,
+        }
+    }
+
+    /// Returns the rows the column ruler takes above the content: one over the hex dump,
+    /// none over the filter view, text records or a summary
+    pub fn ruler_rows(&self) -> usize {
+        let summarized = self.uniform_byte.is_some() && !self.show_raw;
+        usize::from(self.unavailable.is_none() && self.filter_lines.is_none() && !self.text_mode && !summarized)
+    }
+
+    /// Decodes unsigned and signed LEB128 at the top of the view, highlighting the
+    /// bytes the unsigned decode consumed
+    pub fn decode_leb128_at_top(&mut self) {
+        const MAX_LEB128_LEN: usize = 19; // ceil(128 / 7)
+        let start = self.top_line() * self.bytes_per_line;
+        let data = self.read_bytes(start, MAX_LEB128_LEN);
+        let at = self.hex(start, 1);
+        self.invalidate_view();
+        let (Some((unsigned, len)), Some((signed, _))) =
+            (decode_leb128(&data, 0, false), decode_leb128(&data, 0, true))
+        else {
+            self.inspected = None;
+            self.set_error(format!("No complete LEB128 value at 0x{} (truncated or too long).", at));
+            return;
+        };
+        self.inspected = Some(start..start + len);
+        self.set_message(format!(
+            "LEB128 at 0x{} ({} byte(s)): unsigned {}, signed {}",
+            at, len, unsigned as u128, signed
+        ));
+    }
+
+    /// Clears the highlight left by the last decode
+    pub fn clear_inspected(&mut self) {
+        if self.inspected.take().is_some() {
+            self.invalidate_view();
+        }
+    }
+
+    /// Finds the holes of a sparse file so they can be told apart from stored zeros
+    pub fn scan_holes(&mut self) {
+        let base = match &self.parsed_file {
+            ParsedFile::Window { base, .. } => *base,
+            _ => 0,
+        };
+        match File::open(&self.file_path) {
+            Ok(file) => self.holes = sparse_holes(&file, base, self.file_size as u64),
+            Err(e) => {
+                self.set_error(format!("Cannot scan for sparse holes: {}", e));
+                return;
+            }
+        }
+        self.invalidate_view();
+        let hole_bytes: usize = self.holes.iter().map(|range| range.len()).sum();
+        self.set_message(match self.holes.len() {
+            0 => "No sparse holes reported (file is fully allocated or unsupported).".to_string(),
+            n => format!("{} sparse hole(s), {} bytes, shown in gray.", n, hole_bytes),
+        });
+    }
+
+    /// Marks the cached hex dump as stale
+    pub fn invalidate_view(&mut self) {
+        self.view_version = self.view_version.wrapping_add(1);
+    }
+
+    /// Returns the cache key for the current view
+    pub fn render_key(&self, visible_height: usize) -> RenderKey {
+        RenderKey {
+            scroll_offset: self.scroll_offset,
+            bytes_per_line: self.bytes_per_line,
+            visible_height,
+            view_version: self.view_version,
+        }
+    }
+
+    /// Performs search based on the current search type and input buffer
+    pub fn perform_search(&mut self) {
+        self.exit_filter();
+        self.invalidate_view();
+        self.search_results.clear();
+        self.search_groups.clear();
+        self.current_match = None;
+        self.last_search = None;
+        if self.input_buffer.is_empty() {
+            self.set_error("Search query cannot be empty.");
+            return;
+        }
+        let query = self.input_buffer.clone();
+        if !self.search_from(self.search_type, &query, 0) {
+            return;
+        }
+        self.last_search = Some((self.search_type, query));
+
+        // Provide feedback if no matches are found
+        if self.search_results.is_empty() {
+            match self.search_alignment {
+                Some(alignment) => self.set_message(format!(
+                    "No matches found for the search query at {}-byte aligned offsets.",
+                    alignment
+                )),
+                None => self.set_message("No matches found for the search query."),
+            }
+        }
+    }
+
+    /// Searches for `query`, replacing the results that could end past `from` (all of
+    /// them when `from` is 0) with the matches found there. Regex matches have no length
+    /// bound, so a regex search always covers the whole file. Returns false after
+    /// reporting an invalid query.
+    fn search_from(&mut self, search_type: SearchType, query: &str, from: usize) -> bool {
+        // A match of `len` bytes ending past `from` starts at most `len - 1` bytes before it
+        let alignment = self.search_alignment.unwrap_or(1);
+        let start = |len: usize| from.saturating_sub(len.saturating_sub(1)) / alignment * alignment;
+        let (start, found) = match search_type {
+            SearchType::Ascii if self.case_insensitive => {
+                // Match against a lowercased copy; offsets are unchanged by ASCII folding
+                let query_bytes = query.to_ascii_lowercase().into_bytes();
+                let start = start(query_bytes.len());
+                (start, self.search_file(start, query_bytes.len(), |data, alignment| {
+                    find_all(&data.to_ascii_lowercase(), &query_bytes, alignment)
+                }))
+            }
+            SearchType::Ascii => {
+                let start = start(query.len());
+                (start, self.search_file(start, query.len(), |data, alignment| {
+                    find_all(data, query.as_bytes(), alignment)
+                }))
+            }
+            SearchType::Hex => {
+                // `??` matches any byte, e.g. `48 8b ?? ?? 00`
+                let pattern = match parse_hex_pattern(query) {
+                    Ok(pattern) if pattern.is_empty() => {
+                        self.set_error("Hex search query cannot be empty.");
+                        return false;
+                    }
+                    Ok(pattern) => pattern,
+                    Err(e) => {
+                        self.set_error(e);
+                        return false;
+                    }
+                };
+                let start = start(pattern.len());
+                (start, self.search_file(start, pattern.len(), |data, alignment| {
+                    find_pattern(data, &pattern, alignment)
+                }))
+            }
+            SearchType::Regex => {
+                // Matches have no length bound, so there's no safe overlap between chunks
+                if self.parsed_file.data().is_empty() && self.file_size > 0 {
+                    self.set_error("Regex search needs the file in memory or mapped; use ASCII or hex search.");
+                    return false;
+                }
+                let regex = match regex::bytes::RegexBuilder::new(query)
+                    .case_insensitive(self.case_insensitive)
+                    .build()
+                {
+                    Ok(regex) => regex,
+                    Err(e) => {
+                        // Parse errors span several lines; the message bar shows one
+                        let detail = e.to_string().split_whitespace().collect::<Vec<_>>().join(" ");
+                        self.set_error(format!("Invalid regex: {}", detail));
+                        return false;
+                    }
+                };
+                self.search_results.clear();
+                if !self.check_mapping() {
+                    return true;
+                }
+                let mut groups = vec![Vec::new(); regex.captures_len() - 1];
+                for captures in regex.captures_iter(self.parsed_file.data()) {
+                    let found = captures.get(0).map_or(0..0, |m| m.range());
+                    if found.is_empty() || found.start % alignment != 0 {
+                        continue;
+                    }
+                    // One range per group and match, so each group's list stays sorted
+                    for (group, ranges) in groups.iter_mut().enumerate() {
+                        if let Some(m) = captures.get(group + 1).filter(|m| !m.is_empty()) {
+                            ranges.push(m.range());
+                        }
+                    }
+                    self.search_results.push(found);
+                }
+                self.search_groups = groups;
+                return true;
+            }
+        };
+        self.search_results.retain(|range| range.start < start);
+        self.search_results.extend(found);
+        true
+    }
+
+    /// Brings the search results up to date after the file changed size on disk:
+    /// matches past a shrunken end are dropped, and bytes appended after `old_size`
+    /// are searched, so an active search keeps up with a followed or watched file
+    fn refresh_search(&mut self, old_size: usize) {
+        let Some((search_type, query)) = self.last_search.clone() else {
+            return;
+        };
+        let count = self.search_results.len();
+        let size = self.file_size;
+        self.search_results.retain(|range| range.end <= size);
+        for ranges in &mut self.search_groups {
+            ranges.retain(|range| range.end <= size);
+        }
+        if size > old_size {
+            self.search_from(search_type, &query, old_size);
+        }
+        if self.search_results.len() != count {
+            self.current_match = self.current_match.filter(|&i| i < self.search_results.len());
+            self.exit_filter();
+            self.set_message(format!("{} match(es) after the file changed", self.search_results.len()));
+        }
+    }
+
+    /// Highlights every occurrence of `pattern` in the first palette color no other
+    /// term is using. Overlapping terms show the color of the one added last.
+    pub fn add_highlight_term(&mut self, label: String, pattern: Vec<Option<u8>>) {
+        let ranges = self.search_file(0, pattern.len(), |data, alignment| find_pattern(data, &pattern, alignment));
+        let color = (0..TERM_COLORS.len())
+            .find(|&color| self.highlight_terms.iter().all(|term| term.color != color))
+            .unwrap_or(self.highlight_terms.len() % TERM_COLORS.len());
+        self.set_message(format!(
+            "Highlight {}: {} ({} match(es))",
+            self.highlight_terms.len() + 1,
+            label,
+            ranges.len()
+        ));
+        self.highlight_terms.push(HighlightTerm { label, ranges, color });
+        self.invalidate_view();
+    }
+
+    /// Removes the `number`th highlight term (counting from 1), or all of them
+    pub fn remove_highlight_term(&mut self, number: Option<usize>) {
+        match number {
+            None => {
+                self.highlight_terms.clear();
+                self.set_message("Cleared all highlight terms.");
+            }
+            Some(n) if (1..=self.highlight_terms.len()).contains(&n) => {
+                let term = self.highlight_terms.remove(n - 1);
+                self.set_message(format!("Removed highlight {}: {}", n, term.label));
+            }
+            Some(n) => {
+                self.set_error(format!("No highlight term {}; there are {}.", n, self.highlight_terms.len()));
+                return;
+            }
+        }
+        self.invalidate_view();
+    }
+
+    /// Schedules a search of the query being typed for when typing pauses
+    pub fn search_query_edited(&mut self) {
+        self.search_at = Some(Instant::now() + INCREMENTAL_SEARCH_DELAY);
+    }
+
+    /// Searches the query typed so far once typing has paused for `INCREMENTAL_SEARCH_DELAY`,
+    /// so matches update while typing. Called on every pass of the event loop. Very short or
+    /// half-typed queries are skipped, as are files read on demand or too large to search
+    /// between keystrokes; those wait for Enter.
+    pub fn run_incremental_search(&mut self) {
+        if !matches!(self.mode, AppMode::Search) {
+            self.search_at = None;
+            return;
+        }
+        if self.search_at.is_none_or(|at| Instant::now() < at) {
+            return;
+        }
+        self.search_at = None;
+        if matches!(self.parsed_file, ParsedFile::Lazy(_) | ParsedFile::Window { .. })
+            || self.file_size > INCREMENTAL_SEARCH_MAX_SIZE
+            || self.input_buffer.chars().count() < INCREMENTAL_SEARCH_MIN_LEN
+        {
+            return;
+        }
+        let complete = match self.search_type {
+            SearchType::Ascii => true,
+            SearchType::Hex => parse_hex_pattern(&self.input_buffer).is_ok(),
+            SearchType::Regex => regex::bytes::Regex::new(&self.input_buffer).is_ok(),
+        };
+        if complete {
+            self.perform_search();
+            if !self.search_results.is_empty() {
+                self.set_message(format!("{} match(es)", self.search_results.len()));
+            }
+        }
+    }
+
+    /// Overwrites every occurrence of `find` in the in-memory bytes with `replace`, which
+    /// must be the same length; a `None` in `replace` keeps that byte. Matches are found as
+    /// a search would, honoring the search alignment. The file on disk is left as it is.
+    pub fn replace_all(&mut self, find: &[Option<u8>], replace: &[Option<u8>]) {
+        if find.len() != replace.len() {
+            self.set_error(format!(
+                "Find and replace lengths differ ({} vs {} bytes); replacing only overwrites.",
+                find.len(),
+                replace.len()
+            ));
+            return;
+        }
+        if self.parsed_file.data_mut().is_none() {
+            self.set_error("Only files read into memory can be edited.");
+            return;
+        }
+        let ranges = self.search_file(0, find.len(), |data, alignment| find_pattern(data, find, alignment));
+        if let Some(data) = self.parsed_file.data_mut() {
+            for range in &ranges {
+                for (byte, new) in data[range.clone()].iter_mut().zip(replace) {
+                    if let Some(new) = new {
+                        *byte = *new;
+                    }
+                }
+            }
+        }
+        if ranges.is_empty() {
+            self.set_message("Nothing to replace; the pattern wasn't found.");
+        } else {
+            self.bytes_modified();
+            self.set_message(format!("Replaced {} occurrence(s).", ranges.len()));
+        }
+    }
+
+    /// Returns whether the file is held in memory, so it can be edited
+    pub fn editable(&self) -> bool {
+        !matches!(self.parsed_file, ParsedFile::Lazy(_) | ParsedFile::Mmap { .. } | ParsedFile::Window { .. })
+    }
+
+    /// Enters edit mode at the cursor, if the file is held in memory
+    pub fn start_edit(&mut self) {
+        if !self.editable() {
+            self.set_error("Only files read into memory can be edited; this one is read on demand.");
+            return;
+        }
+        if self.file_size == 0 {
+            self.set_message("Nothing to edit in an empty file.");
+            return;
+        }
+        self.exit_filter();
+        self.selection = None;
+        if !self.visible_range().contains(&self.cursor) {
+            self.cursor = usize::min(self.top_line() * self.bytes_per_line, self.file_size - 1);
+        }
+        self.edit_low_nibble = false;
+        self.mode = AppMode::Edit;
+        self.invalidate_view();
+    }
+
+    /// Switches edit mode between typing hex digits and characters
+    pub fn toggle_edit_pane(&mut self) {
+        self.edit_ascii = !self.edit_ascii;
+        self.edit_low_nibble = false;
+    }
+
+    /// Types a character in edit mode. In the hex pane a hex digit overwrites the cursor
+    /// byte's high nibble, then its low one; in the ASCII pane an ASCII character overwrites
+    /// the whole byte. The cursor moves on once the byte is complete.
+    pub fn edit_char(&mut self, c: char) {
+        let cursor = self.cursor;
+        let Some(byte) = self.parsed_file.data_mut().and_then(|data| data.get_mut(cursor)) else {
+            return;
+        };
+        if self.edit_ascii {
+            if !c.is_ascii() {
+                return;
+            }
+            *byte = c as u8;
+        } else {
+            let Some(digit) = c.to_digit(16) else {
+                return;
+            };
+            *byte = if self.edit_low_nibble {
+                (*byte & 0xF0) | digit as u8
+            } else {
+                (*byte & 0x0F) | (digit as u8) << 4
+            };
+            self.edit_low_nibble = !self.edit_low_nibble;
+        }
+        self.bytes_modified();
+        if !self.edit_low_nibble {
+            self.move_cursor(true, 1, false);
+        }
+    }
+
+    /// Writes the in-memory bytes to `path`. With backups on, a file already there is first
+    /// copied to `<path>.bak`. Returns the number of bytes written and the backup's path,
+    /// if one was made.
+    ///
+    /// The bytes go to `<path>.hexsave` first, which is then renamed over `path`, so a
+    /// failed write (disk full, killed mid-save) leaves the original file intact.
+    pub fn save(&mut self, path: &str) -> io::Result<(usize, Option<String>)> {
+        if self.parsed_file.data_mut().is_none() {
+            return Err(io::Error::other("only files read into memory can be saved"));
+        }
+        let backup = (self.backup_on_save && Path::new(path).exists()).then(|| format!("{}{}", path, BACKUP_SUFFIX));
+        if let Some(backup) = &backup {
+            std::fs::copy(path, backup)?;
+        }
+        let len = self.write_data(path)?;
+        self.dirty = false;
+        self.last_edit_at = None;
+        // Edits kept by --autosave are in the file now
+        if let Some(autosave) = self.autosave_path().filter(|autosave| Path::new(autosave).exists()) {
+            let _ = std::fs::remove_file(autosave);
+        }
+        Ok((len, backup))
+    }
+
+    /// Writes the in-memory bytes to `<path>.hexsave`, then renames that over `path`.
+    /// Returns the number of bytes written.
+    fn write_data(&self, path: &str) -> io::Result<usize> {
+        let data = self.parsed_file.data();
+        let temp = format!("{}{}", path, SAVE_TEMP_SUFFIX);
+        let written = (|| {
+            let mut file = File::create(&temp)?;
+            file.write_all(data)?;
+            // The replacement keeps the permissions of the file it replaces
+            if let Ok(metadata) = std::fs::metadata(path) {
+                file.set_permissions(metadata.permissions())?;
+            }
+            file.sync_all()?;
+            std::fs::rename(&temp, path)
+        })();
+        if let Err(e) = written {
+            let _ = std::fs::remove_file(&temp);
+            return Err(e);
+        }
+        Ok(data.len())
+    }
+
+    /// Returns the file unsaved edits are auto-saved to, or None for stdin
+    pub fn autosave_path(&self) -> Option<String> {
+        (self.file_path != STDIN_NAME).then(|| format!("{}{}", self.file_path, AUTOSAVE_SUFFIX))
+    }
+
+    /// With --autosave, copies unsaved edits to `<file>.autosave` once no edit has been
+    /// made for `autosave_after`, leaving the file itself untouched until `:w`.
+    /// Called on every pass of the event loop.
+    pub fn poll_autosave(&mut self) {
+        let (Some(idle), Some(edited)) = (self.autosave_after, self.last_edit_at) else {
+            return;
+        };
+        if !self.dirty || edited.elapsed() < idle {
+            return;
+        }
+        self.last_edit_at = None; // One copy per burst of edits
+        let Some(path) = self.autosave_path() else {
+            return;
+        };
+        match self.write_data(&path) {
+            Ok(len) => self.set_message(format!("Auto-saved edits ({} bytes) to {}; :w writes the file.", len, path)),
+            Err(e) => self.set_error(format!("Cannot auto-save to {}: {}", path, e)),
+        }
+    }
+
+    /// Marks the file as edited and drops everything derived from the old bytes
+    fn bytes_modified(&mut self) {
+        self.dirty = true;
+        self.last_edit_at = Some(Instant::now());
+        self.uniform_byte = self.parsed_file.uniform_byte(self.file_size);
+        self.overview_cache = None;
+        self.stats_cache = None;
+        self.invalidate_view();
+    }
+
+    /// Toggles case-insensitive ASCII search
+    pub fn toggle_case_insensitive(&mut self) {
+        self.case_insensitive = !self.case_insensitive;
+    }
+
+    /// Moves to the next search match after the top of the view, wrapping to the first
+    pub fn next_match(&mut self) {
+        let top_byte = self.top_line() * self.bytes_per_line;
+        let index = match self.current_match {
+            // Step past a match already on the top line, so repeated presses advance
+            Some(i) if self.match_line(i) == Some(self.top_line()) => i + 1,
+            _ => self.search_results.partition_point(|range| range.start <= top_byte),
+        };
+        self.go_to_match(index);
+    }
+
+    /// Moves to the previous search match before the top of the view, wrapping to the last
+    pub fn prev_match(&mut self) {
+        let top_byte = self.top_line() * self.bytes_per_line;
+        let index = match self.current_match {
+            Some(i) if self.match_line(i) == Some(self.top_line()) => i.checked_sub(1),
+            _ => self.search_results.partition_point(|range| range.start < top_byte).checked_sub(1),
+        };
+        // Before the first match, wrap to the last
+        self.go_to_match(index.unwrap_or(self.search_results.len().saturating_sub(1)));
+    }
+
+    /// Returns the file line holding the start of a match
+    fn match_line(&self, index: usize) -> Option<usize> {
+        self.search_results.get(index).map(|range| range.start / self.bytes_per_line)
+    }
+
+    /// Scrolls so the match is on the top line. An index past the end wraps to the first.
+    fn go_to_match(&mut self, index: usize) {
+        let count = self.search_results.len();
+        if count == 0 {
+            self.set_message("No search matches. Press '/' to search.");
+            return;
+        }
+        let index = index % count;
+        let line = self.search_results[index].start / self.bytes_per_line;
+        // In the filter view, scroll to the filtered row showing that line
+        let target = match &self.filter_lines {
+            Some(lines) => lines.partition_point(|&l| l < line),
+            None => line,
+        };
+        let target = usize::min(target, self.max_scroll_offset());
+        if target != self.scroll_offset {
+            self.record_jump();
+            self.scroll_offset = target;
+        }
+        self.current_match = Some(index);
+        self.set_message(format!("Match {}/{}", index + 1, count));
+    }
+
+    /// Finds every occurrence of `needle` in the file, honoring the search alignment
+    fn find(&mut self, needle: &[u8]) -> Vec<Range<usize>> {
+        self.search_file(0, needle.len(), |data, alignment| find_all(data, needle, alignment))
+    }
+
+    /// Runs `matcher` over the file from `start` (an aligned offset) to the end, passing it
+    /// the search alignment. In-memory and mapped files are searched in one pass; files
+    /// read on demand are read in chunks that overlap by `match_len - 1` bytes, so matches
+    /// straddling a chunk boundary are still found.
+    fn search_file(
+        &mut self,
+        start: usize,
+        match_len: usize,
+        matcher: impl Fn(&[u8], usize) -> Vec<Range<usize>>,
+    ) -> Vec<Range<usize>> {
+        let alignment = self.search_alignment.unwrap_or(1);
+        if !matches!(self.parsed_file, ParsedFile::Lazy(_) | ParsedFile::Window { .. }) {
+            if !self.check_mapping() {
+                return Vec::new();
+            }
+            let data = self.parsed_file.data();
+            let start = start.min(data.len());
+            return matcher(&data[start..], alignment)
+                .into_iter()
+                .map(|range| range.start + start..range.end + start)
+                .collect();
+        }
+        let mut results = Vec::new();
+        // Chunks start at aligned offsets so alignment holds within each chunk
+        let mut pos = start;
+        while pos < self.file_size && self.unavailable.is_none() {
+            let chunk_end = pos + SEARCH_CHUNK_LEN;
+            let chunk = self.read_bytes(pos, SEARCH_CHUNK_LEN + match_len.saturating_sub(1));
+            // Matches starting in the overlap are left to the next chunk
+            let found = matcher(&chunk, alignment)
+                .into_iter()
+                .map(|range| range.start + pos..range.end + pos)
+                .filter(|range| range.start < chunk_end);
+            results.extend(found);
+            // Resume after the last match so matches never overlap, as in a single pass
+            let resume = results.last().map_or(0, |range: &Range<usize>| range.end);
+            pos = usize::max(chunk_end, resume).next_multiple_of(alignment);
+        }
+        results
+    }
+
+    /// Jumps to a specific offset provided by the user.
+    /// Offsets are decimal, or hex with a `0x` prefix; a leading `+` or `-` moves
+    /// relative to the top of the view (clamped at 0). A leading `L` selects a
+    /// 0-based display line instead of a byte offset, and `SSSS:OOOO` is a
+    /// real-mode segment:offset address (hex).
+    /// Other input is looked up as a symbol from the map file; a leading `@`
+    /// forces a symbol lookup (for names that look like numbers).
+    pub fn jump_to_offset(&mut self) {
+        self.exit_filter();
+        let input = self.input_buffer.trim().to_string();
+        let line = input.strip_prefix(['L', 'l']).and_then(|line| line.parse::<usize>().ok());
+        let top_byte = self.top_line() * self.bytes_per_line;
+
+        let offset = if let Some(name) = input.strip_prefix('@') {
+            self.resolve_symbol(name)
+        } else if let Some(percent) = input.strip_suffix('%') {
+            // A fraction of the file; anything past 100% is the end
+            match percent.trim().parse::<f64>() {
+                Ok(percent) if percent >= 0.0 => Ok((self.file_size as f64 * percent.min(100.0) / 100.0) as usize),
+                Ok(_) => Err("A percentage can't be negative.".to_string()),
+                Err(_) => Err("Invalid percentage (e.g. 50%).".to_string()),
+            }
+        } else if let Some(line) = line {
+            self.jump_to_line(line);
+            return;
+        } else if let Some(Ok(delta)) = input.strip_prefix('+').map(parse_number) {
+            Ok(top_byte.saturating_add(delta as usize))
+        } else if let Some(Ok(delta)) = input.strip_prefix('-').map(parse_number) {
+            Ok(top_byte.saturating_sub(delta as usize))
+        } else if let Some(address) = self.parse_address(&input).or_else(|| parse_segmented(&input)) {
+            // Absolute addresses count from the base address, like the gutter
+            address.checked_sub(self.base_address).ok_or_else(|| {
+                format!(
+                    "Address 0x{} lies below the base address 0x{}.",
+                    self.hex(address, 1),
+                    self.hex(self.base_address, 1)
+                )
+            })
+        } else if !self.symbols.is_empty() {
+            self.resolve_symbol(&input)
+        } else if input.starts_with(['L', 'l']) {
+            Err("Invalid line number input.".to_string())
+        } else {
+            Err("Invalid offset input (decimal, 0x-prefixed hex, or +/- relative).".to_string())
+        };
+
+        match offset {
+            Ok(offset) => {
+                self.jump_to_line(offset / self.bytes_per_line);
+                self.cursor = usize::min(offset, self.file_size.saturating_sub(1));
+                self.invalidate_view();
+            }
+            Err(message) => self.set_error(message),
+        }
+    }
+
+    /// Parses an absolute Goto address: decimal or `0x` hex, except that with a base
+    /// address set bare digits are hex, as the gutter shows them (`400010` under
+    /// `--base 400000` is offset 0x10)
+    fn parse_address(&self, input: &str) -> Option<usize> {
+        if self.base_address != 0 && !input.is_empty() && input.chars().all(|c| c.is_ascii_hexdigit()) {
+            return usize::from_str_radix(input, 16).ok();
+        }
+        parse_number(input).ok().map(|n| n as usize)
+    }
+
+    /// Moves the view to a file line as a discrete jump, recording the previous
+    /// position for Back. Leaves the filter view.
+    pub fn jump_to_line(&mut self, line: usize) {
+        self.exit_filter();
+        let target = usize::min(line, self.max_scroll_offset());
+        if target != self.scroll_offset {
+            self.record_jump();
+        }
+        self.scroll_offset = target;
+    }
+
+    /// Jumps to a random line, for sampling different regions of an unknown file
+    pub fn jump_to_random(&mut self) {
+        let line = self.rng.random_range(0..=self.max_scroll_offset_unfiltered());
+        self.jump_to_line(line);
+        let offset = self.hex(self.scroll_offset * self.bytes_per_line, 1);
+        self.set_message(format!("Random offset 0x{}", offset));
+    }
+
+    /// Pushes the current top-of-view offset onto the Back history
+    fn record_jump(&mut self) {
+        self.back_history.push(self.top_line() * self.bytes_per_line);
+        if self.back_history.len() > NAV_HISTORY_LIMIT {
+            self.back_history.remove(0);
+        }
+        self.forward_history.clear();
+    }
+
+    /// Returns to the position before the last jump
+    pub fn navigate_back(&mut self) {
+        let Some(offset) = self.back_history.pop() else {
+            self.set_message("No earlier position in the navigation history.");
+            return;
+        };
+        self.forward_history.push(self.top_line() * self.bytes_per_line);
+        self.exit_filter();
+        self.scroll_offset = usize::min(offset / self.bytes_per_line, self.max_scroll_offset());
+    }
+
+    /// Re-applies the last jump undone by Back
+    pub fn navigate_forward(&mut self) {
+        let Some(offset) = self.forward_history.pop() else {
+            self.set_message("No later position in the navigation history.");
+            return;
+        };
+        self.back_history.push(self.top_line() * self.bytes_per_line);
+        self.exit_filter();
+        self.scroll_offset = usize::min(offset / self.bytes_per_line, self.max_scroll_offset());
+    }
+
+    /// Resolves a symbol from the map file to a file offset
+    fn resolve_symbol(&self, name: &str) -> Result<usize, String> {
+        if self.symbols.is_empty() {
+            return Err("No symbol map loaded (use --map).".to_string());
+        }
+        let address = *self
+            .symbols
+            .get(name)
+            .ok_or_else(|| format!("Unknown symbol '{}'.", name))?;
+        address.checked_sub(self.symbol_base).ok_or_else(|| {
+            format!(
+                "Symbol '{}' (0x{}) lies below the map base 0x{}.",
+                name,
+                self.hex(address, 1),
+                self.hex(self.symbol_base, 1)
+            )
+        })
+    }
+
+    /// Jumps to the position matching a click on the metadata bar, which spans 0–100%
+    /// of the scrollable range across its inner width. Returns false if the click missed.
+    pub fn click_metadata(&mut self, column: u16, row: u16) -> bool {
+        // Only the text row inside the border is clickable
+        let inner = Rect {
+            x: self.metadata_area.x + 1,
+            y: self.metadata_area.y + 1,
+            width: self.metadata_area.width.saturating_sub(2),
+            height: self.metadata_area.height.saturating_sub(2),
+        };
+        if inner.width == 0 || !inner.contains((column, row).into()) {
+            return false;
+        }
+        let fraction = if inner.width == 1 {
+            0.0
+        } else {
+            (column - inner.x) as f64 / (inner.width - 1) as f64
+        };
+        let target = (fraction * self.max_scroll_offset() as f64).round() as usize;
+        if target != self.scroll_offset {
+            self.record_jump();
+        }
+        self.scroll_offset = target;
+        true
+    }
+
+    /// Toggles the overview pane
+    pub fn toggle_overview(&mut self) {
+        self.show_overview = !self.show_overview;
+    }
+
+    /// Bytes summarized by each of `rows` overview rows
+    pub fn overview_bucket_size(&self, rows: usize) -> usize {
+        self.file_size.div_ceil(rows.max(1)).max(1)
+    }
+
+    /// Summarizes each of `rows` equal slices of the file by its dominant byte class
+    /// and non-zero density. Files held in memory are scanned in full; files read on
+    /// demand are sampled at the start of each slice. Rows past the end of a small
+    /// file are None.
+    pub fn overview(&mut self, rows: usize) -> Vec<Option<OverviewCell>> {
+        if let Some((cached_rows, cells)) = &self.overview_cache {
+            if *cached_rows == rows {
+                return cells.clone();
+            }
+        }
+        let bucket = self.overview_bucket_size(rows);
+        let mut cells = Vec::with_capacity(rows);
+        for row in 0..rows {
+            let start = row * bucket;
+            if start >= self.file_size {
+                cells.push(None);
+                continue;
+            }
+            let len = usize::min(bucket, self.file_size - start);
+            let cell = match &self.parsed_file {
+                // Mapped files are sampled too, so drawing the map doesn't page in the whole file
+                ParsedFile::Lazy(_) | ParsedFile::Mmap { .. } | ParsedFile::Window { .. } => {
+                    overview_cell(&self.read_bytes(start, len.min(OVERVIEW_SAMPLE_LEN)))
+                }
+                file => overview_cell(&file.data()[start..start + len]),
+            };
+            cells.push(cell);
+        }
+        self.overview_cache = Some((rows, cells.clone()));
+        cells
+    }
+
+    /// Returns the overview row under a screen position, if any
+    fn overview_row(&self, column: u16, row: u16) -> Option<usize> {
+        let inner = Rect {
+            x: self.overview_area.x + 1,
+            y: self.overview_area.y + 1,
+            width: self.overview_area.width.saturating_sub(2),
+            height: self.overview_area.height.saturating_sub(2),
+        };
+        (self.show_overview && inner.contains((column, row).into()))
+            .then(|| (row - inner.y) as usize)
+    }
+
+    /// Moves the view to the start of the clicked overview row.
+    /// Returns false if the click missed the overview.
+    pub fn click_overview(&mut self, column: u16, row: u16) -> bool {
+        let Some(index) = self.overview_row(column, row) else {
+            return false;
+        };
+        let rows = self.overview_area.height.saturating_sub(2) as usize;
+        let offset = index * self.overview_bucket_size(rows);
+        self.jump_to_line(offset / self.bytes_per_line);
+        true
+    }
+
+    /// Scrolls the view by one overview row when the wheel turns over the overview.
+    /// Returns false if the pointer isn't over the overview.
+    pub fn scroll_overview(&mut self, column: u16, row: u16, down: bool) -> bool {
+        if self.overview_row(column, row).is_none() {
+            return false;
+        }
+        let rows = self.overview_area.height.saturating_sub(2) as usize;
+        let lines = (self.overview_bucket_size(rows) / self.bytes_per_line).max(1);
+        self.exit_filter();
+        self.scroll_offset = if down {
+            usize::min(self.scroll_offset + lines, self.max_scroll_offset())
+        } else {
+            self.scroll_offset.saturating_sub(lines)
+        };
+        true
+    }
+
+    /// Toggles the statistics panel
+    pub fn toggle_stats(&mut self) {
+        self.show_stats = !self.show_stats;
+    }
+
+    /// Returns the name, file offset and size of each entry of the section list:
+    /// an executable's sections or a PNG file's chunks
+    fn section_entries(&self) -> Vec<(String, usize, usize)> {
+        let sections = self.parsed_file.sections().iter();
+        let chunks = self.parsed_file.chunks().iter();
+        sections
+            .map(|section| (section.name.clone(), section.offset, section.size))
+            .chain(chunks.map(|chunk| (chunk.name(), chunk.offset, chunk.size())))
+            .collect()
+    }
+
+    /// Opens the section (or chunk) list, starting at the entry holding the cursor
+    pub fn open_sections(&mut self) {
+        let entries = self.section_entries();
+        if entries.is_empty() {
+            self.set_error("No sections in this file (ELF, PE and PNG files only).");
+            return;
+        }
+        let cursor = self.cursor;
+        self.section_index = entries
+            .iter()
+            .position(|&(_, offset, size)| (offset..offset + size).contains(&cursor))
+            .unwrap_or(0);
+        self.mode = AppMode::Sections;
+        self.message = None;
+    }
+
+    /// Moves the section list highlight by one entry, stopping at either end
+    pub fn move_section(&mut self, down: bool) {
+        let last = self.section_entries().len().saturating_sub(1);
+        self.section_index = if down {
+            usize::min(self.section_index + 1, last)
+        } else {
+            self.section_index.saturating_sub(1)
+        };
+    }
+
+    /// Closes the section list and jumps to the start of the highlighted entry
+    pub fn jump_to_section(&mut self) {
+        self.mode = AppMode::Normal;
+        let Some((name, offset, size)) = self.section_entries().into_iter().nth(self.section_index) else {
+            return;
+        };
+        let kind = if self.parsed_file.chunks().is_empty() { "Section" } else { "Chunk" };
+        if offset >= self.file_size {
+            self.set_error(format!("{} {} lies past the end of the file.", kind, name));
+            return;
+        }
+        self.jump_to_line(offset / self.bytes_per_line);
+        self.cursor = offset;
+        self.invalidate_view();
+        self.set_message(format!("{} {} at 0x{} ({} bytes)", kind, name, self.hex(offset, 1), size));
+    }
+
+    /// Closes the section list and scopes the view to the highlighted section's bytes,
+    /// read from the file on disk: offsets count from the section's start and scrolling
+    /// stops at its end. `leave_section` returns to the whole file.
+    pub fn enter_section(&mut self) {
+        self.mode = AppMode::Normal;
+        let Some((name, offset, size)) = self.section_entries().into_iter().nth(self.section_index) else {
+            return;
+        };
+        if size == 0 {
+            self.set_error(format!("Section {} has no bytes in the file.", name));
+            return;
+        }
+        if offset >= self.file_size {
+            self.set_error(format!("Section {} lies past the end of the file.", name));
+            return;
+        }
+        if self.dirty {
+            self.set_error("Save the edits before viewing a section alone.");
+            return;
+        }
+        if self.second_file.is_some() {
+            self.set_error("Cannot view a section alone while comparing two files.");
+            return;
+        }
+        let file = match File::open(&self.file_path) {
+            Ok(file) => file,
+            Err(e) => {
+                self.set_error(format!("Cannot open {}: {}", self.file_path, e));
+                return;
+            }
+        };
+        let len = usize::min(size, self.file_size - offset);
+        self.exit_filter();
+        let window = ParsedFile::Window { file, base: offset as u64, len };
+        let (inside, outside): (Vec<_>, Vec<_>) = std::mem::take(&mut self.bookmarks)
+            .into_iter()
+            .partition(|bookmark| (offset..offset + len).contains(&bookmark.offset));
+        self.isolated = Some(IsolatedSection {
+            name: name.clone(),
+            offset,
+            parsed_file: std::mem::replace(&mut self.parsed_file, window),
+            file_size: std::mem::replace(&mut self.file_size, len),
+            base_address: std::mem::take(&mut self.base_address),
+            bookmarks: outside,
+            search_results: std::mem::take(&mut self.search_results),
+            search_groups: std::mem::take(&mut self.search_groups),
+            last_search: self.last_search.take(),
+            highlight_terms: std::mem::take(&mut self.highlight_terms),
+            holes: std::mem::take(&mut self.holes),
+            back_history: std::mem::take(&mut self.back_history),
+            forward_history: std::mem::take(&mut self.forward_history),
+        });
+        self.bookmarks = inside
+            .into_iter()
+            .map(|bookmark| Bookmark { offset: bookmark.offset - offset, ..bookmark })
+            .collect();
+        self.scroll_offset = 0;
+        self.cursor = 0;
+        self.view_changed();
+        self.set_message(format!(
+            "Viewing section {} alone (0x{}, {} bytes). Backspace returns to the whole file.",
+            name,
+            self.hex(offset, 1),
+            len
+        ));
+    }
+
+    /// Returns from a section viewed alone to the whole file, at the section's position
+    pub fn leave_section(&mut self) {
+        let Some(isolated) = self.isolated.take() else {
+            return;
+        };
+        let offset = isolated.offset;
+        let top = self.top_line() * self.bytes_per_line;
+        let cursor = self.cursor;
+        let mut bookmarks: Vec<Bookmark> = std::mem::take(&mut self.bookmarks)
+            .into_iter()
+            .map(|bookmark| Bookmark { offset: bookmark.offset + offset, ..bookmark })
+            .chain(isolated.bookmarks)
+            .collect();
+        bookmarks.sort_by_key(|bookmark| bookmark.offset);
+        self.bookmarks = bookmarks;
+        self.parsed_file = isolated.parsed_file;
+        self.file_size = isolated.file_size;
+        self.base_address = isolated.base_address;
+        self.search_results = isolated.search_results;
+        self.search_groups = isolated.search_groups;
+        self.last_search = isolated.last_search;
+        self.highlight_terms = isolated.highlight_terms;
+        self.holes = isolated.holes;
+        self.back_history = isolated.back_history;
+        self.forward_history = isolated.forward_history;
+        self.exit_filter();
+        self.view_changed();
+        // Stay on the bytes that were on screen in the section
+        self.scroll_offset = usize::min((offset + top) / self.bytes_per_line, self.max_scroll_offset());
+        self.cursor = offset + cursor;
+        self.set_message(format!("Back to the whole file from section {}.", isolated.name));
+    }
+
+    /// Returns the name and file offset of the section viewed alone, if any
+    pub fn isolated_section(&self) -> Option<(&str, usize)> {
+        self.isolated.as_ref().map(|isolated| (isolated.name.as_str(), isolated.offset))
+    }
+
+    /// Drops everything derived from the bytes on view after switching between the
+    /// whole file and a section
+    fn view_changed(&mut self) {
+        self.prefetch.clear();
+        self.uniform_byte = self.parsed_file.uniform_byte(self.file_size);
+        self.overview_cache = None;
+        self.stats_cache = None;
+        self.selection = None;
+        self.inspected = None;
+        self.diff_ranges.clear();
+        self.agree_ranges.clear();
+        self.current_match = None;
+        self.reversed_line = None;
+        self.invalidate_view();
+    }
+
+    /// Bookmarks `offset`, or renames the bookmark already there
+    pub fn add_bookmark(&mut self, offset: usize, name: Option<String>) {
+        match self.bookmarks.binary_search_by_key(&offset, |bookmark| bookmark.offset) {
+            Ok(i) => self.bookmarks[i].name = name,
+            Err(i) => self.bookmarks.insert(i, Bookmark { offset, name }),
+        }
+        self.invalidate_view();
+    }
+
+    /// Removes the bookmark at `offset`, returning whether there was one
+    pub fn remove_bookmark(&mut self, offset: usize) -> bool {
+        let Ok(i) = self.bookmarks.binary_search_by_key(&offset, |bookmark| bookmark.offset) else {
+            return false;
+        };
+        self.bookmarks.remove(i);
+        self.bookmark_index = self.bookmark_index.min(self.bookmarks.len().saturating_sub(1));
+        self.invalidate_view();
+        true
+    }
+
+    /// Returns the sidecar file the bookmarks are kept in, or None for stdin
+    pub fn bookmark_path(&self) -> Option<String> {
+        (self.file_path != STDIN_NAME).then(|| format!("{}{}", self.file_path, BOOKMARK_SUFFIX))
+    }
+
+    /// Writes the bookmarks to `path` as JSON
+    pub fn save_bookmarks(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let json = serde_json::to_string_pretty(&self.bookmarks)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Replaces the bookmarks with the ones saved in `path`
+    pub fn load_bookmarks(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut bookmarks: Vec<Bookmark> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        bookmarks.sort_by_key(|bookmark| bookmark.offset);
+        bookmarks.dedup_by_key(|bookmark| bookmark.offset);
+        self.bookmarks = bookmarks;
+        self.bookmark_index = 0;
+        self.invalidate_view();
+        Ok(())
+    }
+
+    /// Adds a bookmark at the cursor, or removes the one already there
+    pub fn toggle_bookmark(&mut self) {
+        let offset = self.cursor;
+        if self.file_size == 0 {
+            self.set_message("Nothing to bookmark in an empty file.");
+        } else if self.remove_bookmark(offset) {
+            self.set_message(format!("Removed the bookmark at 0x{}", self.hex(offset, 1)));
+        } else {
+            self.add_bookmark(offset, None);
+            self.set_message(format!("Bookmarked 0x{}", self.hex(offset, 1)));
+        }
+    }
+
+    /// Opens the bookmark list, starting at the last bookmark at or before the cursor
+    pub fn open_bookmarks(&mut self) {
+        if self.bookmarks.is_empty() {
+            self.set_message("No bookmarks yet; press 'm' to bookmark the cursor.");
+            return;
+        }
+        let cursor = self.cursor;
+        self.bookmark_index = self.bookmarks.partition_point(|bookmark| bookmark.offset <= cursor).saturating_sub(1);
+        self.mode = AppMode::Bookmarks;
+        self.message = None;
+    }
+
+    /// Moves the bookmark list highlight by one entry, stopping at either end
+    pub fn move_bookmark(&mut self, down: bool) {
+        let last = self.bookmarks.len().saturating_sub(1);
+        self.bookmark_index = if down {
+            usize::min(self.bookmark_index + 1, last)
+        } else {
+            self.bookmark_index.saturating_sub(1)
+        };
+    }
+
+    /// Removes the highlighted bookmark from the list, closing it when none remain
+    pub fn remove_listed_bookmark(&mut self) {
+        if let Some(bookmark) = self.bookmarks.get(self.bookmark_index) {
+            self.remove_bookmark(bookmark.offset);
+        }
+        if self.bookmarks.is_empty() {
+            self.mode = AppMode::Normal;
+        }
+    }
+
+    /// Closes the bookmark list and moves the cursor to the bookmark at `index`,
+    /// which stays selected in the list and the pins sidebar
+    pub fn jump_to_bookmark(&mut self, index: usize) {
+        self.mode = AppMode::Normal;
+        let Some(bookmark) = self.bookmarks.get(index) else {
+            return;
+        };
+        self.bookmark_index = index;
+        let offset = usize::min(bookmark.offset, self.file_size.saturating_sub(1));
+        let label = match &bookmark.name {
+            Some(name) => format!("Bookmark {} at 0x{}", name, self.hex(offset, 1)),
+            None => format!("Bookmark at 0x{}", self.hex(offset, 1)),
+        };
+        self.jump_to_line(offset / self.bytes_per_line);
+        self.cursor = offset;
+        self.invalidate_view();
+        self.set_message(label);
+    }
+
+    /// Shows or hides the sidebar of pinned offsets (the bookmarks)
+    pub fn toggle_pins(&mut self) {
+        self.show_pins = !self.show_pins;
+    }
+
+    /// Returns the first bookmark the pins sidebar shows: scrolled just far enough
+    /// to keep the selected one in its `rows`, like the bookmark list
+    pub fn pins_scroll(&self, rows: usize) -> usize {
+        (self.bookmark_index + 1).saturating_sub(rows.max(1))
+    }
+
+    /// Jumps to the clicked pin. Returns false if the click missed the sidebar's pins.
+    pub fn click_pins(&mut self, column: u16, row: u16) -> bool {
+        let inner = Rect {
+            x: self.pins_area.x + 1,
+            y: self.pins_area.y + 1,
+            width: self.pins_area.width.saturating_sub(2),
+            height: self.pins_area.height.saturating_sub(2),
+        };
+        if !self.show_pins || !inner.contains((column, row).into()) {
+            return false;
+        }
+        let index = self.pins_scroll(inner.height as usize) + (row - inner.y) as usize;
+        if index >= self.bookmarks.len() {
+            return false;
+        }
+        self.jump_to_bookmark(index);
+        true
+    }
+
+    /// Jumps to the first pin after the cursor, wrapping to the first one
+    pub fn next_pin(&mut self) {
+        if self.bookmarks.is_empty() {
+            self.set_message("No pins yet; press 'm' or use :mark <label> to pin the cursor.");
+            return;
+        }
+        let next = self.bookmarks.partition_point(|bookmark| bookmark.offset <= self.cursor);
+        self.jump_to_bookmark(if next == self.bookmarks.len() { 0 } else { next });
+    }
+
+    /// Toggles the byte inspector panel
+    pub fn toggle_inspector(&mut self) {
+        self.show_inspector = !self.show_inspector;
+    }
+
+    /// Reads the bytes at `offset` as each integer and float type, in the current byte order
+    pub fn inspect_at(&mut self, offset: usize) -> Inspection {
+        let data = self.read_bytes(offset, 8);
+        inspect_bytes(&data, self.endianness)
+    }
+
+    /// Flips the inspector between little- and big-endian
+    pub fn toggle_endianness(&mut self) {
+        self.endianness = match self.endianness {
+            Endianness::Little => Endianness::Big,
+            Endianness::Big => Endianness::Little,
+        };
+    }
+
+    /// Returns statistics for the visible bytes, recomputed only when the view moves
+    pub fn visible_stats(&mut self) -> (Range<usize>, RegionStats) {
+        let range = self.visible_range();
+        if let Some((cached, stats)) = &self.stats_cache {
+            if *cached == range {
+                return (range, *stats);
+            }
+        }
+        let data = match self.filter_lines {
+            // Only the lines on screen count, not those filtered out between them
+            Some(_) => {
+                let lines = self.filter_lines_on_screen(self.last_visible_height.max(1)).to_vec();
+                lines
+                    .into_iter()
+                    .flat_map(|line| self.read_bytes(line * self.bytes_per_line, self.bytes_per_line))
+                    .collect()
+            }
+            None => self.read_bytes(range.start, range.len()),
+        };
+        let stats = region_stats(&data, self.printable);
+        self.stats_cache = Some((range.clone(), stats));
+        (range, stats)
+    }
+
+    /// Returns the byte range covered by the lines currently on screen
+    pub fn visible_range(&self) -> Range<usize> {
+        let rows = self.last_visible_height.max(1);
+        let last_line = match self.filter_lines {
+            Some(_) => self.filter_lines_on_screen(rows).last().copied().unwrap_or(0),
+            None => self.scroll_offset + rows - 1,
+        };
+        let start = usize::min(self.top_line() * self.bytes_per_line, self.file_size);
+        let end = usize::min((last_line + 1) * self.bytes_per_line, self.file_size);
+        start..end
+    }
+
+    /// Returns the system clipboard, opening it on first use
+    fn clipboard(&mut self) -> Result<&mut arboard::Clipboard, arboard::Error> {
+        if self.clipboard.is_none() {
+            self.clipboard = Some(arboard::Clipboard::new()?);
+        }
+        self.clipboard.as_mut().ok_or(arboard::Error::ClipboardNotSupported)
+    }
+
+    /// Places text on the system clipboard
+    pub fn set_clipboard(&mut self, text: String) -> Result<(), arboard::Error> {
+        self.clipboard()?.set_text(text)
+    }
+
+    /// Reads the clipboard as bytes: a hex string if it parses as one, raw text otherwise
+    fn clipboard_bytes(&mut self) -> Result<Vec<u8>, String> {
+        let text = self.clipboard().and_then(|c| c.get_text()).map_err(|e| e.to_string())?;
+        let bytes = parse_clipboard_bytes(&text);
+        if bytes.is_empty() {
+            return Err("clipboard is empty".to_string());
+        }
+        Ok(bytes)
+    }
+
+    /// Searches the file for the clipboard contents, highlighting every occurrence
+    pub fn search_clipboard(&mut self) {
+        let needle = match self.clipboard_bytes() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.set_error(format!("Cannot search for clipboard contents: {}", e));
+                return;
+            }
+        };
+        self.exit_filter();
+        self.invalidate_view();
+        self.search_results = self.find(&needle);
+        self.search_groups.clear();
+        self.last_search = None;
+        self.current_match = None;
+        self.set_message(match self.search_results.len() {
+            0 => format!("Clipboard contents ({} bytes) not found in the file.", needle.len()),
+            n => format!("Found clipboard contents ({} bytes) {} time(s).", needle.len(), n),
+        });
+    }
+
+    /// Compares the clipboard contents with the file starting at the top of the view,
+    /// highlighting the bytes that differ
+    pub fn compare_clipboard(&mut self) {
+        let expected = match self.clipboard_bytes() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.set_error(format!("Cannot compare with clipboard: {}", e));
+                return;
+            }
+        };
+        let start = self.top_line() * self.bytes_per_line;
+        let actual = self.read_bytes(start, expected.len());
+
+        self.diff_ranges.clear();
+        for (i, &byte) in expected.iter().enumerate().take(actual.len()) {
+            if actual[i] != byte {
+                let offset = start + i;
+                match self.diff_ranges.last_mut() {
+                    Some(range) if range.end == offset => range.end += 1,
+                    _ => self.diff_ranges.push(offset..offset + 1),
+                }
+            }
+        }
+        self.invalidate_view();
+
+        let differing: usize = self.diff_ranges.iter().map(|range| range.len()).sum();
+        let missing = expected.len() - actual.len();
+        let at = self.hex(start, 1);
+        self.set_message(if differing == 0 && missing == 0 {
+            format!("Clipboard ({} bytes) matches the file at 0x{}.", expected.len(), at)
+        } else if missing > 0 {
+            format!(
+                "Clipboard ({} bytes) differs at {} byte(s) from 0x{}; {} byte(s) run past end of file.",
+                expected.len(), differing, at, missing
+            )
+        } else {
+            format!(
+                "Clipboard ({} bytes) differs at {} byte(s) from 0x{}.",
+                expected.len(), differing, at
+            )
+        });
+    }
+
+    /// Clears the highlighted differences from the last comparison
+    pub fn clear_diff(&mut self) {
+        if !self.diff_ranges.is_empty() || !self.agree_ranges.is_empty() {
+            self.diff_ranges.clear();
+            self.agree_ranges.clear();
+            self.invalidate_view();
+        }
+    }
+
+    /// Starts comparing a typed hex pattern against the bytes at the top of the view
+    pub fn start_live_compare(&mut self) {
+        self.exit_filter();
+        self.clear_diff();
+        self.compare_start = self.top_line() * self.bytes_per_line;
+        self.input_buffer.clear();
+        self.mode = AppMode::Compare;
+    }
+
+    /// Re-compares the pattern typed so far, marking each byte green or red.
+    /// A trailing half-typed byte is ignored and `??` bytes are not marked.
+    pub fn update_live_compare(&mut self) {
+        let mut text: String =
+            self.input_buffer.chars().filter(|c| !c.is_whitespace()).collect();
+        if !text.len().is_multiple_of(2) {
+            text.pop();
+        }
+        self.diff_ranges.clear();
+        self.agree_ranges.clear();
+        self.invalidate_view();
+        let pattern = match parse_hex_pattern(&text) {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                self.set_error(e);
+                return;
+            }
+        };
+        let actual = self.read_bytes(self.compare_start, pattern.len());
+        for (i, (expected, &byte)) in pattern.iter().zip(&actual).enumerate() {
+            let Some(expected) = expected else { continue };
+            let ranges = if *expected == byte {
+                &mut self.agree_ranges
+            } else {
+                &mut self.diff_ranges
+            };
+            let offset = self.compare_start + i;
+            match ranges.last_mut() {
+                Some(range) if range.end == offset => range.end += 1,
+                _ => ranges.push(offset..offset + 1),
+            }
+        }
+
+        let differing: usize = self.diff_ranges.iter().map(|range| range.len()).sum();
+        let missing = pattern.len() - actual.len();
+        let message = if pattern.is_empty() {
+            "Type hex bytes to compare; ?? matches any byte.".to_string()
+        } else if differing == 0 && missing == 0 {
+            format!("All {} byte(s) match.", pattern.len())
+        } else if missing > 0 {
+            format!(
+                "{} of {} byte(s) differ; {} run past end of file.",
+                differing, pattern.len(), missing
+            )
+        } else {
+            format!("{} of {} byte(s) differ.", differing, pattern.len())
+        };
+        self.set_message(message);
+    }
+
+    /// Copies the visible byte range (e.g. `0x100-0x1FF`) to the clipboard
+    pub fn copy_view_range(&mut self) {
+        let range = self.visible_range();
+        if range.is_empty() {
+            self.set_message("Nothing visible to copy.");
+            return;
+        }
+        let text = format!(
+            "0x{}-0x{}",
+            self.hex(range.start, 1),
+            self.hex(range.end - 1, 1)
+        );
+        match self.set_clipboard(text.clone()) {
+            Ok(()) => self.set_message(format!("Copied view range {}", text)),
+            Err(e) => self.set_error(format!("Clipboard unavailable ({}). View range: {}", e, text)),
+        }
+    }
+
+    /// Reads the selected bytes, or the byte under the cursor if nothing is selected,
+    /// for copying. Sets an error and returns None if there is nothing to copy.
+    fn bytes_to_copy(&mut self) -> Option<Vec<u8>> {
+        if self.file_size == 0 {
+            self.set_message("Nothing to copy in an empty file.");
+            return None;
+        }
+        let ranges = self.selected_ranges();
+        let len: usize = ranges.iter().map(Range::len).sum();
+        if len > COPY_LIMIT {
+            self.set_error(format!("Selection too large to copy ({} bytes, limit {}).", len, COPY_LIMIT));
+            return None;
+        }
+        Some(self.read_ranges(&ranges))
+    }
+
+    /// Computes a checksum over the selection, or the whole file if nothing is
+    /// selected, reading the bytes a chunk at a time
+    pub fn checksum_selection(&mut self, algo: ChecksumAlgo) -> u32 {
+        let ranges = match self.selection {
+            Some(_) => self.selected_ranges(),
+            None => std::iter::once(0..self.file_size).collect(),
+        };
+        let mut state = ChecksumState::new(algo);
+        for range in ranges {
+            let mut pos = range.start;
+            while pos < range.end && self.unavailable.is_none() {
+                state.update(&self.read_bytes(pos, usize::min(CHECKSUM_CHUNK_LEN, range.end - pos)));
+                pos += CHECKSUM_CHUNK_LEN;
+            }
+        }
+        state.finish()
+    }
+
+    /// Shows the selected bytes, or the byte under the cursor, as spaced hex in the
+    /// message bar. Selections longer than `readout_limit` are refused.
+    pub fn show_selection_bytes(&mut self) {
+        if self.file_size == 0 {
+            self.set_message("Nothing to show in an empty file.");
+            return;
+        }
+        let ranges = self.selected_ranges();
+        let len: usize = ranges.iter().map(Range::len).sum();
+        if len > self.readout_limit {
+            self.set_error(format!(
+                "Selection too large to show ({} bytes, limit {}); use y to copy or :export.",
+                len, self.readout_limit
+            ));
+            return;
+        }
+        let bytes = self.read_ranges(&ranges);
+        if self.unavailable.is_some() {
+            return;
+        }
+        let text = CopyFormat::HexSpaced.format(&bytes, self.uppercase_hex);
+        self.set_message(format!("0x{}: {}", self.hex(self.base_address + ranges[0].start, 1), text));
+    }
+
+    /// Shows the CRC32, Adler32 and sum of the selection, or of the whole file
+    pub fn show_checksums(&mut self) {
+        let algos = [ChecksumAlgo::Crc32, ChecksumAlgo::Adler32, ChecksumAlgo::Sum];
+        let values: Vec<u32> = algos.iter().map(|&algo| self.checksum_selection(algo)).collect();
+        if self.unavailable.is_some() {
+            return;
+        }
+        let scope = match &self.selection {
+            Some(_) if self.block_mode => {
+                format!("{} bytes in the selected block", self.selected_ranges().iter().map(Range::len).sum::<usize>())
+            }
+            Some(range) => format!("{} selected bytes", range.len()),
+            None => format!("the whole file ({} bytes)", self.file_size),
+        };
+        let results: Vec<String> = algos
+            .iter()
+            .zip(values)
+            .map(|(algo, value)| format!("{} 0x{}", algo.name(), self.hex(value as usize, 8)))
+            .collect();
+        self.set_message(format!("{} of {}", results.join(", "), scope));
+    }
+
+    /// Hashes the whole file, reading it a chunk at a time
+    pub fn file_digest(&mut self, algo: DigestAlgo) -> Vec<u8> {
+        let mut state = DigestState::new(algo);
+        let mut pos = 0;
+        while pos < self.file_size && self.unavailable.is_none() {
+            state.update(&self.read_bytes(pos, usize::min(CHECKSUM_CHUNK_LEN, self.file_size - pos)));
+            pos += CHECKSUM_CHUNK_LEN;
+        }
+        state.finish()
+    }
+
+    /// Shows a "hashing" message and queues the digest, so the message is drawn
+    /// before the file is read
+    pub fn start_file_digest(&mut self, algo: DigestAlgo) {
+        self.set_message(format!("Hashing {} bytes with {}...", self.file_size, algo.name()));
+        self.pending_digest = Some(algo);
+    }
+
+    /// Computes a digest queued by `start_file_digest` and shows it
+    pub fn run_pending_digest(&mut self) {
+        let Some(algo) = self.pending_digest.take() else {
+            return;
+        };
+        let digest = self.file_digest(algo);
+        if self.unavailable.is_some() {
+            return;
+        }
+        let digest: String = digest.iter().map(|&byte| self.hex(byte as usize, 2)).collect();
+        self.set_message(format!("{} of {}: {}", algo.name(), self.file_path, digest));
+    }
+
+    /// Copies the selected bytes, or the byte under the cursor if nothing is selected,
+    /// to the clipboard as text
+    pub fn copy_selection(&mut self, format: CopyFormat) {
+        let Some(bytes) = self.bytes_to_copy() else {
+            return;
+        };
+        let text = format.format(&bytes, self.uppercase_hex);
+        match self.set_clipboard(text) {
+            Ok(()) => self.set_message(format!("Copied {} bytes as {}", bytes.len(), format.name())),
+            Err(e) => self.set_error(format!("Clipboard unavailable: {}", e)),
+        }
+    }
+
+    /// Copies the selection (or cursor byte) as a C array definition named `name`
+    pub fn copy_c_array(&mut self, name: &str, bytes_per_line: usize) {
+        let Some(bytes) = self.bytes_to_copy() else {
+            return;
+        };
+        let text = format_c_array(&bytes, name, bytes_per_line, self.uppercase_hex);
+        match self.set_clipboard(text) {
+            Ok(()) => self.set_message(format!("Copied {} bytes as C array {}", bytes.len(), name)),
+            Err(e) => self.set_error(format!("Clipboard unavailable: {}", e)),
+        }
+    }
+
+    /// Runs a command typed at the goto prompt, returning false if the input is not
+    /// a command (so it is treated as an offset). Commands:
+    /// `w [file]` saves edits to the file or another one; `export <file>` writes the
+    /// selection, or the whole file, as a text dump (it was `w <file>` before saving
+    /// existed); `carray [name] [bytes/line]` copies the selection as a C array;
+    /// `sha256` and `md5` hash the whole file; `mark [name]` bookmarks the cursor.
+    pub fn run_command(&mut self) -> bool {
+        let input = self.input_buffer.trim().to_string();
+        let (command, argument) = input.split_once(' ').unwrap_or((&input, ""));
+        let argument = argument.trim();
+        match command {
+            "carray" => {
+                let mut words = argument.split_whitespace();
+                let name = words.next().unwrap_or("data");
+                match words.next().map(str::parse::<usize>) {
+                    None => self.copy_c_array(name, C_ARRAY_BYTES_PER_LINE),
+                    Some(Ok(width)) if width > 0 => self.copy_c_array(name, width),
+                    Some(_) => self.set_error("Usage: carray [name] [bytes per line]"),
+                }
+            }
+            "mark" if self.file_size == 0 => self.set_message("Nothing to bookmark in an empty file."),
+            "mark" => {
+                let name = (!argument.is_empty()).then(|| argument.to_string());
+                self.add_bookmark(self.cursor, name);
+                self.set_message(format!("Bookmarked 0x{}", self.hex(self.cursor, 1)));
+            }
+            "hl" if argument.is_empty() => match self.highlight_terms.len() {
+                0 => self.set_message("Usage: hl <text>, or hlx <hex bytes>"),
+                _ => {
+                    let terms: Vec<String> = self
+                        .highlight_terms
+                        .iter()
+                        .enumerate()
+                        .map(|(i, term)| format!("{}: {} ({})", i + 1, term.label, term.ranges.len()))
+                        .collect();
+                    self.set_message(terms.join(", "));
+                }
+            },
+            "hl" => self.add_highlight_term(format!("\"{}\"", argument), argument.bytes().map(Some).collect()),
+            "hlx" => match parse_hex_pattern(argument) {
+                Ok(pattern) if pattern.is_empty() => self.set_error("Usage: hlx <hex bytes>"),
+                Ok(pattern) => self.add_highlight_term(argument.to_string(), pattern),
+                Err(e) => self.set_error(e),
+            },
+            "unhl" if argument.is_empty() => self.remove_highlight_term(None),
+            "unhl" => match argument.parse() {
+                Ok(n) => self.remove_highlight_term(Some(n)),
+                Err(_) => self.set_error("Usage: unhl [term number]"),
+            },
+            "replace" => match argument.split_once('/') {
+                Some((find, replace)) if !find.is_empty() => {
+                    let find: Vec<Option<u8>> = find.bytes().map(Some).collect();
+                    let replace: Vec<Option<u8>> = replace.bytes().map(Some).collect();
+                    self.replace_all(&find, &replace);
+                }
+                _ => self.set_error("Usage: replace <text>/<replacement>"),
+            },
+            "replacex" => match argument
+                .split_once('/')
+                .map(|(find, replace)| (parse_hex_pattern(find), parse_hex_pattern(replace)))
+            {
+                Some((Ok(find), Ok(replace))) if !find.is_empty() => self.replace_all(&find, &replace),
+                Some((Err(e), _) | (_, Err(e))) => self.set_error(e),
+                _ => self.set_error("Usage: replacex <hex bytes>/<hex bytes>"),
+            },
+            "edit" => self.start_edit(),
+            "sha256" => self.start_file_digest(DigestAlgo::Sha256),
+            "md5" => self.start_file_digest(DigestAlgo::Md5),
+            "w" if argument.is_empty() && self.file_path == STDIN_NAME => {
+                self.set_error("Standard input has no file to save to; use :w <file>.")
+            }
+            "w" => {
+                let path = if argument.is_empty() { self.file_path.clone() } else { argument.to_string() };
+                let was_dirty = self.dirty;
+                match self.save(&path) {
+                    Ok((len, Some(backup))) => {
+                        self.set_message(format!("Wrote {} bytes to {} (previous contents in {})", len, path, backup))
+                    }
+                    // `:w <file>` used to export a text dump; point anyone expecting that at :export
+                    Ok((len, None)) if !argument.is_empty() && !was_dirty => self.set_message(format!(
+                        "Wrote a {}-byte copy of the file to {} (:export <file> writes a text dump)",
+                        len, path
+                    )),
+                    Ok((len, None)) => self.set_message(format!("Wrote {} bytes to {}", len, path)),
+                    Err(e) if !self.editable() => self.set_error(format!(
+                        "Cannot save to {}: {} (:export <file> writes a text dump)",
+                        path, e
+                    )),
+                    Err(e) => self.set_error(format!("Cannot save to {}: {}", path, e)),
+                }
+            }
+            "export" if argument.is_empty() => self.set_error("Usage: export <file>"),
+            "export" => {
+                let range = self.selection.clone().unwrap_or(0..self.file_size);
+                match self.export_dump(argument, range.clone()) {
+                    Ok(()) => self.set_message(format!("Exported {} bytes to {}", range.len(), argument)),
+                    Err(e) => self.set_error(format!("Cannot export to {}: {}", argument, e)),
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Writes `range` to `path` as a plain text hex dump in the current layout
+    pub fn export_dump(&mut self, path: &str, range: Range<usize>) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        // Exports show this file's bytes alone, without the overlay
+        let options = DisplayOptions { overlay: false, ..self.display_options() };
+        let chunk_len = EXPORT_CHUNK_LINES * self.bytes_per_line;
+        let mut pos = range.start;
+        while pos < range.end {
+            let data = self.read_bytes(pos, usize::min(chunk_len, range.end - pos));
+            if let Some(reason) = &self.unavailable {
+                return Err(io::Error::other(reason.clone()));
+            }
+            if data.is_empty() {
+                break;
+            }
+            out.write_all(format_plain_dump(&data, pos, self.bytes_per_line, options).as_bytes())?;
+            pos += data.len();
+        }
+        out.flush()
+    }
+
+    /// Cycles through the Light, Dark and (when loaded) custom themes
+    pub fn toggle_theme(&mut self) {
+        self.theme = match (&self.theme, self.custom_scheme) {
+            (Theme::Light, _) => Theme::Dark,
+            (Theme::Dark, Some(scheme)) => Theme::Custom(scheme),
+            (Theme::Dark, None) | (Theme::Custom(_), _) => Theme::Light,
+        };
+        self.invalidate_view();
+    }
+
+    /// Toggles the inverted-colors overlay, independently of the theme
+    pub fn toggle_invert(&mut self) {
+        self.invert_colors = !self.invert_colors;
+    }
+
+    /// Resolves the active theme's colors, applying the invert overlay
+    pub fn theme_colors(&self) -> ThemeColors {
+        let colors = self.theme.colors();
+        if self.invert_colors {
+            colors.inverted()
+        } else {
+            colors
+        }
+    }
+
+    /// Reads bytes from the file. An I/O error switches the app into the
+    /// unavailable state, after which no further reads are attempted.
+    pub fn read_bytes(&mut self, start: usize, len: usize) -> Vec<u8> {
+        if self.unavailable.is_some() {
+            return Vec::new();
+        }
+        let result = self.parsed_file.read_range(start, len);
+        self.take_read_result(result, start, len)
+    }
+
+    /// Unwraps a read of `len` bytes at `start`, recording a failure as the unavailable
+    /// state. A read that ends before the file size seen at open means the file shrank,
+    /// which is reported as `UnexpectedEof`.
+    fn take_read_result(&mut self, result: io::Result<Vec<u8>>, start: usize, len: usize) -> Vec<u8> {
+        let expected = usize::min(len, self.file_size.saturating_sub(start));
+        let result = result.and_then(|bytes| {
+            if bytes.len() < expected {
+                Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("file ends at {:#x}, expected {:#x}", start + bytes.len(), start + expected),
+                ))
+            } else {
+                Ok(bytes)
+            }
+        });
+        match result {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.mark_unavailable(e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Switches into the unavailable state after a failed read
+    fn mark_unavailable(&mut self, e: io::Error) {
+        let reason = format!("File became unavailable: {}", e);
+        self.set_error(reason.clone());
+        self.unavailable = Some(reason);
+        self.invalidate_view();
+    }
+
+    /// Checks that the whole file can be sliced from memory before a pass over
+    /// `ParsedFile::data`, recording a truncated mapping as the unavailable state
+    fn check_mapping(&mut self) -> bool {
+        if self.unavailable.is_some() {
+            return false;
+        }
+        match self.parsed_file.check_mapping() {
+            Ok(()) => true,
+            Err(e) => {
+                self.mark_unavailable(e);
+                false
+            }
+        }
+    }
+
+    /// Retrieves the data to display based on the current scroll offset and visible height
+    pub fn get_display_data(&mut self, visible_height: usize) -> Vec<u8> {
+        if self.unavailable.is_some() {
+            return Vec::new();
+        }
+        let (start, len) = (self.scroll_offset * self.bytes_per_line, visible_height * self.bytes_per_line);
+        let result = match self.parsed_file {
+            ParsedFile::Lazy(_) | ParsedFile::Window { .. } => {
+                self.prefetch.read(&mut self.parsed_file, start, len, self.bytes_per_line)
+            }
+            _ => self.parsed_file.get_chunk(self.scroll_offset, self.bytes_per_line, visible_height),
+        };
+        self.take_read_result(result, start, len)
+    }
+}
+
+/// Opens a whole file: read into memory, or mapped when larger than `lazy_threshold`
+fn open_file(path: &str, size: usize, lazy_threshold: usize) -> Result<ParsedFile, Box<dyn Error>> {
+    if size <= lazy_threshold {
+        return parse_file(path); // parse_file already returns ParsedFile
+    }
+    // Map large files, falling back to on-demand reads where mapping fails (pipes etc.)
+    let file = File::open(path)?;
+    // SAFETY: the map is read-only, and every read of it first checks that the file
+    // still covers the mapping (`ParsedFile::check_mapping`), so a file truncated by
+    // another process is reported as unavailable instead of faulting
+    match unsafe { Mmap::map(&file) } {
+        Ok(map) => {
+            let mut mapped = ParsedFile::Mmap { map, file, structure: Structure::default() };
+            mapped.parse_structure()?;
+            Ok(mapped)
+        }
+        Err(_) => Ok(ParsedFile::Lazy(file)),
+    }
+}
+
+/// Parses a real-mode `SSSS:OOOO` address (hex) into a linear offset. Each half is
+/// one to four hex digits, as in the address gutter.
+fn parse_segmented(input: &str) -> Option<usize> {
+    let (segment, offset) = input.split_once(':')?;
+    let word = |digits: &str| {
+        let valid = (1..=4).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_hexdigit());
+        valid.then(|| usize::from_str_radix(digits, 16).ok()).flatten()
+    };
+    word(segment)?.checked_mul(16)?.checked_add(word(offset)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::SectionInfo;
+    use crate::utils::ByteClass;
+    use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+    use tempfile::NamedTempFile;
+
+    /// Opens `data` from a temporary file, which lives as long as the returned handle
+    fn open(data: &[u8], bytes_per_line: usize) -> (NamedTempFile, App) {
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), data).unwrap();
+        let path = file.path().to_string_lossy().into_owned();
+        let app = App::new(path, bytes_per_line, Theme::Dark, PrintablePolicy::Standard, 0, None, usize::MAX).unwrap();
+        (file, app)
+    }
+
+    /// Draws one frame of the whole interface and returns the screen's text, row after row
+    fn draw(app: &mut App, width: u16, height: u16) -> String {
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(width, height)).unwrap();
+        let frame = terminal.draw(|f| crate::ui::draw_ui(f, app)).unwrap();
+        frame.buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    /// Presses a key without modifiers, as the event loop would
+    fn press(app: &mut App, code: KeyCode) {
+        crate::event::handle_event(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)), app);
+        app.clamp_scroll_offset();
+    }
+
+    /// Builds a minimal 64-bit little-endian ELF file: 16 bytes of `.text` at offset 64,
+    /// then the section name table and the section headers
+    fn tiny_elf() -> Vec<u8> {
+        let names = b"\0.text\0.shstrtab\0";
+        let section_headers = 64 + 16 + 24; // Past the names, padded to 8 bytes
+        let mut elf = Vec::new();
+        elf.extend_from_slice(b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0");
+        elf.extend_from_slice(&1u16.to_le_bytes()); // Relocatable
+        elf.extend_from_slice(&0x3eu16.to_le_bytes()); // x86-64
+        elf.extend_from_slice(&1u32.to_le_bytes());
+        elf.extend_from_slice(&0u64.to_le_bytes()); // Entry point
+        elf.extend_from_slice(&0u64.to_le_bytes()); // No program headers
+        elf.extend_from_slice(&(section_headers as u64).to_le_bytes());
+        elf.extend_from_slice(&0u32.to_le_bytes());
+        for half in [64u16, 0, 0, 64, 3, 2] {
+            elf.extend_from_slice(&half.to_le_bytes()); // Header sizes and counts, names in section 2
+        }
+        elf.extend((0..16).map(|i| 0x90 + i as u8));
+        elf.extend_from_slice(names);
+        elf.resize(section_headers, 0);
+        let section = |name: u32, kind: u32, address: u64, offset: u64, size: u64| {
+            let mut header = Vec::new();
+            header.extend_from_slice(&name.to_le_bytes());
+            header.extend_from_slice(&kind.to_le_bytes());
+            header.extend_from_slice(&0u64.to_le_bytes());
+            for field in [address, offset, size] {
+                header.extend_from_slice(&field.to_le_bytes());
+            }
+            header.extend_from_slice(&[0; 24]); // Link, info, alignment and entry size
+            header
+        };
+        elf.extend(section(0, 0, 0, 0, 0));
+        elf.extend(section(1, 1, 0x401000, 64, 16));
+        elf.extend(section(7, 3, 0, 80, names.len() as u64));
+        elf
+    }
+
+    #[test]
+    fn changing_bytes_per_line_keeps_the_first_visible_byte() {
+        let (_file, mut app) = open(&[0; 1024], 16);
+        app.scroll_offset = 10; // Byte 160 at the top
+        app.adjust_bytes_per_line(true);
+        assert_eq!(app.bytes_per_line, 17);
+        let top = app.scroll_offset * app.bytes_per_line;
+        assert!((top..top + app.bytes_per_line).contains(&160));
+        // Going back returns to the same byte rather than drifting a line
+        app.adjust_bytes_per_line(false);
+        assert_eq!(app.scroll_offset * app.bytes_per_line, 160);
+    }
+
+    #[test]
+    fn changing_bytes_per_line_clamps_the_scroll_offset() {
+        let (_file, mut app) = open(&[0; 100], 4);
+        app.scroll_offset = app.max_scroll_offset(); // Line 24, byte 96
+        app.set_bytes_per_line(64);
+        assert_eq!(app.scroll_offset, 1);
+        assert_eq!(app.scroll_offset, app.max_scroll_offset());
+    }
+
+    #[test]
+    fn bytes_per_line_never_reaches_zero() {
+        let (_file, mut app) = open(&[0; 100], 0);
+        assert_eq!(app.bytes_per_line, 1);
+        app.adjust_bytes_per_line(false);
+        assert_eq!(app.bytes_per_line, 1);
+        app.set_bytes_per_line(0);
+        assert_eq!(app.bytes_per_line, 1);
+        assert_eq!(app.max_scroll_offset(), 99);
+    }
+
+    #[test]
+    fn typing_hex_digits_edits_the_cursor_byte_a_nibble_at_a_time() {
+        let (_file, mut app) = open(b"abcd", 16);
+        app.cursor = 1;
+        app.start_edit();
+        assert!(matches!(app.mode, AppMode::Edit));
+        app.edit_char('4');
+        assert_eq!(app.parsed_file.data(), b"aBcd"); // 0x62 with a high nibble of 4
+        assert!(app.dirty);
+        assert_eq!(app.cursor, 1);
+        app.edit_char('1');
+        assert_eq!(app.parsed_file.data(), b"aAcd");
+        assert_eq!(app.cursor, 2);
+        // Non-hex characters are ignored in the hex pane, and typed in the ASCII pane
+        app.edit_char('z');
+        assert_eq!(app.parsed_file.data(), b"aAcd");
+        app.toggle_edit_pane();
+        app.edit_char('z');
+        assert_eq!(app.parsed_file.data(), b"aAzd");
+        assert_eq!(app.cursor, 3);
+    }
+
+    #[test]
+    fn saving_round_trips_edits_and_keeps_a_backup() {
+        let (file, mut app) = open(b"hello world", 16);
+        let path = file.path().to_string_lossy().into_owned();
+        app.backup_on_save = true;
+        app.replace_all(&b"world".map(Some), &b"there".map(Some));
+        assert!(app.dirty);
+        let (len, backup) = app.save(&path).unwrap();
+        assert_eq!(len, 11);
+        assert!(!app.dirty);
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello there");
+        let backup = backup.unwrap();
+        assert_eq!(backup, format!("{}.bak", path));
+        assert_eq!(std::fs::read(&backup).unwrap(), b"hello world");
+        std::fs::remove_file(backup).unwrap();
+    }
+
+    #[test]
+    fn saving_to_a_new_file_makes_no_backup() {
+        let (_file, mut app) = open(b"abc", 16);
+        app.backup_on_save = true;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("copy.bin").to_string_lossy().into_owned();
+        assert_eq!(app.save(&path).unwrap(), (3, None));
+        assert_eq!(std::fs::read(&path).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn saving_replaces_the_file_through_a_temporary_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin").to_string_lossy().into_owned();
+        std::fs::write(&path, b"old contents").unwrap();
+        let mut app = App::new(path.clone(), 16, Theme::Dark, PrintablePolicy::Standard, 0, None, usize::MAX).unwrap();
+        app.replace_all(&b"old".map(Some), &b"new".map(Some));
+        assert_eq!(app.save(&path).unwrap(), (12, None));
+        assert_eq!(std::fs::read(&path).unwrap(), b"new contents");
+        // Only the saved file is left; the temporary copy was renamed over it
+        let names: Vec<_> = std::fs::read_dir(dir.path()).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(names, ["data.bin"]);
+
+        // A temporary copy that cannot be written leaves the file as it was
+        std::fs::create_dir(format!("{}{}", path, SAVE_TEMP_SUFFIX)).unwrap();
+        app.replace_all(&b"new".map(Some), &b"bad".map(Some));
+        assert!(app.save(&path).is_err());
+        assert!(app.dirty);
+        assert_eq!(std::fs::read(&path).unwrap(), b"new contents");
+    }
+
+    #[test]
+    fn reading_past_a_truncated_end_marks_the_file_unavailable() {
+        let (file, mut app) = open(&[0x5a; 64], 16);
+        app.parsed_file = ParsedFile::Lazy(File::open(file.path()).unwrap());
+        assert_eq!(app.read_bytes(48, 32), vec![0x5a; 16]);
+        assert!(app.unavailable.is_none());
+        // Another process truncates the file behind the viewer's back
+        file.as_file().set_len(40).unwrap();
+        assert!(app.read_bytes(32, 16).is_empty());
+        assert!(app.unavailable.as_deref().unwrap().contains("file ends at 0x28"));
+    }
+
+    #[test]
+    fn filter_view_rows_count_the_separators() {
+        let (_file, mut app) = open(&[0; 16 * 20], 16);
+        app.search_results = vec![32..33, 160..161];
+        app.toggle_filter();
+        assert_eq!(app.filter_lines.as_deref(), Some(&[1, 2, 3, 9, 10, 11][..]));
+        // Lines 1-3 fill three rows, and line 9 needs a "--" row above it as well
+        app.last_visible_height = 4;
+        assert_eq!(app.filter_lines_on_screen(4), [1, 2, 3]);
+        assert_eq!(app.visible_range(), 16..64);
+        app.last_visible_height = 5;
+        assert_eq!(app.filter_lines_on_screen(5), [1, 2, 3, 9]);
+        assert_eq!(app.visible_range(), 16..160);
+        assert_eq!(app.visible_stats().1.entropy, 0.0);
+        app.page_down();
+        assert_eq!(app.top_line(), 10);
+    }
+
+    #[test]
+    fn chunked_search_finds_matches_across_chunk_boundaries() {
+        let mut data = vec![0; 2 * SEARCH_CHUNK_LEN + 100];
+        let starts = [10, SEARCH_CHUNK_LEN - 4, SEARCH_CHUNK_LEN + 3, 2 * SEARCH_CHUNK_LEN - 1];
+        for &start in &starts {
+            data[start..start + 6].copy_from_slice(b"needle");
+        }
+        let (file, mut app) = open(&data, 16);
+        assert_eq!(app.find(b"needle"), find_all(&data, b"needle", 1));
+        // Read on demand, the file is searched a chunk at a time
+        app.parsed_file = ParsedFile::Lazy(File::open(file.path()).unwrap());
+        let found: Vec<usize> = app.find(b"needle").into_iter().map(|range| range.start).collect();
+        assert_eq!(found, starts);
+        // Chunk starts stay aligned, so aligned matches are kept across the boundary too
+        app.search_alignment = Some(4);
+        let found: Vec<usize> = app.find(b"needle").into_iter().map(|range| range.start).collect();
+        assert_eq!(found, [SEARCH_CHUNK_LEN - 4]);
+    }
+
+    #[test]
+    fn files_past_the_lazy_threshold_are_mapped_and_read_the_same() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), &data).unwrap();
+        let path = file.path().to_string_lossy().into_owned();
+        let mut mapped = App::new(path, 16, Theme::Dark, PrintablePolicy::Standard, 0, None, 4096).unwrap();
+        assert!(matches!(mapped.parsed_file, ParsedFile::Mmap { .. }));
+        assert_eq!(mapped.parsed_file.data(), data);
+        let mut lazy = ParsedFile::Lazy(File::open(file.path()).unwrap());
+        for (offset, lines) in [(0, 4), (100, 30), (620, 10)] {
+            let chunk = mapped.parsed_file.get_chunk(offset, 16, lines).unwrap();
+            let end = usize::min((offset + lines) * 16, data.len());
+            assert_eq!(chunk, data[offset * 16..end]);
+            assert_eq!(chunk, lazy.get_chunk(offset, 16, lines).unwrap());
+        }
+    }
+
+    #[test]
+    fn bookmarks_round_trip_through_the_sidecar_file() {
+        let (file, mut app) = open(&[0; 256], 16);
+        let sidecar = app.bookmark_path().unwrap();
+        assert_eq!(sidecar, format!("{}{}", file.path().display(), BOOKMARK_SUFFIX));
+        app.add_bookmark(0x80, Some("header".to_string()));
+        app.add_bookmark(0x10, None);
+        app.save_bookmarks(&sidecar).unwrap();
+
+        let (_other, mut reopened) = open(&[0; 256], 16);
+        reopened.load_bookmarks(&sidecar).unwrap();
+        std::fs::remove_file(&sidecar).unwrap();
+        let bookmarks: Vec<_> = reopened.bookmarks.iter().map(|b| (b.offset, b.name.as_deref())).collect();
+        assert_eq!(bookmarks, [(0x10, None), (0x80, Some("header"))]);
+    }
+
+    #[test]
+    fn loaded_bookmarks_are_sorted_and_deduplicated() {
+        let (file, mut app) = open(&[0; 16], 16);
+        let sidecar = format!("{}{}", file.path().display(), BOOKMARK_SUFFIX);
+        std::fs::write(&sidecar, r#"[{"offset":9,"name":"b"},{"offset":2,"name":null},{"offset":9,"name":"c"}]"#).unwrap();
+        app.load_bookmarks(&sidecar).unwrap();
+        std::fs::remove_file(&sidecar).unwrap();
+        let offsets: Vec<usize> = app.bookmarks.iter().map(|b| b.offset).collect();
+        assert_eq!(offsets, [2, 9]);
+    }
+
+    #[test]
+    fn overview_rows_cover_equal_slices_of_the_file() {
+        let mut data = vec![0; 100];
+        data[40..80].fill(b'a');
+        let (_file, mut app) = open(&data, 16);
+        assert_eq!(app.overview_bucket_size(4), 25);
+        let cells = app.overview(4);
+        let classes: Vec<_> = cells.iter().map(|cell| cell.unwrap().class).collect();
+        assert!(classes == [ByteClass::Zero, ByteClass::Zero, ByteClass::Text, ByteClass::Zero]);
+        assert_eq!(cells[1].unwrap().density, 10.0 / 25.0);
+        // Rows past the end of a small file are left empty
+        assert_eq!(app.overview_bucket_size(300), 1);
+        let cells = app.overview(300);
+        assert!(cells[99].is_some() && cells[100..].iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn truncating_a_mapped_file_marks_it_unavailable_without_faulting() {
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), [0x41; 3 * 4096]).unwrap();
+        let path = file.path().to_string_lossy().into_owned();
+        let mut app = App::new(path, 16, Theme::Dark, PrintablePolicy::Standard, 0, None, 4096).unwrap();
+        assert!(matches!(app.parsed_file, ParsedFile::Mmap { .. }));
+        file.as_file().set_len(100).unwrap();
+        // The view sits on pages past the new end, which would fault if touched
+        app.scroll_offset = 500;
+        assert!(app.get_display_data(10).is_empty());
+        assert!(app.unavailable.is_some());
+
+        // Searching the whole mapping is refused the same way
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), [0x41; 3 * 4096]).unwrap();
+        let path = file.path().to_string_lossy().into_owned();
+        let mut app = App::new(path, 16, Theme::Dark, PrintablePolicy::Standard, 0, None, 4096).unwrap();
+        file.as_file().set_len(0).unwrap();
+        app.search_type = SearchType::Hex;
+        app.input_buffer = "41".to_string();
+        app.perform_search();
+        assert!(app.search_results.is_empty());
+        assert!(app.unavailable.is_some());
+    }
+
+    #[test]
+    fn segmented_addresses_take_up_to_four_hex_digits_a_side() {
+        assert_eq!(parse_segmented("1234:5"), Some(0x12345));
+        assert_eq!(parse_segmented("ffff:ffff"), Some(0x10ffef));
+        for malformed in ["", ":", "12:", ":34", "12:3g", "+1:2", "1:-2", "1:2:3", "12345:0", "0:10000"] {
+            assert_eq!(parse_segmented(malformed), None, "{:?}", malformed);
+        }
+        // Oversized pairs are refused in Goto rather than overflowing
+        let (_file, mut app) = open(&[0; 256], 16);
+        app.input_buffer = "ffffffffffffffff:0".to_string();
+        app.jump_to_offset();
+        assert_eq!(app.scroll_offset, 0);
+        assert!(app.message_is_error);
+    }
+
+    #[test]
+    fn goto_reads_bare_digits_as_hex_addresses_from_the_base() {
+        let (_file, mut app) = open(&[0; 256], 16);
+        app.base_address = 0x400000;
+        for (input, cursor) in [("400010", 0x10), ("0x400020", 0x20), ("4000a0", 0xa0), ("+16", 0xb0)] {
+            app.input_buffer = input.to_string();
+            app.jump_to_offset();
+            assert_eq!(app.cursor, cursor, "{}", input);
+        }
+        assert_eq!(app.scroll_offset, 0xb0 / 16);
+        // Addresses below the base are refused rather than wrapping
+        app.input_buffer = "3fffff".to_string();
+        app.jump_to_offset();
+        assert!(app.message_is_error);
+        assert_eq!(app.cursor, 0xb0);
+        // The gutter is wide enough for the based addresses
+        assert_eq!(app.display_options().address_width, 6);
+    }
+
+    #[test]
+    fn selection_readout_honors_case_and_the_limit() {
+        let (_file, mut app) = open(&[0xab, 0x01, 0xcd, 0xef, 0x00], 16);
+        app.show_selection_bytes();
+        assert_eq!(app.message.as_deref(), Some("0x0: ab"));
+        app.selection = Some(1..4);
+        app.uppercase_hex = true;
+        app.show_selection_bytes();
+        assert_eq!(app.message.as_deref(), Some("0x1: 01 CD EF"));
+        assert!(!app.message_is_error);
+        app.readout_limit = 2;
+        app.show_selection_bytes();
+        assert!(app.message_is_error);
+        assert!(app.message.as_deref().unwrap().starts_with("Selection too large"));
+    }
+
+    #[test]
+    fn block_selections_copy_their_columns_row_by_row() {
+        let data: Vec<u8> = (0..40).collect();
+        let (_file, mut app) = open(&data, 16);
+        app.block_mode = true;
+        app.selection = Some(selection_between(0x01, 0x12));
+        assert_eq!(app.bytes_to_copy().unwrap(), [0x01, 0x02, 0x11, 0x12]);
+        // Rows running past the end of the file are cut short
+        app.selection = Some(selection_between(0x25, 0x0e));
+        assert_eq!(app.bytes_to_copy().unwrap(), [5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 37, 38, 39]);
+        app.readout_limit = 4;
+        app.selection = Some(selection_between(0x01, 0x12));
+        app.show_selection_bytes();
+        assert_eq!(app.message.as_deref(), Some("0x1: 01 02 11 12"));
+        // The same selection is a run of bytes again in linear mode
+        app.block_mode = false;
+        assert_eq!(app.bytes_to_copy().unwrap().len(), 0x12);
+    }
+
+    #[test]
+    fn regex_capture_groups_are_highlighted_in_their_own_colors() {
+        let (_file, mut app) = open(b"id=42; x=7;", 16);
+        app.search_type = SearchType::Regex;
+        app.input_buffer = r"(\w+)=(\d+)".to_string();
+        app.perform_search();
+        assert_eq!(app.search_results, [0..5, 7..10]);
+        assert_eq!(app.search_groups, [vec![0..2, 7..8], vec![3..5, 9..10]]);
+
+        // Each cell's background: group 1, group 2, or the rest of the match
+        let data = app.read_bytes(0, 16);
+        let line = crate::utils::format_hex_dump(&data, 0, 1, 16, app.highlights(), app.display_options()).remove(0);
+        let backgrounds: Vec<Option<Color>> = line.spans[2..13].iter().map(|span| span.style.bg).collect();
+        let (one, two, rest) = (Some(TERM_COLORS[0]), Some(TERM_COLORS[1]), Some(app.display_options().colors.match_bg));
+        assert_eq!(backgrounds, [one, one, rest, two, two, None, None, one, rest, two, None]);
+
+        // A nested group wins over the group around it
+        app.input_buffer = r"((\w)\w)=".to_string();
+        app.perform_search();
+        let line = crate::utils::format_hex_dump(&data, 0, 1, 16, app.highlights(), app.display_options()).remove(0);
+        assert_eq!(line.spans[2].style.bg, two);
+        assert_eq!(line.spans[3].style.bg, one);
+    }
+
+    #[test]
+    fn a_section_viewed_alone_is_scoped_to_its_bytes() {
+        let elf = tiny_elf();
+        let (_file, mut app) = open(&elf, 16);
+        let names: Vec<&str> = app.parsed_file.sections().iter().map(|section| section.name.as_str()).collect();
+        assert_eq!(names, [".text", ".shstrtab"]);
+        app.add_bookmark(0x48, Some("inside".to_string()));
+        app.add_bookmark(0x10, None);
+        app.section_index = 0;
+        app.enter_section();
+        assert_eq!(app.isolated_section(), Some((".text", 64)));
+        assert_eq!(app.file_size, 16);
+        assert_eq!(app.read_bytes(0, 64), (0x90..0xa0).collect::<Vec<u8>>());
+        assert_eq!(app.max_scroll_offset(), 0);
+        assert_eq!(app.bookmarks.iter().map(|bookmark| bookmark.offset).collect::<Vec<_>>(), [8]);
+        // Bookmarks made in the section are kept at their file offsets
+        app.add_bookmark(4, None);
+        app.cursor = 2;
+        app.leave_section();
+        assert_eq!(app.isolated_section(), None);
+        assert_eq!(app.file_size, elf.len());
+        assert_eq!(app.cursor, 66);
+        assert_eq!(app.bookmarks.iter().map(|bookmark| bookmark.offset).collect::<Vec<_>>(), [0x10, 0x44, 0x48]);
+
+        // Sections without bytes, or past the end of the file, are refused
+        app.parsed_file = ParsedFile::Elf {
+            data: elf.clone(),
+            sections: vec![
+                SectionInfo { name: ".empty".to_string(), offset: 64, size: 0, address: 0 },
+                SectionInfo { name: ".gone".to_string(), offset: 0x1000, size: 8, address: 0 },
+            ],
+        };
+        for index in 0..2 {
+            app.section_index = index;
+            app.enter_section();
+            assert!(app.message_is_error);
+            assert_eq!(app.isolated_section(), None);
+        }
+    }
+
+    #[test]
+    fn idle_edits_are_auto_saved_beside_the_file() {
+        let (file, mut app) = open(b"hello", 16);
+        let autosave = app.autosave_path().unwrap();
+        app.replace_all(&b"h".map(Some), &b"j".map(Some));
+        // Off by default
+        app.poll_autosave();
+        assert!(!Path::new(&autosave).exists());
+        app.autosave_after = Some(Duration::from_secs(60));
+        app.poll_autosave();
+        assert!(!Path::new(&autosave).exists()); // Not idle long enough yet
+        app.last_edit_at = Some(Instant::now() - Duration::from_secs(61));
+        app.poll_autosave();
+        assert_eq!(std::fs::read(&autosave).unwrap(), b"jello");
+        assert_eq!(std::fs::read(file.path()).unwrap(), b"hello");
+        assert!(app.dirty);
+        // Saving writes the file and drops the copy
+        app.save(&file.path().to_string_lossy()).unwrap();
+        assert!(!Path::new(&autosave).exists());
+    }
+
+    #[test]
+    fn search_results_follow_the_file_as_it_grows_and_shrinks() {
+        let (file, mut app) = open(b"abc--ab", 16);
+        let starts = |app: &App| app.search_results.iter().map(|range| range.start).collect::<Vec<_>>();
+        app.input_buffer = "abc".to_string();
+        app.perform_search();
+        assert_eq!(starts(&app), [0]);
+        // The match completed by the appended byte straddles the old end
+        std::fs::write(file.path(), b"abc--abc-abc").unwrap();
+        app.reload();
+        assert_eq!(app.search_results, [0..3, 5..8, 9..12]);
+        assert_eq!(app.message.as_deref(), Some("3 match(es) after the file changed"));
+        std::fs::write(file.path(), b"abc--ab").unwrap();
+        app.reload();
+        assert_eq!(starts(&app), [0]);
+
+        // Hex searches read on demand pick up appended bytes too
+        app.parsed_file = ParsedFile::Lazy(File::open(file.path()).unwrap());
+        app.search_type = SearchType::Hex;
+        app.input_buffer = "62 63".to_string();
+        app.perform_search();
+        assert_eq!(starts(&app), [1]);
+        std::fs::write(file.path(), b"abc--abc").unwrap();
+        app.reload();
+        assert_eq!(app.search_results, [1..3, 6..8]);
+    }
+
+    #[test]
+    fn overlaying_a_shorter_second_file_marks_the_bytes_past_its_end() {
+        let (_file, mut app) = open(&[0xde, 1, 2, 3], 4);
+        app.toggle_overlay();
+        assert!(app.message_is_error && !app.overlay); // Nothing to overlay yet
+        let second = NamedTempFile::new().unwrap();
+        std::fs::write(second.path(), [0xad, 1]).unwrap();
+        app.open_second_file(second.path().to_string_lossy().into_owned(), usize::MAX).unwrap();
+        assert!(!app.display_options().overlay);
+        app.toggle_overlay();
+        assert!(app.display_options().overlay);
+        assert!(app.message.as_deref().unwrap().contains("0x2 on show --"));
+        let data = app.get_display_data(1);
+        let other = app.second_file_data(0, 1);
+        let highlights = Highlights { overlay: Some(&other), ..app.highlights() };
+        let line = crate::utils::format_hex_dump(&data, 0, 1, 4, highlights, app.display_options()).remove(0);
+        let text: String = line.spans.iter().map(|span| span.content.as_ref()).collect();
+        assert!(text.contains("de→ad 01    02→-- 03→-- "), "{}", text);
+        app.toggle_overlay();
+        assert!(!app.display_options().overlay);
+    }
+
+    #[test]
+    fn clicking_a_pin_in_the_sidebar_jumps_to_it() {
+        let (_file, mut app) = open(&[0u8; 0x100], 16);
+        app.add_bookmark(0x80, Some("header".to_string()));
+        app.add_bookmark(0x10, None);
+        app.pins_area = Rect { x: 50, y: 5, width: 28, height: 4 }; // Two rows inside the borders
+        assert!(!app.click_pins(51, 6)); // Hidden
+        app.toggle_pins();
+        assert!(!app.click_pins(51, 5)); // The border
+        assert!(app.click_pins(51, 7));
+        assert_eq!((app.cursor, app.bookmark_index), (0x80, 1));
+        assert!(app.message.as_deref().unwrap().contains("header"));
+        app.remove_bookmark(0x80);
+        assert!(!app.click_pins(51, 7)); // Below the last pin
+        // The next pin wraps around to the first
+        app.add_bookmark(0x80, None);
+        app.next_pin();
+        assert_eq!((app.cursor, app.bookmark_index), (0x10, 0));
+        app.next_pin();
+        assert_eq!(app.cursor, 0x80);
+    }
+
+    #[test]
+    fn the_render_cache_is_reused_until_the_view_changes() {
+        let data: Vec<u8> = (0..0x1000).map(|i| i as u8).collect();
+        let (_file, mut app) = open(&data, 16);
+        // Draws a frame with a marker in place of the cached lines, returning whether it was shown
+        let draw_marked = |app: &mut App| {
+            if let Some((_, lines)) = &mut app.render_cache {
+                *lines = vec![Line::from("cached-marker")];
+            }
+            draw(app, 100, 30).contains("cached-marker")
+        };
+        assert!(!draw_marked(&mut app)); // Nothing cached yet
+        let key = app.render_cache.as_ref().unwrap().0;
+        assert!(draw_marked(&mut app)); // Nothing changed, so the cached lines are drawn as-is
+        assert!(app.render_cache.as_ref().is_some_and(|(cached, _)| *cached == key));
+        app.invalidate_view();
+        assert!(!draw_marked(&mut app));
+        assert!(draw_marked(&mut app));
+        app.scroll_offset += 1;
+        assert!(!draw_marked(&mut app));
+        assert_eq!(app.render_cache.as_ref().unwrap().0.scroll_offset, 1);
+    }
+
+    #[test]
+    fn goto_reads_hex_decimal_and_relative_offsets() {
+        let (_file, mut app) = open(&[0; 0x2000], 16);
+        app.last_visible_height = 4;
+        let goto = |app: &mut App, input: &str| {
+            app.input_buffer = input.to_string();
+            app.jump_to_offset();
+            app.top_line() * app.bytes_per_line
+        };
+        assert_eq!(goto(&mut app, "0x1000"), 0x1000);
+        assert_eq!(goto(&mut app, "4096"), 4096);
+        // Relative moves count from the top of the view, which shows the target's line
+        assert_eq!(goto(&mut app, "+10"), 4096);
+        assert_eq!(app.cursor, 4106);
+        assert_eq!(goto(&mut app, "-10"), 4080);
+        assert_eq!(app.cursor, 4086);
+        // Moving back past the start stops at it
+        goto(&mut app, "0x10");
+        assert_eq!(goto(&mut app, "-0x100"), 0);
+        assert_eq!(app.cursor, 0);
+        assert!(!app.message_is_error);
+    }
+
+    #[test]
+    fn the_cursor_stays_in_the_file_and_on_screen() {
+        let (_file, mut app) = open(&[0; 100], 16);
+        app.last_visible_height = 2;
+        app.move_cursor(false, 1, false);
+        assert_eq!(app.cursor, 0); // Nothing before the first byte
+        app.move_cursor(false, 16, false);
+        assert_eq!((app.cursor, app.scroll_offset), (0, 0));
+        app.move_cursor(true, 1, false);
+        app.move_cursor(true, 16, false);
+        assert_eq!((app.cursor, app.scroll_offset), (17, 0));
+        // Leaving the bottom of the view scrolls it by a line
+        app.move_cursor(true, 16, false);
+        assert_eq!((app.cursor, app.scroll_offset), (33, 1));
+        // Moves past the end stop on the last byte, scrolled into view
+        app.move_cursor(true, 1000, false);
+        assert_eq!(app.cursor, 99);
+        assert!(app.visible_range().contains(&99));
+        app.move_cursor(true, 1, false);
+        assert_eq!(app.cursor, 99);
+        // Leaving the top scrolls back up
+        app.move_cursor(false, 3 * 16, false);
+        assert_eq!(app.cursor, 51);
+        assert_eq!(app.scroll_offset, 51 / 16);
+    }
+
+    #[test]
+    fn files_at_or_below_the_lazy_threshold_are_read_into_memory() {
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), [7u8; 4096]).unwrap();
+        let path = file.path().to_string_lossy().into_owned();
+        let open_with = |threshold| App::new(path.clone(), 16, Theme::Dark, PrintablePolicy::Standard, 0, None, threshold).unwrap();
+        assert!(matches!(open_with(usize::MAX).parsed_file, ParsedFile::Generic(_)));
+        assert!(matches!(open_with(4096).parsed_file, ParsedFile::Generic(_)));
+        assert!(matches!(open_with(4095).parsed_file, ParsedFile::Mmap { .. }));
+        assert!(matches!(open_with(0).parsed_file, ParsedFile::Mmap { .. })); // Zero always loads lazily
+    }
+
+    #[test]
+    fn line_gotos_are_zero_based_and_clamped_to_the_last_line() {
+        let (_file, mut app) = open(&[0; 100], 16); // Lines 0 to 6
+        let goto = |app: &mut App, input: &str| {
+            app.input_buffer = input.to_string();
+            app.jump_to_offset();
+            app.scroll_offset
+        };
+        assert_eq!(goto(&mut app, "L3"), 3);
+        assert_eq!(goto(&mut app, "L0"), 0);
+        assert_eq!(goto(&mut app, "L6"), 6);
+        assert_eq!(goto(&mut app, "l2"), 2);
+        assert_eq!(goto(&mut app, "L7"), 6); // One past the last line
+        assert_eq!(goto(&mut app, "L99999999"), 6);
+        assert!(!app.message_is_error);
+        assert_eq!(goto(&mut app, "Lx"), 6);
+        assert!(app.message_is_error);
+    }
+
+    #[test]
+    fn mapped_executables_list_their_sections() {
+        let elf = tiny_elf();
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), &elf).unwrap();
+        let path = file.path().to_string_lossy().into_owned();
+        let mut app = App::new(path, 16, Theme::Dark, PrintablePolicy::Standard, 0, None, 0).unwrap();
+        assert!(matches!(app.parsed_file, ParsedFile::Mmap { .. }));
+        let listing = |parsed_file: &ParsedFile| -> Vec<(String, usize, usize)> {
+            parsed_file.sections().iter().map(|section| (section.name.clone(), section.offset, section.size)).collect()
+        };
+        assert_eq!(listing(&app.parsed_file)[0], (".text".to_string(), 64, 16));
+        // The same sections as when the file is read into memory
+        let (_loaded_file, loaded) = open(&elf, 16);
+        assert!(matches!(loaded.parsed_file, ParsedFile::Elf { .. }));
+        assert_eq!(listing(&app.parsed_file), listing(&loaded.parsed_file));
+        // Mapped sections can be listed, jumped to and viewed alone
+        app.open_sections();
+        assert!(matches!(app.mode, AppMode::Sections));
+        app.enter_section();
+        assert_eq!(app.isolated_section(), Some((".text", 64)));
+        assert_eq!(app.read_bytes(0, 16), (0x90..0xa0).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn png_chunks_report_bad_crcs_whether_read_or_mapped() {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend_from_slice(&13u32.to_be_bytes());
+        png.extend_from_slice(b"IHDR");
+        png.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0]);
+        png.extend_from_slice(&0xdeadbeefu32.to_be_bytes()); // Not the chunk's CRC
+        png.extend_from_slice(&0u32.to_be_bytes());
+        png.extend_from_slice(b"IEND");
+        png.extend_from_slice(&0xae426082u32.to_be_bytes());
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), &png).unwrap();
+        let path = file.path().to_string_lossy().into_owned();
+        for threshold in [usize::MAX, 0] {
+            let app = App::new(path.clone(), 16, Theme::Dark, PrintablePolicy::Standard, 0, None, threshold).unwrap();
+            assert_eq!(matches!(app.parsed_file, ParsedFile::Mmap { .. }), threshold == 0);
+            let chunks: Vec<(String, usize, bool)> =
+                app.parsed_file.chunks().iter().map(|chunk| (chunk.name(), chunk.offset, chunk.crc_ok())).collect();
+            assert_eq!(chunks, [("IHDR".to_string(), 8, false), ("IEND".to_string(), 33, true)]);
+        }
+    }
+
+    #[test]
+    fn uppercase_hex_reaches_the_gutter_bytes_and_metadata() {
+        let data: Vec<u8> = (0..0x1000).map(|i| i as u8).collect();
+        let (_file, mut app) = open(&data, 16);
+        app.jump_to_line(0xab);
+        let lower = draw(&mut app, 120, 20);
+        assert!(lower.matches("ab0").count() >= 2, "{}", lower); // The metadata offset and the gutter
+        assert!(lower.contains("b0 b1 b2") && !lower.contains("AB0"));
+        app.toggle_uppercase_hex();
+        let upper = draw(&mut app, 120, 20);
+        assert!(upper.matches("AB0").count() >= 2, "{}", upper);
+        assert!(upper.contains("B0 B1 B2") && !upper.contains("ab0") && !upper.contains("b0 b1"));
+    }
+
+    #[test]
+    fn aligned_searches_keep_only_matches_at_multiples_of_the_alignment() {
+        let mut data = vec![0u8; 64];
+        for start in [3, 16, 21, 32, 40] {
+            data[start..start + 2].copy_from_slice(&[0xca, 0xfe]);
+        }
+        let (_file, mut app) = open(&data, 16);
+        let search = |app: &mut App, search_type, query: &str| {
+            app.search_type = search_type;
+            app.input_buffer = query.to_string();
+            app.perform_search();
+            app.search_results.iter().map(|range| range.start).collect::<Vec<_>>()
+        };
+        assert_eq!(search(&mut app, SearchType::Hex, "ca fe"), [3, 16, 21, 32, 40]);
+        app.search_alignment = Some(16);
+        assert_eq!(search(&mut app, SearchType::Hex, "ca fe"), [16, 32]);
+        assert_eq!(search(&mut app, SearchType::Regex, r"(?-u)\xca\xfe"), [16, 32]);
+        app.search_alignment = Some(8);
+        assert_eq!(search(&mut app, SearchType::Hex, "ca fe"), [16, 32, 40]);
+        app.search_alignment = Some(64);
+        assert!(search(&mut app, SearchType::Hex, "ca fe").is_empty());
+        assert!(app.message.as_deref().unwrap().contains("64-byte aligned"));
+    }
+
+    #[test]
+    fn back_and_forward_retrace_jumps_but_not_scrolling() {
+        let (_file, mut app) = open(&[0; 0x1000], 16);
+        app.last_visible_height = 4;
+        for line in [0x10, 0x20, 0x30] {
+            app.jump_to_line(line);
+        }
+        app.scroll_down(); // Line moves don't enter the history
+        assert_eq!(app.back_history, [0, 0x100, 0x200]);
+        app.navigate_back();
+        assert_eq!(app.scroll_offset, 0x20);
+        app.navigate_back();
+        app.navigate_back();
+        assert_eq!(app.scroll_offset, 0);
+        app.navigate_back(); // Nothing earlier
+        assert_eq!(app.scroll_offset, 0);
+        app.navigate_forward();
+        app.navigate_forward();
+        assert_eq!(app.scroll_offset, 0x20);
+        // A new jump drops the forward branch
+        app.jump_to_line(0x40);
+        assert!(app.forward_history.is_empty());
+        app.navigate_forward();
+        assert_eq!(app.scroll_offset, 0x40);
+        app.navigate_back();
+        assert_eq!(app.scroll_offset, 0x20);
+        // The history keeps only the newest jumps
+        for line in 0..NAV_HISTORY_LIMIT + 10 {
+            app.jump_to_line(line % 2 * 0x50);
+        }
+        assert_eq!(app.back_history.len(), NAV_HISTORY_LIMIT);
+    }
+
+    #[test]
+    fn clicks_map_to_bytes_past_the_alignment_margin() {
+        let data: Vec<u8> = (0..0x100).map(|i| i as u8).collect();
+        let (_file, mut app) = open(&data, 16);
+        app.content_align = ContentAlign::Right;
+        let width = 140;
+        let screen: Vec<char> = draw(&mut app, width, 20).chars().collect();
+        assert!(app.content_margin > 0);
+        // Find where the byte 0x3c was drawn and click on it
+        let needle: Vec<char> = "3c 3d".chars().collect();
+        let at = screen.windows(needle.len()).position(|window| window == needle.as_slice()).unwrap();
+        let (column, row) = ((at % width as usize) as u16, (at / width as usize) as u16);
+        assert!(app.press_content(column + 1, row)); // The cell's second digit
+        assert_eq!(app.cursor, 0x3c);
+        // The margin itself holds no bytes
+        assert!(!app.press_content(app.content_area.x + 1, row));
+        assert_eq!(app.cursor, 0x3c);
+    }
+
+    #[test]
+    fn pages_move_by_the_visible_height_and_stop_at_the_ends() {
+        let (_file, mut app) = open(&[0; 100 * 16], 16); // Lines 0 to 99
+        app.last_visible_height = 40;
+        press(&mut app, KeyCode::PageDown);
+        assert_eq!(app.scroll_offset, 40);
+        press(&mut app, KeyCode::PageDown);
+        assert_eq!(app.scroll_offset, 80);
+        press(&mut app, KeyCode::PageDown); // Past the end
+        assert_eq!(app.scroll_offset, 99);
+        press(&mut app, KeyCode::PageUp);
+        assert_eq!(app.scroll_offset, 59);
+        app.scroll_offset = 10;
+        press(&mut app, KeyCode::PageUp); // Past the start
+        assert_eq!(app.scroll_offset, 0);
+    }
+
+    #[test]
+    fn home_and_end_reach_the_first_and_last_lines() {
+        let (_file, mut app) = open(&[0; 100 * 16], 16);
+        for (end, home) in [(KeyCode::End, KeyCode::Home), (KeyCode::Char('G'), KeyCode::Char('g'))] {
+            press(&mut app, end);
+            assert_eq!(app.scroll_offset, 99);
+            press(&mut app, home);
+            assert_eq!(app.scroll_offset, 0);
+        }
+        // An empty file has only the first line
+        let (_empty_file, mut empty) = open(&[], 16);
+        for key in [KeyCode::End, KeyCode::Char('G'), KeyCode::Home] {
+            press(&mut empty, key);
+            assert_eq!(empty.scroll_offset, 0);
+        }
+    }
+
+    #[test]
+    fn n_and_shift_n_step_through_matches_and_wrap() {
+        let mut data = vec![0u8; 0x1000];
+        for start in [0x105, 0x400, 0x801] {
+            data[start..start + 3].copy_from_slice(b"key");
+        }
+        let (_file, mut app) = open(&data, 16);
+        app.search_type = SearchType::Ascii;
+        app.input_buffer = "key".to_string();
+        app.perform_search();
+        let step = |app: &mut App, key| {
+            press(app, key);
+            (app.scroll_offset, app.message.clone().unwrap_or_default())
+        };
+        assert_eq!(step(&mut app, KeyCode::Char('n')), (0x10, "Match 1/3".to_string()));
+        assert_eq!(step(&mut app, KeyCode::Char('n')), (0x40, "Match 2/3".to_string()));
+        assert_eq!(step(&mut app, KeyCode::Char('n')), (0x80, "Match 3/3".to_string()));
+        assert_eq!(step(&mut app, KeyCode::Char('n')), (0x10, "Match 1/3".to_string())); // Wraps to the first
+        assert_eq!(step(&mut app, KeyCode::Char('N')), (0x80, "Match 3/3".to_string())); // And back to the last
+        assert_eq!(step(&mut app, KeyCode::Char('N')), (0x40, "Match 2/3".to_string()));
+        // Without a current match, N finds the last match before the top of the view
+        app.current_match = None;
+        app.scroll_offset = 0x60;
+        assert_eq!(step(&mut app, KeyCode::Char('N')), (0x40, "Match 2/3".to_string()));
+    }
+
+    #[test]
+    fn ignoring_case_folds_only_ascii_letters() {
+        let (_file, mut app) = open("ERROR error Error Érror érror".as_bytes(), 16);
+        let search = |app: &mut App, query: &str| {
+            app.search_type = SearchType::Ascii;
+            app.input_buffer = query.to_string();
+            app.perform_search();
+            app.search_results.iter().map(|range| range.start).collect::<Vec<_>>()
+        };
+        assert_eq!(search(&mut app, "error"), [6]);
+        assert_eq!(search(&mut app, "ERROR"), [0]);
+        assert_eq!(search(&mut app, "Error"), [12]);
+        app.case_insensitive = true;
+        for query in ["error", "ERROR", "Error"] {
+            assert_eq!(search(&mut app, query), [0, 6, 12], "{}", query);
+        }
+        // Bytes other than ASCII letters must match exactly
+        assert_eq!(search(&mut app, "rror "), [1, 7, 13, 20]);
+        assert_eq!(search(&mut app, "érror"), [25]); // Not the É at 18
+    }
+
+    #[test]
+    fn regex_searches_find_unanchored_and_anchored_patterns() {
+        let (_file, mut app) = open(b"2024-01 log 1999-12\nend 2000-06", 16);
+        let search = |app: &mut App, query: &str| {
+            app.search_type = SearchType::Regex;
+            app.input_buffer = query.to_string();
+            app.perform_search();
+            app.search_results.clone()
+        };
+        assert_eq!(search(&mut app, r"\d{4}-\d{2}"), [0..7, 12..19, 24..31]);
+        assert_eq!(search(&mut app, r"^\d{4}-\d{2}"), std::iter::once(0..7).collect::<Vec<_>>());
+        assert_eq!(search(&mut app, r"(?m)^\w+"), [0..4, 20..23]);
+        assert_eq!(search(&mut app, r"\d{2}$"), std::iter::once(29..31).collect::<Vec<_>>());
+        // An invalid pattern is reported, not a panic
+        assert!(search(&mut app, r"(\d").is_empty());
+        assert!(app.message_is_error);
+        assert!(app.message.as_deref().unwrap().starts_with("Invalid regex"));
+    }
+
+    #[test]
+    fn hex_searches_match_wildcard_bytes_anywhere() {
+        let data = [0x48, 0x8b, 0x05, 0x10, 0x00, 0x48, 0x8b, 0xff, 0xee, 0x00, 0x48, 0x8b, 0x01, 0x02, 0x01];
+        let (_file, mut app) = open(&data, 16);
+        let search = |app: &mut App, query: &str| {
+            app.search_type = SearchType::Hex;
+            app.input_buffer = query.to_string();
+            app.perform_search();
+            app.search_results.iter().map(|range| range.start).collect::<Vec<_>>()
+        };
+        assert_eq!(search(&mut app, "48 8b ?? ?? 00"), [0, 5]);
+        assert_eq!(search(&mut app, "48 8b"), [0, 5, 10]); // The exact path
+        assert_eq!(search(&mut app, "?? 00"), [3, 8]);
+        assert_eq!(search(&mut app, "488b??"), [0, 5, 10]);
+        assert_eq!(search(&mut app, "01 ?? 01"), [12]);
+        assert!(!app.message_is_error);
+        // Tokens must be two hex digits or ??
+        for query in ["48 8g", "48 ?", "4?"] {
+            assert!(search(&mut app, query).is_empty(), "{}", query);
+            assert!(app.message_is_error, "{}", query);
+        }
+        assert_eq!(parse_hex_pattern("48 ?? 00"), Ok(vec![Some(0x48), None, Some(0)]));
+    }
+
+    #[test]
+    fn copies_take_the_selection_or_the_cursor_byte_from_files_read_on_demand() {
+        let data: Vec<u8> = (0..64).collect();
+        let (file, mut app) = open(&data, 16);
+        app.parsed_file = ParsedFile::Lazy(File::open(file.path()).unwrap());
+        app.cursor = 5;
+        assert_eq!(app.bytes_to_copy().unwrap(), [5]);
+        app.selection = Some(selection_between(40, 33));
+        assert_eq!(app.bytes_to_copy().unwrap(), (33..41).collect::<Vec<u8>>());
+        let (_empty_file, mut empty) = open(&[], 16);
+        assert!(empty.bytes_to_copy().is_none());
+    }
+
+    #[test]
+    fn exported_dumps_match_the_plain_text_layout() {
+        let (_file, mut app) = open(b"Hello, world!\x00\x01\xff\x7fAB", 8);
+        let out = NamedTempFile::new().unwrap();
+        let path = out.path().to_string_lossy().into_owned();
+        app.export_dump(&path, 0..app.file_size).unwrap();
+        let expected = "\
+000000: 48 65 6c 6c 6f 2c 20 77   Hello, w
+000008: 6f 72 6c 64 21 00 01 ff   orld!...
+000010: 7f 41 42                  .AB
+";
+        assert_eq!(std::fs::read_to_string(out.path()).unwrap(), expected);
+        // The export command writes just the selection, at its own offsets
+        app.selection = Some(0x0a..0x12);
+        app.input_buffer = format!("export {}", path);
+        assert!(app.run_command());
+        assert_eq!(app.message.as_deref(), Some(format!("Exported 8 bytes to {}", path).as_str()));
+        let expected = "\
+00000a: 6c 64 21 00 01 ff 7f 41   ld!....A
+";
+        assert_eq!(std::fs::read_to_string(out.path()).unwrap(), expected);
+    }
+
+    #[test]
+    fn piped_input_is_read_into_memory_and_windowed() {
+        let input: &[u8] = b"\x7fELF piped bytes";
+        let app = App::from_reader(input, 16, Theme::Dark, PrintablePolicy::Standard, 0, None, 0).unwrap();
+        assert_eq!(app.file_path, STDIN_NAME);
+        assert_eq!(app.file_size, input.len());
+        // Never mapped, whatever the threshold
+        assert!(matches!(&app.parsed_file, ParsedFile::Generic(data) if data == input));
+        assert!(app.bookmark_path().is_none() && app.autosave_path().is_none());
+        assert!(!app.message_is_error); // No complaint that the ELF headers don't parse
+        let windowed = App::from_reader(input, 16, Theme::Dark, PrintablePolicy::Standard, 5, Some(5), 0).unwrap();
+        assert_eq!(windowed.parsed_file.data(), b"piped");
+        assert!(App::from_reader(input, 16, Theme::Dark, PrintablePolicy::Standard, 100, None, 0).is_err());
+    }
+
+    #[test]
+    fn a_watched_file_that_grows_is_reloaded_in_place() {
+        let (file, mut app) = open(&[1; 64], 16);
+        app.watch().unwrap();
+        app.scroll_offset = 2;
+        assert_eq!(app.max_scroll_offset(), 3);
+        std::fs::write(file.path(), [2; 160]).unwrap();
+        // Changes are picked up once they have settled
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while app.file_size == 64 && Instant::now() < deadline {
+            app.poll_watch();
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(app.file_size, 160);
+        assert_eq!(app.max_scroll_offset(), 9);
+        assert_eq!(app.scroll_offset, 2); // The view stays put
+        assert_eq!(app.read_bytes(0, 1), [2]);
+        // Shrinking below the view pulls it back to the new end
+        app.scroll_offset = 9;
+        std::fs::write(file.path(), [3; 40]).unwrap();
+        app.reload();
+        assert_eq!((app.file_size, app.max_scroll_offset(), app.scroll_offset), (40, 2, 2));
+    }
+
+    #[test]
+    fn follow_mode_tracks_growth_until_scrolled_up() {
+        let (file, mut app) = open(&[1; 64], 16);
+        press(&mut app, KeyCode::Char('F'));
+        assert!(app.follow);
+        assert_eq!(app.scroll_offset, app.max_scroll_offset());
+        assert!(draw(&mut app, 250, 10).contains("FOLLOW"));
+        // Without a watcher, growth is found by polling the file's size
+        std::fs::write(file.path(), [2; 160]).unwrap();
+        app.poll_growth();
+        app.update_follow();
+        assert!(app.follow);
+        assert_eq!((app.file_size, app.scroll_offset), (160, app.max_scroll_offset()));
+        // Scrolling up leaves follow mode
+        press(&mut app, KeyCode::PageUp);
+        app.update_follow();
+        assert!(!app.follow);
+        assert!(!draw(&mut app, 250, 10).contains("FOLLOW"));
+        // Once off, growth no longer moves the view
+        let offset = app.scroll_offset;
+        std::fs::write(file.path(), [3; 320]).unwrap();
+        app.poll_growth();
+        assert_eq!((app.file_size, app.scroll_offset), (160, offset));
+        // Toggling again turns it off explicitly
+        press(&mut app, KeyCode::Char('F'));
+        press(&mut app, KeyCode::Char('F'));
+        assert!(!app.follow);
+    }
+
+    #[test]
+    fn auto_width_refits_until_the_width_is_set_by_hand() {
+        let (_file, mut app) = open(&[1; 256], 16);
+        app.auto_width = true;
+        app.fit_to_width(106);
+        assert_eq!(app.bytes_per_line, 24);
+        app.fit_to_width(80);
+        assert_eq!(app.bytes_per_line, 16);
+        press(&mut app, KeyCode::Char('+'));
+        assert!(!app.auto_width);
+        assert_eq!(app.bytes_per_line, 17);
+        app.fit_to_width(106); // No longer fitted
+        assert_eq!(app.bytes_per_line, 17);
+    }
+
+    #[test]
+    fn gutter_and_metadata_offset_share_the_address_width() {
+        let (_file, mut app) = open(&(0x41..0xa5).collect::<Vec<u8>>(), 16);
+        let screen = draw(&mut app, 250, 20);
+        assert!(screen.contains("Offset: 0x000000 "), "{screen}");
+        assert!(screen.contains("000000: 41"), "{screen}");
+        app.address_width = Some(10); // As --addr-width sets it
+        app.invalidate_view();
+        let screen = draw(&mut app, 250, 20);
+        assert!(screen.contains("Offset: 0x0000000000 "), "{screen}");
+        assert!(screen.contains("0000000000: 41"), "{screen}");
+    }
+
+    #[test]
+    fn detected_format_is_shown_in_the_metadata_bar() {
+        let (_file, mut app) = open(&tiny_elf(), 16);
+        assert!(matches!(app.parsed_file, ParsedFile::Elf { .. }));
+        assert!(draw(&mut app, 250, 20).contains("| Format: ELF |"));
+        let (_file, mut app) = open(b"plain text, no signature", 16);
+        assert!(!draw(&mut app, 250, 20).contains("Format:"));
+    }
+
+    #[test]
+    fn png_chunk_list_jumps_to_the_chosen_chunk() {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        for (kind, data, crc) in [
+            (b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0][..], 0x3a7e9b55u32),
+            (b"IDAT", &[0x78, 0x9c, 0x63, 0x60, 0, 0, 0, 2, 0, 1][..], 0xdeadbeef), // Corrupted
+            (b"IEND", &[][..], 0xae426082),
+        ] {
+            png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            png.extend_from_slice(kind);
+            png.extend_from_slice(data);
+            png.extend_from_slice(&crc.to_be_bytes());
+        }
+        let (_file, mut app) = open(&png, 8);
+        assert!(matches!(app.parsed_file, ParsedFile::Png { .. }));
+        app.open_sections();
+        assert!(matches!(app.mode, AppMode::Sections));
+        let screen = draw(&mut app, 120, 20);
+        assert!(screen.contains("IDAT  0x000021  10 bytes  CRC 0xdeadbeef (computed 0x48afa471)"), "{screen}");
+        app.move_section(true);
+        app.jump_to_section();
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert_eq!((app.cursor, app.scroll_offset), (33, 4));
+        assert_eq!(app.message.as_deref(), Some("Chunk IDAT at 0x21 (22 bytes)"));
+    }
+
+    #[test]
+    fn bookmarks_are_added_removed_and_jumped_to() {
+        let data: Vec<u8> = (0..=255).collect();
+        let (_file, mut app) = open(&data, 16);
+        app.add_bookmark(0xa0, Some("table".to_string()));
+        app.add_bookmark(0x10, None);
+        app.add_bookmark(0xa0, Some("header".to_string())); // Renames rather than duplicating
+        let marks: Vec<(usize, Option<&str>)> =
+            app.bookmarks.iter().map(|bookmark| (bookmark.offset, bookmark.name.as_deref())).collect();
+        assert_eq!(marks, [(0x10, None), (0xa0, Some("header"))]);
+        // Marked lines carry a glyph in the gutter
+        let screen = draw(&mut app, 120, 20);
+        assert!(screen.contains("000010:*10") && screen.contains("000000: 00"), "{screen}");
+        // The list opens at the bookmark at or before the cursor and jumps to the chosen one
+        app.cursor = 0x30;
+        press(&mut app, KeyCode::Char('\''));
+        assert!(matches!(app.mode, AppMode::Bookmarks));
+        assert_eq!(app.bookmark_index, 0);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert_eq!((app.cursor, app.scroll_offset), (0xa0, 0xa));
+        assert_eq!(app.message.as_deref(), Some("Bookmark header at 0xa0"));
+        // 'm' on a bookmark removes it; removing a missing one reports false
+        press(&mut app, KeyCode::Char('m'));
+        assert_eq!(app.bookmarks.len(), 1);
+        assert!(!app.remove_bookmark(0xa0));
+        assert!(app.remove_bookmark(0x10));
+        assert!(app.bookmarks.is_empty());
+        press(&mut app, KeyCode::Char('\''));
+        assert!(matches!(app.mode, AppMode::Normal)); // Nothing to list
+    }
+
+    #[test]
+    fn custom_theme_toml_deserializes_into_a_color_scheme() {
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(
+            file.path(),
+            "address = \"lightblue\"\n\
+             hex_byte = \"#ff8800\"\n\
+             ascii_printable = \"green\"\n\
+             ascii_nonprintable = \"gray\"\n\
+             match_fg = \"black\"\n\
+             match_bg = \"#ffff00\"\n\
+             background = \"#1e1e2e\"\n",
+        )
+        .unwrap();
+        let scheme = load_color_scheme(file.path()).unwrap();
+        assert_eq!(scheme.address, Color::LightBlue);
+        assert_eq!(scheme.hex_byte, Color::Rgb(0xff, 0x88, 0x00));
+        assert_eq!(scheme.ascii_printable, Color::Green);
+        assert_eq!(scheme.ascii_nonprintable, Color::Gray);
+        assert_eq!((scheme.match_fg, scheme.match_bg), (Color::Black, Color::Rgb(0xff, 0xff, 0)));
+        assert_eq!(scheme.background, Color::Rgb(0x1e, 0x1e, 0x2e));
+        // Fields left out keep their defaults
+        assert_eq!(scheme.ff_byte, ColorScheme::default().ff_byte);
+        assert_eq!(Theme::Custom(scheme).dump_colors().hex_byte, Color::Rgb(0xff, 0x88, 0x00));
+    }
+
+    #[test]
+    fn malformed_theme_files_are_rejected() {
+        let file = NamedTempFile::new().unwrap();
+        for text in ["address = \"not a color\"", "adress = \"blue\"", "address = "] {
+            std::fs::write(file.path(), text).unwrap();
+            assert!(load_color_scheme(file.path()).is_err(), "{}", text);
+        }
+        assert!(load_color_scheme(Path::new("/nonexistent/theme.toml")).is_err());
+    }
+
+    #[test]
+    fn overlapping_highlight_terms_resolve_to_the_later_term() {
+        let (_file, mut app) = open(b"abcdef", 16);
+        let pattern = |text: &str| text.bytes().map(Some).collect::<Vec<_>>();
+        app.add_highlight_term("abcd".to_string(), pattern("abcd"));
+        app.add_highlight_term("cdef".to_string(), pattern("cdef"));
+        let colors: Vec<usize> = app.highlight_terms.iter().map(|term| term.color).collect();
+        assert_eq!(colors, [0, 1]);
+        let backgrounds = |app: &mut App| -> Vec<Option<Color>> {
+            let data = app.read_bytes(0, 16);
+            let line = crate::utils::format_hex_dump(&data, 0, 1, 16, app.highlights(), app.display_options()).remove(0);
+            line.spans[2..8].iter().map(|span| span.style.bg).collect()
+        };
+        let (one, two) = (Some(TERM_COLORS[0]), Some(TERM_COLORS[1]));
+        assert_eq!(backgrounds(&mut app), [one, one, two, two, two, two]);
+        // A search match wins over both terms
+        app.input_buffer = "d".to_string();
+        app.perform_search();
+        let found = Some(app.display_options().colors.match_bg);
+        assert_eq!(backgrounds(&mut app), [one, one, two, found, two, two]);
+        app.search_results.clear();
+        // Removing the later term uncovers the earlier one, and its color is reused
+        app.remove_highlight_term(Some(2));
+        assert_eq!(backgrounds(&mut app), [one, one, one, one, None, None]);
+        app.add_highlight_term("f".to_string(), pattern("f"));
+        assert_eq!(app.highlight_terms[1].color, 1);
+        assert_eq!(backgrounds(&mut app), [one, one, one, one, None, two]);
+        app.remove_highlight_term(Some(5));
+        assert!(app.message_is_error);
+        app.remove_highlight_term(None);
+        assert!(app.highlight_terms.is_empty());
+    }
+
+    #[test]
+    fn typing_a_search_narrows_the_matches_once_typing_pauses() {
+        let (_file, mut app) = open(b"xx abc abd abcd ab", 16);
+        press(&mut app, KeyCode::Char('/'));
+        app.search_type = SearchType::Ascii;
+        let type_and_pause = |app: &mut App, c: char| -> Vec<Range<usize>> {
+            press(app, KeyCode::Char(c));
+            app.run_incremental_search();
+            assert!(app.search_at.is_some()); // Still typing: nothing searched yet
+            app.search_at = Some(Instant::now()); // The pause has passed
+            app.run_incremental_search();
+            assert!(app.search_at.is_none());
+            app.search_results.clone()
+        };
+        assert!(type_and_pause(&mut app, 'a').is_empty()); // Too short to search live
+        assert_eq!(type_and_pause(&mut app, 'b'), [3..5, 7..9, 11..13, 16..18]);
+        assert_eq!(type_and_pause(&mut app, 'c'), [3..6, 11..14]);
+        assert_eq!(app.message.as_deref(), Some("2 match(es)"));
+        // Backspace widens the set again
+        press(&mut app, KeyCode::Backspace);
+        app.search_at = Some(Instant::now());
+        app.run_incremental_search();
+        assert_eq!(app.search_results.len(), 4);
+        // Enter confirms and moves to a match
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert_eq!(app.search_results, std::slice::from_ref(&(7..10)));
+        assert_eq!(app.current_match, Some(0));
+    }
+
+    #[test]
+    fn files_read_on_demand_only_search_on_enter() {
+        let data = b"xx abc abd abcd ab";
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), data).unwrap();
+        let path = file.path().to_string_lossy().into_owned();
+        let mut app = App::new(path, 16, Theme::Dark, PrintablePolicy::Standard, 0, None, 0).unwrap();
+        app.parsed_file = ParsedFile::Lazy(File::open(file.path()).unwrap());
+        press(&mut app, KeyCode::Char('/'));
+        app.search_type = SearchType::Ascii;
+        for c in "abc".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        app.search_at = Some(Instant::now());
+        app.run_incremental_search();
+        assert!(app.search_results.is_empty());
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.search_results.len(), 2);
+    }
+
+    #[test]
+    fn replace_all_overwrites_every_occurrence_in_memory() {
+        let original = b"cat dog cat bird cat";
+        let (file, mut app) = open(original, 16);
+        app.input_buffer = "replace cat/cow".to_string();
+        assert!(app.run_command());
+        assert_eq!(app.read_bytes(0, 32), b"cow dog cow bird cow");
+        assert!(app.dirty);
+        assert_eq!(app.message.as_deref(), Some("Replaced 3 occurrence(s)."));
+        assert_eq!(std::fs::read(file.path()).unwrap(), original); // Saving is left to :w
+        // A wildcard in the replacement keeps that byte
+        app.input_buffer = "replacex 63 6f 77/43 ?? 57".to_string();
+        assert!(app.run_command());
+        assert_eq!(app.read_bytes(0, 32), b"CoW dog CoW bird CoW");
+        // Lengths must match, and nothing changes when they don't
+        app.replace_all(&[Some(b'd'), Some(b'o'), Some(b'g')], &[Some(b'x')]);
+        assert!(app.message_is_error);
+        assert_eq!(
+            app.message.as_deref(),
+            Some("Find and replace lengths differ (3 vs 1 bytes); replacing only overwrites.")
+        );
+        assert_eq!(app.read_bytes(0, 32), b"CoW dog CoW bird CoW");
+        app.replace_all(&[Some(b'z')], &[Some(b'y')]);
+        assert_eq!(app.message.as_deref(), Some("Nothing to replace; the pattern wasn't found."));
+    }
+
+    #[test]
+    fn replace_all_refuses_files_read_on_demand() {
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), b"cat dog cat").unwrap();
+        let path = file.path().to_string_lossy().into_owned();
+        let mut app = App::new(path, 16, Theme::Dark, PrintablePolicy::Standard, 0, None, 0).unwrap();
+        app.replace_all(&[Some(b'c')], &[Some(b'b')]);
+        assert!(app.message_is_error && !app.dirty);
+        assert_eq!(app.read_bytes(0, 16), b"cat dog cat");
+    }
+
+    #[test]
+    fn goto_accepts_a_percentage_of_the_file() {
+        let data: Vec<u8> = (0..1600).map(|i| (i % 251) as u8).collect();
+        let (_file, mut app) = open(&data, 16); // 100 lines
+        let goto = |app: &mut App, input: &str| {
+            press(app, KeyCode::Char(':'));
+            for c in input.chars() {
+                press(app, KeyCode::Char(c));
+            }
+            press(app, KeyCode::Enter);
+            (app.scroll_offset, app.cursor)
+        };
+        assert_eq!(goto(&mut app, "50%"), (50, 800));
+        assert_eq!(goto(&mut app, "0%"), (0, 0));
+        assert_eq!(goto(&mut app, "150%"), (app.max_scroll_offset(), 1599)); // Clamped to the end
+        assert_eq!(goto(&mut app, "12.5%"), (12, 200));
+        // Negative or malformed percentages are refused and the view stays put
+        assert_eq!(goto(&mut app, "-5%"), (12, 200));
+        assert_eq!(app.message.as_deref(), Some("A percentage can't be negative."));
+        assert_eq!(goto(&mut app, "half%"), (12, 200));
+        assert_eq!(app.message.as_deref(), Some("Invalid percentage (e.g. 50%)."));
+        // The hint names the form
+        press(&mut app, KeyCode::Char(':'));
+        assert!(draw(&mut app, 120, 20).contains("50%"));
     }
 }
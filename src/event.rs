@@ -1,29 +1,90 @@
 // src/event.rs
 
 use crate::app::{App, AppMode, SearchType};
-use crossterm::event::{Event as CrosstermEvent, KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+use crate::utils::CopyFormat;
+use crossterm::event::{
+    Event as CrosstermEvent, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent,
+    MouseEventKind,
+};
 
 pub fn handle_event(event: CrosstermEvent, app: &mut App) -> bool {
     match app.mode {
         AppMode::Normal => match event {
-            CrosstermEvent::Key(KeyEvent { code, .. }) => match code {
+            CrosstermEvent::Key(KeyEvent { code, modifiers, .. }) => match code {
                 KeyCode::Char('q') => {
                     app.running = false;
                     false
                 }
-                KeyCode::Up => {
+                KeyCode::Insert => { // Press 'Insert' to overwrite bytes at the cursor
+                    app.message = None; // Clear message
+                    app.start_edit();
+                    true
+                }
+                KeyCode::Left if modifiers.contains(KeyModifiers::ALT) => {
+                    app.message = None; // Clear message
+                    app.navigate_back();
+                    true
+                }
+                KeyCode::Right if modifiers.contains(KeyModifiers::ALT) => {
+                    app.message = None; // Clear message
+                    app.navigate_forward();
+                    true
+                }
+                // Arrows move the byte cursor; the filter view has no cursor and scrolls instead
+                KeyCode::Up if app.filter_lines.is_some() => {
                     app.scroll_up();
                     app.message = None; // Clear message
                     true
                 }
-                KeyCode::Down => {
+                KeyCode::Down if app.filter_lines.is_some() => {
                     app.scroll_down();
                     app.message = None; // Clear message
                     true
                 }
+                // Shift+arrows extend the selection from the cursor
+                KeyCode::Up => {
+                    app.move_cursor_lines(false, modifiers.contains(KeyModifiers::SHIFT));
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::Down => {
+                    app.move_cursor_lines(true, modifiers.contains(KeyModifiers::SHIFT));
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::Left => {
+                    app.move_cursor(false, 1, modifiers.contains(KeyModifiers::SHIFT));
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::Right => {
+                    app.move_cursor(true, 1, modifiers.contains(KeyModifiers::SHIFT));
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::Home | KeyCode::Char('g') => {
+                    app.scroll_to_top();
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::End | KeyCode::Char('G') => {
+                    app.scroll_to_bottom();
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::PageUp => {
+                    app.page_up();
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::PageDown => {
+                    app.page_down();
+                    app.message = None; // Clear message
+                    true
+                }
                 KeyCode::Char('/') => {
                     app.mode = AppMode::Search;
-                    app.search_type = SearchType::Ascii;
+                    app.search_type = app.default_search;
                     app.input_buffer.clear();
                     app.message = None; // Clear message
                     true
@@ -41,6 +102,18 @@ pub fn handle_event(event: CrosstermEvent, app: &mut App) -> bool {
                     app.message = None; // Clear message
                     true
                 }
+                KeyCode::Char('c') => { // Press 'c' to compare typed hex with the top of the view
+                    app.start_live_compare();
+                    app.update_live_compare();
+                    true
+                }
+                KeyCode::Char('e') => { // Press 'e' to search with a regex
+                    app.mode = AppMode::Search;
+                    app.search_type = SearchType::Regex;
+                    app.input_buffer.clear();
+                    app.message = None; // Clear message
+                    true
+                }
                 KeyCode::Char('h') => { // Press 'h' to enter Help mode
                     app.mode = AppMode::Help;
                     app.message = None; // Clear message
@@ -51,16 +124,219 @@ pub fn handle_event(event: CrosstermEvent, app: &mut App) -> bool {
                     app.message = None; // Clear message
                     true
                 }
+                KeyCode::Char('f') => { // Press 'f' to toggle the filter view
+                    app.message = None; // Clear message
+                    app.toggle_filter();
+                    true
+                }
+                KeyCode::Esc => { // Press 'Esc' to return to the full view
+                    app.exit_filter();
+                    app.clear_diff();
+                    app.clear_inspected();
+                    app.clear_selection();
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::Char('v') => { // Press 'v' to reverse the top line's byte order
+                    app.toggle_line_reverse();
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::Char('a') => { // Press 'a' to cycle the ASCII column rendering
+                    app.cycle_ascii_render();
+                    true
+                }
+                KeyCode::Char('y') if modifiers.contains(KeyModifiers::ALT) => { // Alt+y copies the selection as ASCII
+                    app.copy_selection(CopyFormat::Ascii);
+                    true
+                }
+                KeyCode::Char('y') => { // Press 'y' to copy the selection (or cursor byte) as hex
+                    app.copy_selection(CopyFormat::Hex);
+                    true
+                }
+                KeyCode::Char('Y') => { // Press 'Y' to copy the selection as space-separated hex
+                    app.copy_selection(CopyFormat::HexSpaced);
+                    true
+                }
+                KeyCode::Backspace => { // Press Backspace to return from a section to the whole file
+                    app.leave_section();
+                    true
+                }
+                KeyCode::Char('V') => { // Press 'V' to switch between linear and block selection
+                    app.toggle_block_mode();
+                    true
+                }
+                KeyCode::Char('b') => { // Press 'b' to show the selected bytes in the message bar
+                    app.show_selection_bytes();
+                    true
+                }
+                KeyCode::Char('F') => { // Press 'F' to follow the end of a growing file
+                    app.toggle_follow();
+                    true
+                }
+                KeyCode::Char(']') => { // Press ']' to move to the next differing region
+                    app.next_diff();
+                    true
+                }
+                KeyCode::Char('[') => { // Press '[' to move to the previous differing region
+                    app.prev_diff();
+                    true
+                }
+                KeyCode::Char('o') => { // Press 'o' to copy the visible offset range
+                    app.copy_view_range();
+                    true
+                }
+                KeyCode::Char('O') => { // Press 'O' to toggle the overview pane
+                    app.toggle_overview();
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::Char('w') => { // Press 'w' to cycle the line width
+                    app.cycle_bytes_per_line();
+                    true
+                }
+                KeyCode::Char('+') | KeyCode::Char('=') => { // Press '+' to widen lines by a byte
+                    app.adjust_bytes_per_line(true);
+                    true
+                }
+                KeyCode::Char('-') => { // Press '-' to narrow lines by a byte
+                    app.adjust_bytes_per_line(false);
+                    true
+                }
+                KeyCode::Char('n') => { // Press 'n' for the next search match
+                    app.next_match();
+                    true
+                }
+                KeyCode::Char('N') => { // Press 'N' for the previous search match
+                    app.prev_match();
+                    true
+                }
+                KeyCode::Char('l') => { // Press 'l' to decode LEB128 at the top of the view
+                    app.decode_leb128_at_top();
+                    true
+                }
+                KeyCode::Char('R') => { // Press 'R' to jump to a random offset
+                    app.jump_to_random();
+                    true
+                }
+                KeyCode::Char('z') => { // Press 'z' to compact low-byte runs in the ASCII column
+                    app.toggle_compact_runs();
+                    true
+                }
+                KeyCode::Char('d') => { // Press 'd' to cycle hex, octal, decimal and binary bytes
+                    app.cycle_radix();
+                    true
+                }
+                KeyCode::Char('S') => { // Press 'S' to list the sections of an ELF or PE file, or a PNG's chunks
+                    app.open_sections();
+                    true
+                }
+                KeyCode::Char('m') => { // Press 'm' to bookmark the cursor (again to remove it)
+                    app.toggle_bookmark();
+                    true
+                }
+                KeyCode::Char('B') => { // Press 'B' to toggle the sidebar of pinned offsets (bookmarks)
+                    app.toggle_pins();
+                    true
+                }
+                KeyCode::Char('J') => { // Press 'J' to jump to the next pin after the cursor
+                    app.next_pin();
+                    true
+                }
+                KeyCode::Char('\'') => { // Press ' to list the bookmarks
+                    app.open_bookmarks();
+                    true
+                }
+                KeyCode::Char('X') => { // Press 'X' to overlay the second file's bytes (again to show it beside)
+                    app.toggle_overlay();
+                    true
+                }
+                KeyCode::Char('K') => { // Press 'K' for checksums of the selection or the whole file
+                    app.show_checksums();
+                    true
+                }
+                KeyCode::Char('k') => { // Press 'k' to cycle the checksum column
+                    app.cycle_checksum();
+                    true
+                }
+                KeyCode::Char('s') => { // Press 's' to toggle the statistics panel
+                    app.toggle_stats();
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::Char('I') => { // Press 'I' to toggle the byte inspector
+                    app.toggle_inspector();
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::Char('E') => { // Press 'E' to flip the inspector's byte order
+                    app.toggle_endianness();
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::Char('p') => { // Press 'p' to search for the clipboard contents
+                    app.search_clipboard();
+                    true
+                }
+                KeyCode::Char('P') => { // Press 'P' to compare the clipboard with the view
+                    app.compare_clipboard();
+                    true
+                }
+                KeyCode::Char('i') => { // Press 'i' to invert the theme's colors
+                    app.toggle_invert();
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::Char('u') => { // Press 'u' to toggle uppercase hex digits
+                    app.toggle_uppercase_hex();
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::Char('T') => { // Press 'T' to toggle the text records view
+                    app.toggle_text_mode();
+                    app.message = None; // Clear message
+                    true
+                }
+                KeyCode::Char('A') => { // Press 'A' to toggle scroll acceleration
+                    app.toggle_scroll_acceleration();
+                    true
+                }
+                KeyCode::Char('r') => { // Press 'r' to toggle the raw view of a uniform file
+                    if app.uniform_byte.is_some() {
+                        app.show_raw = !app.show_raw;
+                    }
+                    app.message = None; // Clear message
+                    true
+                }
                 _ => true,
             },
-            CrosstermEvent::Mouse(MouseEvent { kind, .. }) => match kind {
+            CrosstermEvent::Mouse(MouseEvent { kind, column, row, .. }) => match kind {
+                MouseEventKind::Down(MouseButton::Left) => {
+                    // A clicked pin's message names it, so only other clicks clear the message
+                    if !app.click_pins(column, row)
+                        && (app.click_metadata(column, row)
+                            || app.click_overview(column, row)
+                            || app.press_content(column, row))
+                    {
+                        app.message = None; // Clear message
+                    }
+                    true
+                }
+                MouseEventKind::Drag(MouseButton::Left) => {
+                    app.drag_content(column, row);
+                    true
+                }
                 MouseEventKind::ScrollUp => {
-                    app.scroll_up();
+                    if !app.scroll_overview(column, row, false) {
+                        app.scroll_up();
+                    }
                     app.message = None; // Clear message
                     true
                 }
                 MouseEventKind::ScrollDown => {
-                    app.scroll_down();
+                    if !app.scroll_overview(column, row, true) {
+                        app.scroll_down();
+                    }
                     app.message = None; // Clear message
                     true
                 }
@@ -77,26 +353,39 @@ pub fn handle_event(event: CrosstermEvent, app: &mut App) -> bool {
                                 // Message is already set in perform_search
                             } else {
                                 app.perform_search();
+                                if !app.search_results.is_empty() {
+                                    app.next_match();
+                                }
                             }
                         }
                         AppMode::Goto => {
                             if app.input_buffer.is_empty() {
                                 // Message is already set in jump_to_offset
-                            } else {
+                            } else if !app.run_command() {
                                 app.jump_to_offset();
                             }
                         }
                         _ => {}
                     }
-                    app.mode = AppMode::Normal;
+                    // A command may have switched modes itself, e.g. `:edit`
+                    if matches!(app.mode, AppMode::Search | AppMode::Goto) {
+                        app.mode = AppMode::Normal;
+                    }
+                    true
+                }
+                KeyCode::Tab if matches!(app.mode, AppMode::Search) => {
+                    app.toggle_case_insensitive();
+                    app.search_query_edited();
                     true
                 }
                 KeyCode::Char(c) => {
                     app.input_buffer.push(c);
+                    app.search_query_edited();
                     true
                 }
                 KeyCode::Backspace => {
                     app.input_buffer.pop();
+                    app.search_query_edited();
                     true
                 }
                 KeyCode::Esc => {
@@ -108,9 +397,27 @@ pub fn handle_event(event: CrosstermEvent, app: &mut App) -> bool {
             },
             _ => true,
         },
-        AppMode::Help => match event {
+        AppMode::Edit => match event {
             CrosstermEvent::Key(KeyEvent { code, .. }) => match code {
-                KeyCode::Char('h') | KeyCode::Esc => { // Press 'h' or 'Esc' to exit Help mode
+                KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down => {
+                    app.edit_low_nibble = false;
+                    match code {
+                        KeyCode::Left => app.move_cursor(false, 1, false),
+                        KeyCode::Right => app.move_cursor(true, 1, false),
+                        KeyCode::Up => app.move_cursor(false, app.bytes_per_line, false),
+                        _ => app.move_cursor(true, app.bytes_per_line, false),
+                    }
+                    true
+                }
+                KeyCode::Tab => {
+                    app.toggle_edit_pane();
+                    true
+                }
+                KeyCode::Char(c) => {
+                    app.edit_char(c);
+                    true
+                }
+                KeyCode::Esc | KeyCode::Insert => {
                     app.mode = AppMode::Normal;
                     app.message = None; // Clear message
                     true
@@ -119,5 +426,95 @@ pub fn handle_event(event: CrosstermEvent, app: &mut App) -> bool {
             },
             _ => true,
         },
+        AppMode::Compare => match event {
+            CrosstermEvent::Key(KeyEvent { code, .. }) => match code {
+                KeyCode::Enter => {
+                    // Keep the highlights; Esc in Normal mode clears them
+                    app.mode = AppMode::Normal;
+                    true
+                }
+                KeyCode::Char(c) => {
+                    app.input_buffer.push(c);
+                    app.update_live_compare();
+                    true
+                }
+                KeyCode::Backspace => {
+                    app.input_buffer.pop();
+                    app.update_live_compare();
+                    true
+                }
+                KeyCode::Esc => {
+                    app.clear_diff();
+                    app.mode = AppMode::Normal;
+                    app.message = None; // Clear message
+                    true
+                }
+                _ => true,
+            },
+            _ => true,
+        },
+        AppMode::Sections => match event {
+            CrosstermEvent::Key(KeyEvent { code, .. }) => match code {
+                KeyCode::Up => {
+                    app.move_section(false);
+                    true
+                }
+                KeyCode::Down => {
+                    app.move_section(true);
+                    true
+                }
+                KeyCode::Enter => {
+                    app.jump_to_section();
+                    true
+                }
+                KeyCode::Char('v') => { // Press 'v' to view the highlighted section alone
+                    app.enter_section();
+                    true
+                }
+                KeyCode::Esc | KeyCode::Char('S') => {
+                    app.mode = AppMode::Normal;
+                    true
+                }
+                _ => true,
+            },
+            _ => true,
+        },
+        AppMode::Bookmarks => match event {
+            CrosstermEvent::Key(KeyEvent { code, .. }) => match code {
+                KeyCode::Up => {
+                    app.move_bookmark(false);
+                    true
+                }
+                KeyCode::Down => {
+                    app.move_bookmark(true);
+                    true
+                }
+                KeyCode::Enter => {
+                    app.jump_to_bookmark(app.bookmark_index);
+                    true
+                }
+                KeyCode::Char('d') | KeyCode::Delete => {
+                    app.remove_listed_bookmark();
+                    true
+                }
+                KeyCode::Esc | KeyCode::Char('\'') => {
+                    app.mode = AppMode::Normal;
+                    true
+                }
+                _ => true,
+            },
+            _ => true,
+        },
+        AppMode::Help => match event {
+            CrosstermEvent::Key(KeyEvent {
+                code: KeyCode::Char('h') | KeyCode::Esc,
+                ..
+            }) => { // Press 'h' or 'Esc' to exit Help mode
+                app.mode = AppMode::Normal;
+                app.message = None; // Clear message
+                true
+            }
+            _ => true,
+        },
     }
 }
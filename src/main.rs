@@ -3,97 +3,468 @@
 mod app;
 mod event;
 mod parsers;
+mod symbols;
 mod ui;
 mod utils;
 
-use crate::app::{App, Theme};
+use crate::app::{
+    custom_theme_path, load_color_scheme, App, ContentAlign, SearchType, Theme,
+    DEFAULT_PREFETCH_LINES, DEFAULT_READOUT_LIMIT,
+};
 use crate::event::handle_event;
 use crate::ui::draw_ui;
+use crate::utils::{parse_number, AddressStyle, LineChecksum, PrintablePolicy};
 
 use clap::Parser;
 use crossterm::{
+    cursor::Show,
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
-use std::{error::Error, io, panic};
+use std::{error::Error, io, panic, path::Path, time::Duration};
 
 /// Command-line arguments
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    /// File path to view
+    /// File path to view, or `-` to read from stdin (e.g. `cat dump.bin | file_viewer -`)
     file_path: String,
 
+    /// Second file to compare side by side with the first
+    compare_path: Option<String>,
+
     /// Number of bytes per line in the hex view
-    #[arg(short, long, default_value_t = 16)]
-    bytes_per_line: usize,
+    #[arg(short, long, default_value_t = 16, value_parser = clap::value_parser!(u64).range(1..))]
+    bytes_per_line: u64,
 
-    /// Theme: light or dark
+    /// Theme: light, dark, or custom to read colors from ~/.config/hex-viewer/theme.toml
     #[arg(short, long, default_value = "dark")]
     theme: String,
+
+    /// Printable set for the ASCII column: standard, strict, latin1, or a
+    /// list of hex byte ranges such as `20-7e,a1-ff`
+    #[arg(long, default_value = "standard")]
+    printable: String,
+
+    /// Placement of the hex dump: left, center or right
+    #[arg(long, default_value = "left")]
+    align: String,
+
+    /// Total width (in columns) the hex dump is centered or right-aligned
+    /// within; defaults to the width of the content pane
+    #[arg(long)]
+    content_width: Option<usize>,
+
+    /// Search type entered with '/': ascii, hex or regex ('x' always searches
+    /// hex, 'e' always searches with a regex)
+    #[arg(long, default_value = "ascii")]
+    default_search: String,
+
+    /// Fold ASCII letters in ASCII searches (toggle with Tab while searching)
+    #[arg(short = 'i', long)]
+    ignore_case: bool,
+
+    /// Only report matches that start at a multiple of this many bytes
+    /// (e.g. 16 for values in 16-byte records)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    search_align: Option<u64>,
+
+    /// Files larger than this are memory-mapped (or read on demand where mapping fails)
+    /// instead of loaded into memory (e.g. `512K`, `10M`; 0 never loads them)
+    #[arg(long, default_value = "10M", value_parser = parse_size_arg)]
+    lazy_threshold: u64,
+
+    /// Lines read ahead of the visible window when a large file is read on
+    /// demand, so the next scroll is served from memory (0 disables read-ahead)
+    #[arg(long, default_value_t = DEFAULT_PREFETCH_LINES)]
+    prefetch_lines: usize,
+
+    /// Show hex digits in upper case (toggle at runtime with 'u')
+    #[arg(long)]
+    uppercase: bool,
+
+    /// Largest selection, in bytes, that 'b' shows as hex in the message bar
+    #[arg(long, default_value_t = DEFAULT_READOUT_LIMIT)]
+    readout_limit: usize,
+
+    /// Address of file offset 0 in the gutter, for memory dumps (hex, e.g. `400000`);
+    /// Goto addresses count from it too
+    #[arg(long, default_value = "0", value_parser = parse_hex_arg)]
+    base: u64,
+
+    /// Hex digits in the address gutter; by default just enough for the file
+    /// size (at least 6)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..=16))]
+    addr_width: Option<u64>,
+
+    /// Bytes per group in the hex column, separated by an extra space like `xxd -g`
+    /// (0 doesn't group)
+    #[arg(short, long, default_value_t = 0)]
+    group_size: usize,
+
+    /// Address gutter style: linear, or segmented for real-mode `SSSS:OOOO`
+    #[arg(long, default_value = "linear")]
+    address_style: String,
+
+    /// Per-line checksum column: off, xor, or sum (mod 256); cycle at runtime with 'k'
+    #[arg(long, default_value = "off")]
+    checksum: String,
+
+    /// Seed for random offsets ('R'), so a sampling session can be repeated
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Accelerate scrolling while an arrow key is held (toggle at runtime with 'A')
+    #[arg(long)]
+    scroll_accel: bool,
+
+    /// Seconds before a message clears itself (4 by default); 0 keeps messages until the next key
+    #[arg(long, value_parser = parse_seconds_arg)]
+    message_timeout: Option<Duration>,
+
+    /// Keep error messages until dismissed, even when info messages time out
+    #[arg(long)]
+    sticky_errors: bool,
+
+    /// Fit bytes per line (a multiple of 8) to the window width, overriding -b
+    #[arg(long)]
+    auto_width: bool,
+
+    /// Copy a file to `<file>.bak` before saving edits over it with `:w`
+    #[arg(long)]
+    backup: bool,
+
+    /// Seconds without an edit before unsaved edits are copied to `<file>.autosave`;
+    /// the file itself is only written by `:w`. Off unless given.
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    autosave: Option<u64>,
+
+    /// Reload the file when another program changes it
+    #[arg(long)]
+    watch: bool,
+
+    /// Linker map or symbol list (`name address`, `nm` output, GNU ld map);
+    /// Goto then accepts symbol names
+    #[arg(long)]
+    map: Option<String>,
+
+    /// Address that corresponds to file offset 0 for symbols from --map.
+    /// Accepts decimal (optionally with a K/M/G suffix) or 0x-prefixed hex.
+    #[arg(long, default_value = "0", value_parser = parse_size_arg)]
+    map_base: u64,
+
+    /// Start of the byte range to view; the range is shown as if it were the
+    /// whole file. Accepts decimal (optionally with a K/M/G suffix) or 0x-prefixed hex.
+    #[arg(long, default_value = "0", value_parser = parse_size_arg)]
+    offset: u64,
+
+    /// Length of the byte range to view (clamped to the available bytes).
+    /// Accepts decimal (optionally with a K/M/G suffix) or 0x-prefixed hex.
+    #[arg(long, value_parser = parse_size_arg)]
+    length: Option<u64>,
+
+    /// Show the holes of a sparse file (regions with no storage that read as
+    /// zeros) in gray, so they stand out from stored zeros. Linux only.
+    #[arg(long)]
+    holes: bool,
+
+    /// Render inline instead of on the alternate screen, so the last frame
+    /// stays in the terminal scrollback after quitting. Earlier frames are
+    /// overwritten in place, so there is no redraw history to scroll back to.
+    #[arg(long)]
+    no_alt_screen: bool,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+    // Parse command-line arguments
+    let cli = Cli::parse();
+    let alt_screen = !cli.no_alt_screen;
+
     // Set a panic hook to restore terminal state in case of panic
-    panic::set_hook(Box::new(|info| {
+    panic::set_hook(Box::new(move |info| {
         let mut stdout = io::stdout();
         let _ = disable_raw_mode();
-        let _ = execute!(stdout, LeaveAlternateScreen, DisableMouseCapture);
+        if alt_screen {
+            let _ = execute!(stdout, LeaveAlternateScreen);
+        }
+        // Without the alternate screen nothing else restores the hidden cursor
+        let _ = execute!(stdout, DisableMouseCapture, Show);
         eprintln!("Application panicked: {:?}", info);
     }));
 
-    // Parse command-line arguments
-    let cli = Cli::parse();
-
-    // Determine theme
+    // Determine theme; a custom theme that doesn't load falls back to Dark with a message
+    let mut theme_error = None;
     let theme = match cli.theme.to_lowercase().as_str() {
         "light" => Theme::Light,
         "dark" => Theme::Dark,
+        "custom" => {
+            let loaded = custom_theme_path()
+                .ok_or_else(|| "HOME is not set".to_string())
+                .and_then(|path| load_color_scheme(&path).map_err(|e| format!("{}: {}", path.display(), e)));
+            match loaded {
+                Ok(scheme) => Theme::Custom(scheme),
+                Err(e) => {
+                    theme_error = Some(format!("Cannot load the custom theme ({}); using Dark.", e));
+                    Theme::Dark
+                }
+            }
+        }
         _ => {
             eprintln!("Unknown theme '{}'. Falling back to Dark theme.", cli.theme);
             Theme::Dark
         }
     };
 
+    // Determine which bytes the ASCII column shows as characters
+    let printable = match cli.printable.to_lowercase().as_str() {
+        "standard" => PrintablePolicy::Standard,
+        "strict" => PrintablePolicy::Strict,
+        "latin1" => PrintablePolicy::Latin1,
+        spec => PrintablePolicy::from_ranges(spec).unwrap_or_else(|| {
+            eprintln!("Unknown printable set '{}'. Falling back to standard.", cli.printable);
+            PrintablePolicy::Standard
+        }),
+    };
+
+    // Determine how line addresses are shown
+    let address_style = match cli.address_style.to_lowercase().as_str() {
+        "linear" => AddressStyle::Linear,
+        "segmented" => AddressStyle::Segmented,
+        _ => {
+            eprintln!("Unknown address style '{}'. Falling back to linear.", cli.address_style);
+            AddressStyle::Linear
+        }
+    };
+
+    // Determine the per-line checksum column
+    let checksum = match cli.checksum.to_lowercase().as_str() {
+        "off" => None,
+        "xor" => Some(LineChecksum::Xor),
+        "sum" => Some(LineChecksum::Sum),
+        _ => {
+            eprintln!("Unknown checksum '{}'. Falling back to off.", cli.checksum);
+            None
+        }
+    };
+
+    // Determine where the dump sits within the content width
+    let content_align = match cli.align.to_lowercase().as_str() {
+        "left" => ContentAlign::Left,
+        "center" => ContentAlign::Center,
+        "right" => ContentAlign::Right,
+        _ => {
+            eprintln!("Unknown alignment '{}'. Falling back to left.", cli.align);
+            ContentAlign::Left
+        }
+    };
+
+    // Determine which search '/' starts
+    let default_search = match cli.default_search.to_lowercase().as_str() {
+        "ascii" => SearchType::Ascii,
+        "hex" => SearchType::Hex,
+        "regex" => SearchType::Regex,
+        _ => {
+            eprintln!("Unknown search type '{}'. Falling back to ascii.", cli.default_search);
+            SearchType::Ascii
+        }
+    };
+
+    // Load symbols before touching the terminal so errors are readable
+    let symbols = match &cli.map {
+        Some(path) => symbols::load_map_file(path)?,
+        None => Default::default(),
+    };
+
     // Set up terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    if alt_screen {
+        execute!(stdout, EnterAlternateScreen)?;
+    }
+    execute!(stdout, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // Initialize app state
-    let mut app = match App::new(cli.file_path, cli.bytes_per_line, theme) {
+    let mut app = match App::new(
+        cli.file_path,
+        cli.bytes_per_line as usize,
+        theme,
+        printable,
+        cli.offset,
+        cli.length.map(|len| len as usize),
+        cli.lazy_threshold as usize,
+    ) {
         Ok(app) => app,
         Err(e) => {
+            // Restore terminal before reporting, so the message isn't lost with the alternate screen
+            let _ = restore_terminal(&mut terminal, alt_screen);
             eprintln!("Failed to initialize application: {}", e);
-            // Restore terminal before exiting
-            disable_raw_mode()?;
-            execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
             return Err(e);
         }
     };
 
+    app.default_search = default_search;
+    app.content_align = content_align;
+    app.content_width = cli.content_width;
+    app.case_insensitive = cli.ignore_case;
+    app.search_alignment = cli.search_align.map(|alignment| alignment as usize).filter(|&a| a > 1);
+    app.prefetch.lines = cli.prefetch_lines;
+    app.scroll_acceleration = cli.scroll_accel;
+    app.uppercase_hex = cli.uppercase;
+    app.readout_limit = cli.readout_limit;
+    if let Theme::Custom(scheme) = app.theme {
+        app.custom_scheme = Some(scheme);
+    }
+    if let Some(message) = theme_error {
+        app.set_error(message);
+    }
+    app.auto_width = cli.auto_width;
+    app.backup_on_save = cli.backup;
+    app.autosave_after = cli.autosave.map(Duration::from_secs);
+    app.group_size = cli.group_size;
+    app.address_width = cli.addr_width.map(|width| width as usize);
+    app.base_address = cli.base as usize;
+    app.checksum = checksum;
+    app.address_style = address_style;
+    app.symbols = symbols;
+    if let Some(timeout) = cli.message_timeout {
+        app.message_timeout = (!timeout.is_zero()).then_some(timeout);
+    }
+    app.sticky_errors = cli.sticky_errors;
+    app.symbol_base = cli.map_base as usize;
+    if let Some(seed) = cli.seed {
+        app.rng = rand::SeedableRng::seed_from_u64(seed);
+    }
+    if cli.holes {
+        app.scan_holes();
+    }
+    if cli.watch {
+        if let Err(e) = app.watch() {
+            app.set_error(format!("Cannot watch {} for changes: {}", app.file_path, e));
+        }
+    }
+    // Restore the bookmarks saved next to the file; a sidecar that doesn't load is left alone
+    let mut save_bookmarks = true;
+    if let Some(path) = app.bookmark_path().filter(|path| Path::new(path).exists()) {
+        if let Err(e) = app.load_bookmarks(&path) {
+            app.set_error(format!("Ignoring bookmarks in {} (left unchanged): {}", path, e));
+            save_bookmarks = false;
+        }
+    }
+    if let Some(path) = cli.compare_path {
+        if let Err(e) = app.open_second_file(path, cli.lazy_threshold as usize) {
+            let _ = restore_terminal(&mut terminal, alt_screen);
+            eprintln!("Failed to open the file to compare: {}", e);
+            return Err(e);
+        }
+    }
+
     // Run application
     let res = run_app(&mut terminal, &mut app);
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    // Restore terminal (even if the app or the restore itself failed part-way)
+    let restored = restore_terminal(&mut terminal, alt_screen);
 
     // Handle errors
     if let Err(err) = res {
         eprintln!("Error: {}", err);
     }
 
+    // Keep the bookmarks for next time, without creating a sidecar for a file that never had any
+    app.leave_section(); // Bookmarks made in a section viewed alone are kept at their file offsets
+    if let Some(path) = app.bookmark_path().filter(|_| save_bookmarks) {
+        if !app.bookmarks.is_empty() || Path::new(&path).exists() {
+            if let Err(e) = app.save_bookmarks(&path) {
+                eprintln!("Cannot save bookmarks to {}: {}", path, e);
+            }
+        }
+    }
+
+    restored
+}
+
+/// Parses a hex command-line number with or without a 0x prefix
+fn parse_hex_arg(value: &str) -> Result<u64, String> {
+    let digits = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")).unwrap_or(value);
+    u64::from_str_radix(digits, 16).map_err(|e| format!("invalid hex number '{}': {}", value, e))
+}
+
+/// Parses a decimal or 0x-prefixed hexadecimal command-line number. Decimal
+/// numbers may end in a K, M or G suffix (powers of 1024), e.g. `512K`.
+fn parse_size_arg(value: &str) -> Result<u64, String> {
+    let (digits, multiplier) = match value.char_indices().last() {
+        Some((i, c)) if !value.starts_with("0x") && !value.starts_with("0X") => {
+            match c.to_ascii_uppercase() {
+                'K' => (&value[..i], 1 << 10),
+                'M' => (&value[..i], 1 << 20),
+                'G' => (&value[..i], 1 << 30),
+                _ => (value, 1),
+            }
+        }
+        _ => (value, 1),
+    };
+    parse_number(digits)
+        .map_err(|e| format!("invalid number '{}': {}", value, e))?
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("number '{}' is too large", value))
+}
+
+/// Parses a non-negative number of seconds, which may have a fraction, e.g. `2.5`
+fn parse_seconds_arg(value: &str) -> Result<Duration, String> {
+    let seconds: f64 = value.parse().map_err(|e| format!("invalid number of seconds '{}': {}", value, e))?;
+    Duration::try_from_secs_f64(seconds).map_err(|e| format!("'{}' is not a usable number of seconds: {}", value, e))
+}
+
+/// One step of restoring the terminal: what it does, for error messages, and how
+type RestoreStep<T> = (&'static str, fn(&mut T) -> io::Result<()>);
+
+/// Leaves raw mode, mouse capture and (if it was entered) the alternate screen.
+/// Every step is attempted even if an earlier one fails; failures are logged to
+/// stderr and the first one is returned.
+fn restore_terminal(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    alt_screen: bool,
+) -> Result<(), Box<dyn Error>> {
+    run_restore_steps(terminal, &restore_steps(alt_screen))
+}
+
+/// Lists the steps that restore the terminal, in order
+fn restore_steps(alt_screen: bool) -> [RestoreStep<Terminal<CrosstermBackend<std::io::Stdout>>>; 4] {
+    let leave: RestoreStep<Terminal<CrosstermBackend<std::io::Stdout>>> = if alt_screen {
+        ("leave the alternate screen", |terminal| execute!(terminal.backend_mut(), LeaveAlternateScreen))
+    } else {
+        ("park the cursor", park_cursor)
+    };
+    [
+        ("disable raw mode", |_| disable_raw_mode()),
+        leave,
+        ("disable mouse capture", |terminal| execute!(terminal.backend_mut(), DisableMouseCapture)),
+        ("show the cursor", |terminal| terminal.show_cursor()),
+    ]
+}
+
+/// Runs every step on `target`, even after one fails; failures are logged to
+/// stderr and the first one is returned
+fn run_restore_steps<T>(target: &mut T, steps: &[RestoreStep<T>]) -> Result<(), Box<dyn Error>> {
+    let mut first_error: Option<Box<dyn Error>> = None;
+    for (step, run) in steps {
+        if let Err(e) = run(target) {
+            eprintln!("Failed to {} while restoring the terminal: {}", step, e);
+            first_error.get_or_insert(e.into());
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Moves the cursor below the last inline frame so the shell prompt doesn't overwrite it
+fn park_cursor(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>) -> io::Result<()> {
+    let size = terminal.size()?;
+    terminal.set_cursor_position((0, size.height.saturating_sub(1)))?;
+    println!();
     Ok(())
 }
 
@@ -102,15 +473,69 @@ fn run_app(
     app: &mut App,
 ) -> Result<(), Box<dyn Error>> {
     while app.running {
+        app.expire_message();
+        app.poll_watch();
+        app.poll_growth();
+        app.poll_autosave();
+        app.run_incremental_search();
         terminal.draw(|f| draw_ui(f, app))?;
+        app.run_pending_digest();
 
-        if crossterm::event::poll(std::time::Duration::from_millis(100))? {
+        if crossterm::event::poll(Duration::from_millis(100))? {
             let event = crossterm::event::read()?;
             if !handle_event(event, app) {
                 break;
             }
             app.clamp_scroll_offset(); // Ensure scroll_offset is valid
+            app.clear_stale_line_reverse();
+            app.update_follow();
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn message_timeouts_must_be_finite_and_non_negative() {
+        assert_eq!(parse_seconds_arg("2.5"), Ok(Duration::from_millis(2500)));
+        assert_eq!(parse_seconds_arg("0"), Ok(Duration::ZERO));
+        for value in ["1e20", "inf", "NaN", "-1", "soon"] {
+            assert!(parse_seconds_arg(value).is_err(), "{}", value);
+        }
+        assert!(Cli::try_parse_from(["file_viewer", "--message-timeout", "1e20", "file"]).is_err());
+    }
+
+    #[test]
+    fn restoring_the_terminal_attempts_every_step_after_a_failure() {
+        let names = |alt_screen| restore_steps(alt_screen).map(|(name, _)| name);
+        assert_eq!(names(true), ["disable raw mode", "leave the alternate screen", "disable mouse capture", "show the cursor"]);
+        assert_eq!(names(false)[1], "park the cursor");
+
+        // Steps that record themselves instead of touching the terminal, the first two failing
+        let steps: [RestoreStep<Vec<&str>>; 4] = [
+            ("flush the screen", |log| {
+                log.push("flush the screen");
+                Err(io::Error::other("first failure"))
+            }),
+            ("disable raw mode", |log| {
+                log.push("disable raw mode");
+                Err(io::Error::other("second failure"))
+            }),
+            ("leave the alternate screen", |log| {
+                log.push("leave the alternate screen");
+                Ok(())
+            }),
+            ("show the cursor", |log| {
+                log.push("show the cursor");
+                Ok(())
+            }),
+        ];
+        let mut log = Vec::new();
+        let result = run_restore_steps(&mut log, &steps);
+        assert_eq!(result.unwrap_err().to_string(), "first failure");
+        assert_eq!(log, ["flush the screen", "disable raw mode", "leave the alternate screen", "show the cursor"]);
+    }
+}
@@ -1,16 +1,40 @@
-use super::{FileParser, ParsedFile};
-use std::fs::File;
-// Use a crate like goblin for parsing ELF files
+// src/parsers/elf.rs
+
+use super::{FileParser, ParsedFile, SectionInfo};
+use goblin::elf::section_header::SHT_NOBITS;
 use goblin::elf::Elf;
+use std::fs::File;
+use std::io::Read;
 
+/// ELF parser that reads the file into memory and lists its sections.
+/// Files that fail to parse as ELF are still shown as plain bytes.
 pub struct ElfParser;
 
 impl FileParser for ElfParser {
     fn parse(file: &mut File) -> Result<ParsedFile, Box<dyn std::error::Error>> {
         let mut buffer = Vec::new();
         file.read_to_end(&mut buffer)?;
-        let elf = Elf::parse(&buffer)?;
-        // Process ELF data and store in ParsedFile::Elf variant
-        Ok(ParsedFile::Elf(/* ELF data */))
+        match parse_sections(&buffer) {
+            Some(sections) => Ok(ParsedFile::Elf { data: buffer, sections }),
+            None => Ok(ParsedFile::Generic(buffer)),
+        }
     }
 }
+
+/// Lists the sections of the ELF file in `data`, or None if it doesn't parse as ELF
+pub fn parse_sections(data: &[u8]) -> Option<Vec<SectionInfo>> {
+    let elf = Elf::parse(data).ok()?;
+    // Skip the null section and sections like .bss that take no space in the file
+    let sections = elf
+        .section_headers
+        .iter()
+        .filter(|header| header.sh_type != SHT_NOBITS && header.sh_size > 0)
+        .map(|header| SectionInfo {
+            name: elf.shdr_strtab.get_at(header.sh_name).unwrap_or("?").to_string(),
+            offset: header.sh_offset as usize,
+            size: header.sh_size as usize,
+            address: header.sh_addr as usize,
+        })
+        .collect();
+    Some(sections)
+}
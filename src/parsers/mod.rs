@@ -1,9 +1,55 @@
 // src/parsers/mod.rs
 
+pub mod elf;
 pub mod generic;
+pub mod pe;
+pub mod png;
 
-use crate::utils::read_file_chunk;
+use crate::utils::read_file_range;
+use memmap2::Mmap;
+use png::PngChunk;
 use std::fs::File;
+use std::io::{self, Read, Seek};
+
+/// Number of evenly spaced samples taken when checking a lazy file for uniformity
+const UNIFORM_SAMPLES: usize = 64;
+/// Bytes read per sample when checking a lazy file for uniformity
+const UNIFORM_SAMPLE_LEN: usize = 4096;
+
+/// Bytes read from the start of a file to detect its format
+pub const MAGIC_LEN: usize = 8;
+
+/// A section of an executable that has bytes in the file
+pub struct SectionInfo {
+    pub name: String,
+    pub offset: usize,  // File offset of the section's first byte
+    pub size: usize,    // Bytes the section takes in the file
+    pub address: usize, // Virtual address the section is loaded at
+}
+
+/// What a format's parser found in a file too large to read into memory
+#[derive(Default)]
+pub struct Structure {
+    pub sections: Vec<SectionInfo>, // An executable's sections, in section table order
+    pub chunks: Vec<PngChunk>,      // A PNG file's chunks, in file order
+    pub entry_point: Option<usize>, // Virtual address execution starts at, for formats that record it
+}
+
+impl Structure {
+    /// Parses `data` in place with the parser for the format its magic bytes
+    /// name; files that don't parse have no structure
+    pub fn parse(data: &[u8]) -> Self {
+        match detect_format(&data[..data.len().min(MAGIC_LEN)]) {
+            FileFormat::Elf => Structure { sections: elf::parse_sections(data).unwrap_or_default(), ..Self::default() },
+            FileFormat::Pe => match pe::parse_sections(data) {
+                Some((sections, entry_point)) => Structure { sections, entry_point: Some(entry_point), ..Self::default() },
+                None => Structure::default(),
+            },
+            FileFormat::Png => Structure { chunks: png::parse_chunks(data).unwrap_or_default(), ..Self::default() },
+            _ => Structure::default(),
+        }
+    }
+}
 
 /// Trait for parsing different file types
 pub trait FileParser {
@@ -13,7 +59,12 @@ pub trait FileParser {
 /// Enum representing the parsed file content
 pub enum ParsedFile {
     Generic(Vec<u8>),
+    Elf { data: Vec<u8>, sections: Vec<SectionInfo> }, // An ELF file read into memory, with its section table
+    Pe { data: Vec<u8>, sections: Vec<SectionInfo>, entry_point: usize }, // A PE file read into memory; the entry point is a virtual address
+    Png { data: Vec<u8>, chunks: Vec<PngChunk> }, // A PNG file read into memory, with its chunk list
     Lazy(File), // For lazy loading large files
+    Mmap { map: Mmap, file: File, structure: Structure }, // Large files mapped into memory; the OS pages them in as they are touched
+    Window { file: File, base: u64, len: usize }, // A byte range of a larger file or device, exposed as the whole file
     // Future variants for other file types
 }
 
@@ -21,33 +72,283 @@ impl ParsedFile {
     /// Returns a byte slice of the file data
     pub fn data(&self) -> &[u8] {
         match self {
-            ParsedFile::Generic(data) => data.as_slice(),
+            ParsedFile::Generic(data)
+            | ParsedFile::Elf { data, .. }
+            | ParsedFile::Pe { data, .. }
+            | ParsedFile::Png { data, .. } => data.as_slice(),
+            ParsedFile::Mmap { map, .. } => map,
             ParsedFile::Lazy(_) => &[], // For Lazy loading, data is fetched via get_chunk
+            ParsedFile::Window { .. } => &[], // Windows are read on demand like Lazy files
             // Handle other variants
         }
     }
 
+    /// Returns the file data for editing, or None when it isn't held in memory
+    pub fn data_mut(&mut self) -> Option<&mut [u8]> {
+        match self {
+            ParsedFile::Generic(data)
+            | ParsedFile::Elf { data, .. }
+            | ParsedFile::Pe { data, .. }
+            | ParsedFile::Png { data, .. } => Some(data.as_mut_slice()),
+            ParsedFile::Lazy(_) | ParsedFile::Mmap { .. } | ParsedFile::Window { .. } => None,
+        }
+    }
+
+    /// Returns the sections of an executable, in section table order; empty for other files
+    pub fn sections(&self) -> &[SectionInfo] {
+        match self {
+            ParsedFile::Elf { sections, .. } | ParsedFile::Pe { sections, .. } => sections,
+            ParsedFile::Mmap { structure, .. } => &structure.sections,
+            _ => &[],
+        }
+    }
+
+    /// Returns the chunks of a PNG file in file order; empty for other files
+    pub fn chunks(&self) -> &[PngChunk] {
+        match self {
+            ParsedFile::Png { chunks, .. } => chunks,
+            ParsedFile::Mmap { structure, .. } => &structure.chunks,
+            _ => &[],
+        }
+    }
+
+    /// Returns the virtual address execution starts at, for formats that record it
+    pub fn entry_point(&self) -> Option<usize> {
+        match self {
+            ParsedFile::Pe { entry_point, .. } => Some(*entry_point),
+            ParsedFile::Mmap { structure, .. } => structure.entry_point,
+            _ => None,
+        }
+    }
+
+    /// Parses a mapped file's structure (sections or chunks) from the mapping, once the
+    /// file is known to still cover it. Other files were parsed as they were read.
+    pub fn parse_structure(&mut self) -> io::Result<()> {
+        self.check_mapping()?;
+        if let ParsedFile::Mmap { map, structure, .. } = self {
+            *structure = Structure::parse(map);
+        }
+        Ok(())
+    }
+
+    /// Checks that a mapped file still covers its whole mapping. Touching a mapped page
+    /// past the end of a file truncated by another process faults, so mapped bytes are
+    /// only read after this passes; a shrunken file is reported as `UnexpectedEof`.
+    pub fn check_mapping(&self) -> io::Result<()> {
+        if let ParsedFile::Mmap { map, file, .. } = self {
+            let len = file.metadata()?.len();
+            if len < map.len() as u64 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("file shrank to {:#x} bytes while mapped", len),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Retrieves a chunk of data based on the current scroll offset
-    pub fn get_chunk(&mut self, offset: usize, bytes_per_line: usize, lines: usize) -> Vec<u8> {
-        match self {
-            ParsedFile::Generic(data) => {
-                let start = offset * bytes_per_line;
-                let end = usize::min(start + (bytes_per_line * lines), data.len());
-                if start >= data.len() {
-                    Vec::new()
-                } else {
-                    data[start..end].to_vec()
+    pub fn get_chunk(&mut self, offset: usize, bytes_per_line: usize, lines: usize) -> io::Result<Vec<u8>> {
+        self.read_range(offset * bytes_per_line, bytes_per_line * lines)
+    }
+
+    /// Reads up to `len` bytes starting at `start`, clamped to the end of the data
+    pub fn read_range(&mut self, start: usize, len: usize) -> io::Result<Vec<u8>> {
+        match self {
+            ParsedFile::Lazy(file) => read_file_range(file, start as u64, len),
+            ParsedFile::Window { file, base, len: window_len } => {
+                if start >= *window_len {
+                    return Ok(Vec::new());
                 }
+                let len = usize::min(len, *window_len - start);
+                read_file_range(file, *base + start as u64, len)
+            }
+            // Files in memory or mapped are sliced
+            _ => {
+                self.check_mapping()?;
+                let data = self.data();
+                let start = usize::min(start, data.len());
+                let end = usize::min(start.saturating_add(len), data.len());
+                Ok(data[start..end].to_vec())
             }
-            ParsedFile::Lazy(file) => read_file_chunk(file, offset, bytes_per_line, lines),
-            // Handle other variants
         }
     }
+
+    /// Returns the byte the whole file consists of, if it is a single repeated value.
+    /// In-memory files are scanned fully; lazy and mapped files are sampled at evenly
+    /// spaced points, and a read error counts as not uniform.
+    pub fn uniform_byte(&mut self, file_size: usize) -> Option<u8> {
+        match self {
+            ParsedFile::Lazy(_) | ParsedFile::Mmap { .. } | ParsedFile::Window { .. } => {
+                let first = *self.read_range(0, 1).ok()?.first()?;
+                let stride = (file_size / UNIFORM_SAMPLES).max(1);
+                (0..file_size).step_by(stride).take(UNIFORM_SAMPLES).all(|offset| {
+                    let sample = self.read_range(offset, UNIFORM_SAMPLE_LEN).unwrap_or_default();
+                    !sample.is_empty() && sample.iter().all(|&b| b == first)
+                }).then_some(first)
+            }
+            _ => {
+                let data = self.data();
+                let first = *data.first()?;
+                data.iter().all(|&b| b == first).then_some(first)
+            }
+        }
+    }
+}
+
+/// Read-ahead buffer for files read on demand, so that scrolling through
+/// nearby lines is served from memory instead of a fresh seek and read
+pub struct Prefetch {
+    pub lines: usize, // Extra lines read beyond the visible window, in the scroll direction
+    start: usize,     // File offset of buffer[0]
+    buffer: Vec<u8>,
+    at_eof: bool,     // The buffer runs to the end of the file
+}
+
+impl Prefetch {
+    pub fn new(lines: usize) -> Self {
+        Self { lines, start: 0, buffer: Vec::new(), at_eof: false }
+    }
+
+    /// Drops the buffered bytes, e.g. after the file changed on disk
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.start = 0;
+        self.at_eof = false;
+    }
+
+    /// Returns `len` bytes at `start`, reading ahead by `lines * bytes_per_line`
+    /// past the requested range in the direction of travel when it isn't buffered
+    pub fn read(
+        &mut self,
+        file: &mut ParsedFile,
+        start: usize,
+        len: usize,
+        bytes_per_line: usize,
+    ) -> io::Result<Vec<u8>> {
+        let buffer_end = self.start + self.buffer.len();
+        let covered = start >= self.start
+            && (start + len <= buffer_end || (self.at_eof && start <= buffer_end));
+        if !covered {
+            let extra = self.lines * bytes_per_line;
+            let (read_start, read_len) = if start < self.start {
+                // Scrolling up: read ahead before the requested range
+                let read_start = start.saturating_sub(extra);
+                (read_start, len + (start - read_start))
+            } else {
+                (start, len + extra)
+            };
+            self.buffer = file.read_range(read_start, read_len)?;
+            self.at_eof = self.buffer.len() < read_len;
+            self.start = read_start;
+        }
+
+        let from = usize::min(start - self.start, self.buffer.len());
+        let to = usize::min(from + len, self.buffer.len());
+        Ok(self.buffer[from..to].to_vec())
+    }
+}
+
+/// File formats recognized by the magic bytes they start with
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Generic, // No known signature
+    Elf,
+    Pe,
+    MachO,
+    Zip,
+    Png,
 }
 
-/// Parses the file and returns a `ParsedFile` instance
+impl FileFormat {
+    /// Short name shown in the metadata bar
+    pub fn name(self) -> &'static str {
+        match self {
+            FileFormat::Generic => "data",
+            FileFormat::Elf => "ELF",
+            FileFormat::Pe => "PE",
+            FileFormat::MachO => "Mach-O",
+            FileFormat::Zip => "ZIP",
+            FileFormat::Png => "PNG",
+        }
+    }
+}
+
+/// Identifies a file's format from its first bytes (up to `MAGIC_LEN`)
+pub fn detect_format(header: &[u8]) -> FileFormat {
+    const SIGNATURES: [(&[u8], FileFormat); 8] = [
+        (b"\x7fELF", FileFormat::Elf),
+        (b"MZ", FileFormat::Pe),
+        (b"\xca\xfe\xba\xbe", FileFormat::MachO), // Universal (fat) binary
+        (b"\xfe\xed\xfa", FileFormat::MachO),     // Big-endian 32 and 64-bit
+        (b"\xce\xfa\xed\xfe", FileFormat::MachO), // Little-endian 32-bit
+        (b"\xcf\xfa\xed\xfe", FileFormat::MachO), // Little-endian 64-bit
+        (b"PK\x03\x04", FileFormat::Zip),
+        (b"\x89PNG", FileFormat::Png),
+    ];
+    SIGNATURES
+        .iter()
+        .find(|(magic, _)| header.starts_with(magic))
+        .map_or(FileFormat::Generic, |&(_, format)| format)
+}
+
+/// Parses the file and returns a `ParsedFile` instance, using the parser for
+/// the format its magic bytes name
 pub fn parse_file(path: &str) -> Result<ParsedFile, Box<dyn std::error::Error>> {
     let mut file = File::open(path)?;
-    // For now, always use the generic parser
-    generic::GenericParser::parse(&mut file)
+    let mut header = Vec::new();
+    (&mut file).take(MAGIC_LEN as u64).read_to_end(&mut header)?;
+    file.rewind()?;
+    match detect_format(&header) {
+        FileFormat::Elf => elf::ElfParser::parse(&mut file),
+        FileFormat::Pe => pe::PeParser::parse(&mut file),
+        FileFormat::Png => png::PngParser::parse(&mut file),
+        // Formats without a structure parser are shown as plain bytes
+        _ => generic::GenericParser::parse(&mut file),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn detect_format_matches_each_signature() {
+        let cases: [(&[u8], FileFormat); 9] = [
+            (b"\x7fELF\x02\x01\x01", FileFormat::Elf),
+            (b"MZ\x90\x00", FileFormat::Pe),
+            (b"\xca\xfe\xba\xbe\x00\x00\x00\x02", FileFormat::MachO),
+            (b"\xfe\xed\xfa\xce", FileFormat::MachO),
+            (b"\xfe\xed\xfa\xcf", FileFormat::MachO),
+            (b"\xce\xfa\xed\xfe", FileFormat::MachO),
+            (b"\xcf\xfa\xed\xfe", FileFormat::MachO),
+            (b"PK\x03\x04\x14\x00", FileFormat::Zip),
+            (b"\x89PNG\r\n\x1a\n", FileFormat::Png),
+        ];
+        for (header, format) in cases {
+            assert!(detect_format(header) == format, "{:02x?} is {}", header, format.name());
+        }
+    }
+
+    #[test]
+    fn detect_format_falls_back_to_generic() {
+        for header in [&b""[..], b"M", b"ZM", b"\x7fEL", b"PK\x05\x06", b"hello, world", b"\x00\x7fELF"] {
+            assert!(detect_format(header) == FileFormat::Generic, "{:02x?}", header);
+        }
+    }
+
+    #[test]
+    fn parse_file_falls_back_to_generic_when_the_structure_does_not_parse() {
+        // Magic bytes alone don't make a valid ELF, so the bytes are still shown
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"\x7fELF not really").unwrap();
+        let parsed = parse_file(&file.path().to_string_lossy()).unwrap();
+        assert!(matches!(parsed, ParsedFile::Generic(_)));
+        assert_eq!(parsed.data(), b"\x7fELF not really");
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"plain text").unwrap();
+        assert!(matches!(parse_file(&file.path().to_string_lossy()).unwrap(), ParsedFile::Generic(_)));
+    }
 }
@@ -0,0 +1,44 @@
+// src/parsers/pe.rs
+
+use super::{FileParser, ParsedFile, SectionInfo};
+use goblin::pe::PE;
+use std::fs::File;
+use std::io::Read;
+
+/// PE (Windows executable) parser that reads the file into memory and lists its
+/// sections. Files with malformed PE headers are still shown as plain bytes.
+pub struct PeParser;
+
+impl FileParser for PeParser {
+    fn parse(file: &mut File) -> Result<ParsedFile, Box<dyn std::error::Error>> {
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        match parse_sections(&buffer) {
+            Some((sections, entry_point)) => Ok(ParsedFile::Pe { data: buffer, sections, entry_point }),
+            None => Ok(ParsedFile::Generic(buffer)),
+        }
+    }
+}
+
+/// Lists the sections of the PE file in `data` with the virtual address of its
+/// entry point, or returns None if its headers are malformed
+pub fn parse_sections(data: &[u8]) -> Option<(Vec<SectionInfo>, usize)> {
+    let pe = PE::parse(data).ok()?;
+    let image_base = pe.image_base as usize;
+    // Skip sections with no raw data, such as uninitialized data
+    let sections = pe
+        .sections
+        .iter()
+        .filter(|section| section.size_of_raw_data > 0)
+        .map(|section| SectionInfo {
+            name: section
+                .real_name
+                .clone()
+                .unwrap_or_else(|| section.name().unwrap_or("?").to_string()),
+            offset: section.pointer_to_raw_data as usize,
+            size: section.size_of_raw_data as usize,
+            address: image_base + section.virtual_address as usize,
+        })
+        .collect();
+    Some((sections, image_base + pe.entry as usize))
+}
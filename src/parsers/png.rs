@@ -0,0 +1,140 @@
+// src/parsers/png.rs
+
+use super::{FileParser, ParsedFile};
+use crate::utils::ChecksumAlgo;
+use std::fs::File;
+use std::io::Read;
+
+/// Bytes every PNG file starts with
+const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+/// One chunk of a PNG file
+pub struct PngChunk {
+    pub kind: [u8; 4],     // Chunk type tag, e.g. `IHDR`
+    pub offset: usize,     // File offset of the chunk's length field
+    pub length: usize,     // Bytes of chunk data
+    pub crc: u32,          // CRC stored after the data
+    pub computed_crc: u32, // CRC of the type tag and data as read
+}
+
+impl PngChunk {
+    /// Returns the type tag as text
+    pub fn name(&self) -> String {
+        String::from_utf8_lossy(&self.kind).into_owned()
+    }
+
+    /// Bytes the chunk takes in the file: length, type, data and CRC
+    pub fn size(&self) -> usize {
+        self.length + 12
+    }
+
+    /// Returns whether the stored CRC matches the chunk's contents
+    pub fn crc_ok(&self) -> bool {
+        self.crc == self.computed_crc
+    }
+}
+
+/// PNG parser that reads the file into memory and walks its chunk list.
+/// Files without the full signature are shown as plain bytes.
+pub struct PngParser;
+
+impl FileParser for PngParser {
+    fn parse(file: &mut File) -> Result<ParsedFile, Box<dyn std::error::Error>> {
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        match parse_chunks(&buffer) {
+            Some(chunks) => Ok(ParsedFile::Png { data: buffer, chunks }),
+            None => Ok(ParsedFile::Generic(buffer)),
+        }
+    }
+}
+
+/// Walks the chunks after the signature, stopping after `IEND` or at the first
+/// chunk cut off by the end of the data. Returns None without the full signature.
+pub fn parse_chunks(data: &[u8]) -> Option<Vec<PngChunk>> {
+    if !data.starts_with(SIGNATURE) {
+        return None;
+    }
+    let read_u32 = |at: usize| u32::from_be_bytes(data[at..at + 4].try_into().unwrap());
+    let mut chunks = Vec::new();
+    let mut pos = SIGNATURE.len();
+    while pos + 8 <= data.len() {
+        let length = read_u32(pos) as usize;
+        let crc_at = pos + 8 + length;
+        if crc_at + 4 > data.len() {
+            break;
+        }
+        let kind = data[pos + 4..pos + 8].try_into().unwrap();
+        chunks.push(PngChunk {
+            kind,
+            offset: pos,
+            length,
+            crc: read_u32(crc_at),
+            computed_crc: ChecksumAlgo::Crc32.compute(&data[pos + 4..crc_at]),
+        });
+        pos = crc_at + 4;
+        if &kind == b"IEND" {
+            break;
+        }
+    }
+    Some(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Appends a chunk with the given stored CRC
+    fn push_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8], crc: u32) {
+        png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        png.extend_from_slice(kind);
+        png.extend_from_slice(data);
+        png.extend_from_slice(&crc.to_be_bytes());
+    }
+
+    /// A 1×1 grayscale image: IHDR, IDAT and IEND with their real CRCs
+    fn minimal_png() -> Vec<u8> {
+        let mut png = SIGNATURE.to_vec();
+        push_chunk(&mut png, b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0], 0x3a7e9b55);
+        push_chunk(&mut png, b"IDAT", &[0x78, 0x9c, 0x63, 0x60, 0, 0, 0, 2, 0, 1], 0x48afa471);
+        push_chunk(&mut png, b"IEND", &[], 0xae426082);
+        png
+    }
+
+    fn listing(chunks: &[PngChunk]) -> Vec<(String, usize, usize, bool)> {
+        chunks.iter().map(|chunk| (chunk.name(), chunk.offset, chunk.length, chunk.crc_ok())).collect()
+    }
+
+    #[test]
+    fn parses_a_minimal_valid_png() {
+        let chunks = parse_chunks(&minimal_png()).unwrap();
+        assert_eq!(
+            listing(&chunks),
+            [
+                ("IHDR".to_string(), 8, 13, true),
+                ("IDAT".to_string(), 33, 10, true),
+                ("IEND".to_string(), 55, 0, true),
+            ]
+        );
+        assert_eq!(chunks[1].size(), 22);
+    }
+
+    #[test]
+    fn flags_a_corrupted_crc() {
+        let mut png = minimal_png();
+        png[33 + 8] ^= 0xff; // The first byte of IDAT's data
+        let chunks = parse_chunks(&png).unwrap();
+        let ok: Vec<bool> = chunks.iter().map(PngChunk::crc_ok).collect();
+        assert_eq!(ok, [true, false, true]);
+        assert_eq!(chunks[1].crc, 0x48afa471); // The stored CRC is kept as read
+        assert_ne!(chunks[1].computed_crc, chunks[1].crc);
+    }
+
+    #[test]
+    fn stops_at_a_cut_off_chunk_and_needs_the_signature() {
+        let png = minimal_png();
+        assert_eq!(listing(&parse_chunks(&png[..50]).unwrap()).len(), 1); // IDAT runs past the end
+        assert!(parse_chunks(&png[..7]).is_none());
+        assert!(parse_chunks(b"GIF89a").is_none());
+    }
+}
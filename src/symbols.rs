@@ -0,0 +1,65 @@
+// src/symbols.rs
+
+use std::collections::HashMap;
+use std::error::Error;
+
+/// Loads symbol → address mappings from a linker map or symbol list file
+pub fn load_map_file(path: &str) -> Result<HashMap<String, usize>, Box<dyn Error>> {
+    let text = std::fs::read_to_string(path)?;
+    let symbols = parse_map_file(&text);
+    if symbols.is_empty() {
+        return Err(format!("No symbols found in map file '{}'", path).into());
+    }
+    Ok(symbols)
+}
+
+/// Parses symbol → address mappings, one per line. Understands:
+/// - simple lists: `name address` or `address name`
+/// - `nm` output: `address type name`
+/// - GNU ld map symbol lines: `0x0000000008000188    Reset_Handler`
+///
+/// Addresses are hexadecimal, with or without a `0x` prefix. Lines that don't
+/// match one of these shapes (section headers, assignments, comments) are skipped.
+pub fn parse_map_file(text: &str) -> HashMap<String, usize> {
+    let mut symbols = HashMap::new();
+    for line in text.lines() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let entry = match tokens.as_slice() {
+            [first, second] => match (parse_address(first), parse_address(second)) {
+                // Prefer the explicitly prefixed token as the address when both look like hex
+                (Some(address), _) if is_symbol_name(second) && !has_hex_prefix(second) => {
+                    Some((*second, address))
+                }
+                (_, Some(address)) if is_symbol_name(first) => Some((*first, address)),
+                _ => None,
+            },
+            [address, kind, name] if kind.len() == 1 && is_symbol_name(name) => {
+                parse_address(address).map(|address| (*name, address))
+            }
+            _ => None,
+        };
+        if let Some((name, address)) = entry {
+            symbols.insert(name.to_string(), address);
+        }
+    }
+    symbols
+}
+
+fn has_hex_prefix(token: &str) -> bool {
+    token.starts_with("0x") || token.starts_with("0X")
+}
+
+/// Parses a hexadecimal address with an optional `0x` prefix
+fn parse_address(token: &str) -> Option<usize> {
+    let digits = token.strip_prefix("0x").or_else(|| token.strip_prefix("0X")).unwrap_or(token);
+    usize::from_str_radix(digits, 16).ok()
+}
+
+/// Returns true for identifier-like tokens (C, C++ mangled and dotted names)
+fn is_symbol_name(token: &str) -> bool {
+    let mut chars = token.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '.' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '$'))
+}
@@ -1,15 +1,24 @@
 // src/ui.rs
 
-use crate::app::{App, AppMode, Theme};
-use crate::utils::format_hex_dump;
+use crate::app::{App, AppMode, ContentAlign, SearchType};
+use crate::parsers::{FileFormat, ParsedFile};
+use crate::utils::{format_hex_dump, format_ruler, format_text_records, ByteClass, Highlights};
+use std::fmt::{Display, LowerExp};
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Span, Line, Text},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
     Frame,
 };
 
+/// Width of the overview pane, including its borders
+const OVERVIEW_WIDTH: u16 = 10;
+/// Width of the pins sidebar, including its borders
+const PINS_WIDTH: u16 = 28;
+/// Width of the byte inspector pane, including its borders
+const INSPECTOR_WIDTH: u16 = 30;
+
 pub fn draw_ui<'a>(f: &mut Frame<'a>, app: &mut App) {
     match app.mode {
         AppMode::Help => {
@@ -23,16 +32,75 @@ pub fn draw_ui<'a>(f: &mut Frame<'a>, app: &mut App) {
                 Line::from(Span::raw("This application allows you to view files in a hexadecimal format.")),
                 Line::from(""),
                 Line::from(Span::styled("Keybindings:", Style::default().add_modifier(Modifier::UNDERLINED))),
-                Line::from("  ↑ / ↓ : Scroll Up/Down"),
-                Line::from("  /     : Enter ASCII search mode"),
-                Line::from("  x     : Enter Hex search mode"),
-                Line::from("  :     : Go to Offset"),
-                Line::from("  t     : Toggle Theme (Light/Dark)"),
+                Line::from("  ← / → / ↑ / ↓ : Move the byte cursor (↑/↓ scroll the filter view)"),
+                Line::from("  Shift+arrows : Select bytes from the cursor (or drag with the mouse)"),
+                Line::from("  V     : Toggle block selection: the same columns on each line; copies row by row"),
+                Line::from("  PgUp / PgDn : Scroll by a page"),
+                Line::from("  Home / End (g / G) : Jump to the start/end of the file"),
+                Line::from("  F     : Follow the end of a growing file, like tail -f (scroll up to stop)"),
+                Line::from("  A     : Toggle scroll acceleration for held keys"),
+                Line::from("  /     : Enter search mode (ASCII unless --default-search says otherwise); matches update as you type"),
+                Line::from("  x     : Enter Hex search mode (?? matches any byte)"),
+                Line::from("  e     : Enter Regex search mode (byte regex, e.g. \\d{4}-\\d{2})"),
+                Line::from("          Each capture group is highlighted in its own color; a later group wins where they overlap"),
+                Line::from("  Tab   : (while searching) Toggle case-insensitive ASCII/regex search"),
+                Line::from("  n / N : Jump to the next/previous search match"),
+                Line::from("  :     : Go to Offset: 4096, 0x1000, +/-N relative, 50% of the file, SSSS:OOOO, L<n> line, or --map symbol"),
+                Line::from("          With --base, addresses count from the base and bare digits are hex (400010)"),
+                Line::from("  :w [file] : Save edits to the file (or to another file; --backup keeps a .bak)"),
+                Line::from("              --autosave N copies unsaved edits to <file>.autosave after N idle seconds"),
+                Line::from("  :export <file> : Export the selection (or the whole file) as a text hex dump (formerly :w <file>)"),
+                Line::from("  :carray [name] [n] : Copy the selection as a C array, n bytes per line (12)"),
+                Line::from("  :hl <text> / :hlx <hex> : Highlight a pattern in its own color; :hl lists them"),
+                Line::from("  :unhl [n] : Remove highlight term n, or all of them"),
+                Line::from("  :replace <a>/<b> : Overwrite every a with b, of the same length (:replacex for hex, ?? keeps a byte)"),
+                Line::from("  Insert / :edit : Overwrite bytes at the cursor with hex digits (Tab: type ASCII instead)"),
+                Line::from("                   (not e, which already enters Regex search)"),
+                Line::from("  :sha256 / :md5 : Hash the whole file"),
+                Line::from("  :mark [name] : Bookmark the cursor, optionally with a name"),
+                Line::from("  R     : Jump to a random offset (repeatable with --seed)"),
+                Line::from("  Alt+← / Alt+→ : Back/Forward through jumps (goto, clicks)"),
+                Line::from("  t     : Toggle Theme (Light/Dark, and Custom with --theme custom)"),
+                Line::from("  i     : Invert the theme's colors"),
+                Line::from("  u     : Toggle uppercase hex digits"),
+                Line::from("  w     : Cycle bytes per line (8/16/24/32/48/64)"),
+                Line::from("  + / - : One more/fewer byte per line (4 to 64); w and +/- turn off --auto-width"),
+                Line::from("  T     : Toggle text records view (-b sets the record width)"),
+                Line::from("  r     : Toggle raw view of a single-byte file"),
+                Line::from("  f     : Toggle filter view (only lines with matches)"),
+                Line::from("  v     : Show the top line's bytes in reverse order"),
+                Line::from("  a     : Cycle ASCII rendering (dots/glyphs/cp437/escape)"),
+                Line::from("  O     : Toggle the overview pane (click or scroll it to move the view; lighter shading means more zeros)"),
+                Line::from("  z     : Show runs of 4+ identical control/null bytes as .×N in the ASCII column"),
+                Line::from("  d     : Cycle bytes between hex, octal, decimal and binary"),
+                Line::from("  k     : Cycle the per-line checksum column (off/xor/sum)"),
+                Line::from("  K     : CRC32, Adler32 and sum of the selection (or the whole file)"),
+                Line::from("  m     : Bookmark the cursor (* in the address gutter); again to remove"),
+                Line::from("  '     : List bookmarks; Enter jumps, d deletes (kept in <file>.hexbookmarks)"),
+                Line::from("  B / J : Toggle the pins sidebar (bookmarks, click one to jump) / jump to the next pin"),
+                Line::from("  S     : List the sections of an ELF or PE file, or a PNG's chunks; Enter jumps to one"),
+                Line::from("          v views one alone, offsets from its start; Backspace returns to the whole file"),
+                Line::from("  s     : Toggle statistics for the visible bytes"),
+                Line::from("  I     : Toggle the inspector (bytes at the cursor as i8..u64, f32, f64)"),
+                Line::from("  E     : Switch the inspector between little- and big-endian"),
+                Line::from("  l     : Decode LEB128 at the top of the view (Esc clears the highlight)"),
+                Line::from("  y / Y : Copy the selection (or cursor byte) as hex / spaced hex; Alt+y as ASCII"),
+                Line::from("  b     : Show a small selection as hex in the message bar (up to --readout-limit bytes)"),
+                Line::from("  ] / [ : Next/previous differing region (second file, or last compare)"),
+                Line::from("  X     : Overlay the second file: differing bytes show as de→ad, -- past its end"),
+                Line::from("  o     : Copy the visible offset range to the clipboard"),
+                Line::from("  c     : Compare typed hex (?? = any byte) with the top of the view, live"),
+                Line::from("  p     : Search the file for the clipboard contents (hex or text)"),
+                Line::from("  P     : Compare the clipboard with the bytes at the top of the view"),
                 Line::from("  h     : Toggle Help"),
                 Line::from("  q     : Quit"),
                 Line::from(""),
                 Line::from(Span::styled("Usage:", Style::default().add_modifier(Modifier::UNDERLINED))),
-                Line::from("  - Navigate using arrow keys or mouse wheel."),
+                Line::from("  - Move the cursor with the arrow keys or a click; scroll with the mouse wheel."),
+                Line::from("  - Click anywhere along the metadata bar to jump to that fraction of the file."),
+                Line::from("  - With --holes, unallocated regions of sparse files are shown in gray."),
+                Line::from("  - Pass a second file to compare the two side by side; differences are red."),
+                Line::from("  - Overview colors: gray zero, green text, yellow control, magenta high bytes."),
                 Line::from("  - Search for ASCII strings or hexadecimal patterns to highlight them."),
                 Line::from("  - Jump directly to a specific offset within the file."),
                 Line::from("  - Toggle between Light and Dark themes for better visibility."),
@@ -43,63 +111,251 @@ pub fn draw_ui<'a>(f: &mut Frame<'a>, app: &mut App) {
             ];
             let help_block = Paragraph::new(Text::from(help_text))
                 .block(Block::default().borders(Borders::ALL).title("Help"))
-                .style(match app.theme {
-                    Theme::Light => Style::default().fg(Color::Black).bg(Color::White),
-                    Theme::Dark => Style::default().fg(Color::White).bg(Color::Black),
-                })
+                .style(app.theme_colors().style())
                 .wrap(ratatui::widgets::Wrap { trim: true }); // Enable text wrapping
 
             // Calculate a centered rectangle for the Help block
             let width = 80;  // Width in percentage
-            let height = 30; // Height in percentage
+            let height = 80; // Height in percentage (tall enough for the keybinding list)
             let size = f.area();
             let rect = centered_rect(width, height, size);
             f.render_widget(help_block, rect);
         }
+        AppMode::Sections => {
+            let rect = centered_rect(70, 80, f.area());
+            let sections = render_sections(app, rect.height.saturating_sub(2) as usize);
+            f.render_widget(sections, rect);
+        }
+        AppMode::Bookmarks => {
+            let rect = centered_rect(70, 80, f.area());
+            let bookmarks = render_bookmarks(app, rect.height.saturating_sub(2) as usize)
+                .block(Block::default().borders(Borders::ALL).title("Bookmarks (Enter: go to, d: delete, Esc: close)"));
+            f.render_widget(bookmarks, rect);
+        }
         _ => {
             // Normal mode layout
+            let stats_height = if app.show_stats { 3 } else { 0 };
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints(
                     [
-                        Constraint::Length(3), // Metadata
-                        Constraint::Length(3), // Input or Help
-                        Constraint::Min(0),    // Content
-                        Constraint::Length(3), // Message (increased from 1 to 3)
+                        Constraint::Length(3),            // Metadata
+                        Constraint::Length(3),            // Input or Help
+                        Constraint::Min(0),               // Content
+                        Constraint::Length(stats_height), // Statistics (when shown)
+                        Constraint::Length(3),            // Message (increased from 1 to 3)
                     ]
                     .as_ref(),
                 )
                 .split(f.area());
 
-            // Render metadata
-            let metadata = render_metadata(app);
-            f.render_widget(metadata, chunks[0]);
-
             // Render input box
             let input = render_input(app);
             f.render_widget(input, chunks[1]);
 
+            // Split off the overview pane when it is shown
+            let content_area = if app.show_overview {
+                let panes = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Length(OVERVIEW_WIDTH), Constraint::Min(0)])
+                    .split(chunks[2]);
+                app.overview_area = panes[0];
+                let overview = render_overview(app, panes[0]);
+                f.render_widget(overview, panes[0]);
+                panes[1]
+            } else {
+                chunks[2]
+            };
+
+            // Split off the pins sidebar on the right when it is shown
+            let content_area = if app.show_pins {
+                let panes = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Min(0), Constraint::Length(PINS_WIDTH)])
+                    .split(content_area);
+                app.pins_area = panes[1];
+                let pins = render_pins(app, panes[1].height.saturating_sub(2) as usize);
+                f.render_widget(pins, panes[1]);
+                panes[0]
+            } else {
+                content_area
+            };
+
+            // Split off the byte inspector on the right when it is shown
+            let content_area = if app.show_inspector {
+                let panes = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Min(0), Constraint::Length(INSPECTOR_WIDTH)])
+                    .split(content_area);
+                let inspector = render_inspector(app);
+                f.render_widget(inspector, panes[1]);
+                panes[0]
+            } else {
+                content_area
+            };
+
+            // Share the content area with the second file when comparing two files side by side
+            let content_area = if app.second_file.is_some() && !app.overlay {
+                let panes = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                    .split(content_area);
+                let second = render_second_file(app, panes[1]);
+                f.render_widget(second, panes[1]);
+                panes[0]
+            } else {
+                content_area
+            };
+
             // Render content
-            let content = render_content(app, chunks[2].height as usize);
-            f.render_widget(content, chunks[2]);
+            // Rows inside the content block's borders
+            app.content_area = content_area;
+            app.last_visible_height = (content_area.height as usize).saturating_sub(2 + app.ruler_rows());
+            let content_width = content_area.width.saturating_sub(2) as usize;
+            app.fit_to_width(app.content_width.map_or(content_width, |w| w.min(content_width)));
+            let content = render_content(app, app.last_visible_height, content_width);
+            f.render_widget(content, content_area);
+            render_scrollbar(f, app, content_area);
+
+            // Render metadata once the content has set how much of the file is visible
+            app.metadata_area = chunks[0];
+            let metadata = render_metadata(app);
+            f.render_widget(metadata, chunks[0]);
+
+            // Render statistics for the visible bytes
+            if app.show_stats {
+                let stats = render_stats(app);
+                f.render_widget(stats, chunks[3]);
+            }
 
             // Render message box
             if let Some(message) = &app.message {
                 let message_paragraph = Paragraph::new(message.clone())
                     .block(Block::default().borders(Borders::ALL).title("Message"))
-                    .style(Style::default().fg(Color::Red))
+                    .style(app.theme_colors().style().fg(Color::Red))
                     .alignment(ratatui::layout::Alignment::Left)
                     .wrap(ratatui::widgets::Wrap { trim: true }); // Enable text wrapping
-                f.render_widget(message_paragraph, chunks[3]);
+                f.render_widget(message_paragraph, chunks[4]);
             } else {
                 // Clear the message box if there's no message
-                let empty = Paragraph::new("");
-                f.render_widget(empty, chunks[3]);
+                let empty = Paragraph::new("").style(app.theme_colors().style());
+                f.render_widget(empty, chunks[4]);
             }
         }
     }
 }
 
+/// Lists the sections with their file offsets, virtual addresses and sizes, or a
+/// PNG file's chunks with bad CRCs in red, scrolled to keep the highlighted entry
+/// within the `rows` inside the borders
+fn render_sections(app: &App, rows: usize) -> Paragraph<'_> {
+    let address_width = app.display_options().address_width;
+    let sections = app.parsed_file.sections();
+    let chunks = app.parsed_file.chunks();
+    let name_width = sections.iter().map(|section| section.name.len()).max().unwrap_or(4);
+    // Each entry's text, and whether it is damaged
+    let entries: Vec<(String, bool)> = if chunks.is_empty() {
+        sections
+            .iter()
+            .map(|section| {
+                let text = format!(
+                    "{:<name_width$}  0x{}  VA 0x{}  {} bytes",
+                    section.name,
+                    app.hex(section.offset, address_width),
+                    app.hex(section.address, 8),
+                    section.size,
+                    name_width = name_width
+                );
+                (text, false)
+            })
+            .collect()
+    } else {
+        chunks
+            .iter()
+            .map(|chunk| {
+                let mismatch = if chunk.crc_ok() {
+                    String::new()
+                } else {
+                    format!(" (computed 0x{})", app.hex(chunk.computed_crc as usize, 8))
+                };
+                let text = format!(
+                    "{}  0x{}  {} bytes  CRC 0x{}{}",
+                    chunk.name(),
+                    app.hex(chunk.offset, address_width),
+                    chunk.length,
+                    app.hex(chunk.crc as usize, 8),
+                    mismatch
+                );
+                (text, !chunk.crc_ok())
+            })
+            .collect()
+    };
+    let lines: Vec<Line> = entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, (text, damaged))| {
+            let mut style = Style::default();
+            if damaged {
+                style = style.fg(Color::Red);
+            }
+            if i == app.section_index {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            Line::from(Span::styled(text, style))
+        })
+        .collect();
+    let scroll = (app.section_index + 1).saturating_sub(rows.max(1));
+    let entry = match app.parsed_file.entry_point() {
+        Some(entry) => format!(", entry point 0x{}", app.hex(entry, 8)),
+        None => String::new(),
+    };
+    let kind = if chunks.is_empty() { "Sections" } else { "Chunks" };
+    let title = format!("{}{} (Enter: go to, v: view alone, Esc: close)", kind, entry);
+    Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .style(app.theme_colors().style())
+        .scroll((scroll as u16, 0))
+}
+
+/// Lists the bookmarks by offset, scrolled to keep the highlighted one within
+/// the `rows` inside the borders; the caller adds the block
+fn render_bookmarks(app: &App, rows: usize) -> Paragraph<'_> {
+    let address_width = app.display_options().address_width;
+    let lines: Vec<Line> = app
+        .bookmarks
+        .iter()
+        .enumerate()
+        .map(|(i, bookmark)| {
+            let text = format!(
+                "0x{}  {}",
+                app.hex(app.base_address + bookmark.offset, address_width),
+                bookmark.name.as_deref().unwrap_or("")
+            );
+            if i == app.bookmark_index {
+                Line::from(Span::styled(text, Style::default().add_modifier(Modifier::REVERSED)))
+            } else {
+                Line::from(text)
+            }
+        })
+        .collect();
+    Paragraph::new(lines)
+        .style(app.theme_colors().style())
+        .scroll((app.pins_scroll(rows) as u16, 0))
+}
+
+/// Renders the pins sidebar: the bookmarks with their labels, the selected one
+/// highlighted, or how to add one while there are none
+fn render_pins(app: &App, rows: usize) -> Paragraph<'_> {
+    let block = Block::default().borders(Borders::ALL).title("Pins (J: next, B: hide)");
+    if app.bookmarks.is_empty() {
+        return Paragraph::new("m or :mark <label> pins the cursor")
+            .block(block)
+            .style(app.theme_colors().style())
+            .wrap(ratatui::widgets::Wrap { trim: true });
+    }
+    render_bookmarks(app, rows).block(block)
+}
+
 /// Helper function to create a centered rectangular area
 fn centered_rect(width_percent: u16, height_percent: u16, r: ratatui::layout::Rect) -> ratatui::layout::Rect {
     let vertical_split = Layout::default()
@@ -129,90 +385,506 @@ fn centered_rect(width_percent: u16, height_percent: u16, r: ratatui::layout::Re
     horizontal_split[1]
 }
 
-fn render_metadata(app: &App) -> Paragraph<'_> {
-    let total_lines = (app.file_size + app.bytes_per_line - 1) / app.bytes_per_line;
+fn render_metadata(app: &mut App) -> Paragraph<'_> {
+    let total_lines = app.file_size.div_ceil(app.bytes_per_line);
+    let top_line = app.top_line();
     let percentage = if app.file_size == 0 {
         0.0
     } else {
-        (app.scroll_offset * app.bytes_per_line) as f64 / app.file_size as f64 * 100.0
+        (top_line * app.bytes_per_line) as f64 / app.file_size as f64 * 100.0
+    };
+    let window = match app.parsed_file {
+        ParsedFile::Window { base, len, .. } => format!(
+            " [0x{}..0x{}]",
+            app.hex(base as usize, 1),
+            app.hex(base as usize + len, 1)
+        ),
+        _ => String::new(),
+    };
+    let selection = match (&app.selection, app.block_selection()) {
+        (Some(range), Some(block)) => format!(
+            " | Block: 0x{}-0x{} ({} lines × {} columns)",
+            app.hex(app.base_address + range.start, 1),
+            app.hex(app.base_address + range.end - 1, 1),
+            block.rows.len(),
+            block.cols.len()
+        ),
+        (Some(range), None) => format!(
+            " | Selected: 0x{}-0x{} ({} bytes)",
+            app.hex(app.base_address + range.start, 1),
+            app.hex(app.base_address + range.end - 1, 1),
+            range.len()
+        ),
+        (None, _) => String::new(),
+    };
+    let comparison = match &app.second_file {
+        Some(second) => format!(" | vs {} ({} bytes): {} differing regions", second.path, second.size, app.file_diffs.len()),
+        None => String::new(),
+    };
+    let follow = if app.follow { " | FOLLOW" } else { "" };
+    let format = match app.file_format {
+        FileFormat::Generic => String::new(),
+        format => format!(" | Format: {}", format.name()),
+    };
+    let modified = if app.dirty { " [modified]" } else { "" };
+    // Entropy of the bytes on screen; near 8 bits suggests compressed or encrypted data
+    let entropy = match app.unavailable {
+        Some(_) => String::new(),
+        None => match app.visible_stats() {
+            (range, stats) if !range.is_empty() => format!(" | Entropy: {:.2}", stats.entropy),
+            _ => String::new(),
+        },
     };
     let text = format!(
-        "File: {} | Size: {} bytes | Offset: {:#08x} | {}/{} lines ({:.2}%)",
+        "File: {}{}{}{} | Size: {} bytes | Offset: 0x{} | {}/{} lines ({:.2}%){}{}{}{}",
         app.file_path,
+        modified,
+        window,
+        format,
         app.file_size,
-        app.scroll_offset * app.bytes_per_line,
-        app.scroll_offset + 1,
+        app.hex(app.base_address + top_line * app.bytes_per_line, app.display_options().address_width),
+        top_line + 1,
         total_lines,
-        percentage
+        percentage,
+        entropy,
+        follow,
+        selection,
+        comparison
     );
     Paragraph::new(text)
-        .block(Block::default().borders(Borders::ALL).title("Metadata"))
-        .style(match app.theme {
-            Theme::Light => Style::default().fg(Color::Black).bg(Color::White),
-            Theme::Dark => Style::default().fg(Color::White).bg(Color::Black),
-        })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Metadata ({} bytes/line)", app.bytes_per_line)),
+        )
+        .style(app.theme_colors().style())
 }
 
 fn render_input(app: &App) -> Paragraph<'_> {
     let (title, content) = match app.mode {
         AppMode::Search => (
-            "Search",
-            format!("/{}", app.input_buffer),
+            format!(
+                "{}{} | Enter: search, Esc: cancel",
+                match app.search_type {
+                    SearchType::Ascii if app.case_insensitive => "Search (ASCII, Tab: ignoring case)",
+                    SearchType::Ascii => "Search (ASCII, Tab: ignore case)",
+                    SearchType::Hex => "Search (Hex)",
+                    SearchType::Regex if app.case_insensitive => "Search (Regex, Tab: ignoring case)",
+                    SearchType::Regex => "Search (Regex, Tab: ignore case)",
+                },
+                match app.search_alignment {
+                    Some(alignment) => format!(" aligned to {} bytes", alignment),
+                    None => String::new(),
+                }
+            ),
+            match app.search_type {
+                // Echo hex queries in the configured case
+                SearchType::Hex if app.uppercase_hex => format!("/{}", app.input_buffer.to_uppercase()),
+                _ => format!("/{}", app.input_buffer),
+            },
         ),
-        AppMode::Goto => (
-            "Go To Offset",
+        AppMode::Compare => (
+            format!(
+                "Compare hex at 0x{} (?? = any byte) | Enter: keep, Esc: clear",
+                app.hex(app.compare_start, 1)
+            ),
+            format!("={}", app.input_buffer),
+        ),
+        AppMode::Edit => (
+            format!(
+                "Edit ({}, Tab: {}) | arrows: move, Esc: done",
+                if app.edit_ascii { "ASCII" } else { "hex digits" },
+                if app.edit_ascii { "type hex" } else { "type ASCII" }
+            ),
+            format!(
+                "Overwriting 0x{} = {}{}",
+                app.hex(app.base_address + app.cursor, 1),
+                app.hex(app.parsed_file.data().get(app.cursor).copied().unwrap_or(0) as usize, 2),
+                if app.edit_low_nibble { " (low nibble next)" } else { "" }
+            ),
+        ),
+        AppMode::Goto if app.base_address != 0 => (
+            format!(
+                "Go To (hex address from base 0x{}, +16/-16, 50%, SSSS:OOOO, L<line>, symbol) | Enter: jump, Esc: cancel",
+                app.hex(app.base_address, 1)
+            ),
             format!(":{}", app.input_buffer),
         ),
-        _ => (
-            "Normal Mode",
-            String::from("Press '/' to search, 'x' for Hex search, ':' to go to offset, 't' to toggle theme, 'h' for Help, 'q' to quit"),
+        AppMode::Goto => (
+            "Go To (4096, 0x1000, +16/-16, 50%, SSSS:OOOO, L<line>, symbol) | Enter: jump, Esc: cancel"
+                .to_string(),
+            format!(":{}", app.input_buffer),
         ),
+        _ => ("Normal Mode".to_string(), normal_mode_hints(app)),
     };
     Paragraph::new(content)
         .block(Block::default().borders(Borders::ALL).title(title))
-        .style(match app.theme {
-            Theme::Light => Style::default().fg(Color::Black).bg(Color::White),
-            Theme::Dark => Style::default().fg(Color::White).bg(Color::Black),
-        })
+        .style(app.theme_colors().style())
 }
 
-fn render_content(app: &mut App, visible_height: usize) -> Paragraph<'_> {
-    let data = app.get_display_data(visible_height);
+/// Picks the few keys most relevant to the current state for the hint bar
+fn normal_mode_hints(app: &App) -> String {
+    let mut hints: Vec<&str> = Vec::new();
+    if app.unavailable.is_some() {
+        hints.push("q: quit");
+        return hints.join(", ");
+    }
+    if app.uniform_byte.is_some() && !app.show_raw {
+        hints.push("r: view raw bytes");
+    }
+    if app.filter_lines.is_some() {
+        hints.extend(["f/Esc: full view", "↑/↓: scroll matches"]);
+    } else if !app.search_results.is_empty() {
+        hints.extend(["n/N: next/prev match", "f: filter to matches", "/: new search"]);
+    }
+    if app.selection.is_some() {
+        hints.extend(["y: copy as hex", "Esc: clear selection"]);
+    }
+    if !app.diff_ranges.is_empty() || !app.agree_ranges.is_empty() {
+        hints.extend(["Esc: clear differences", "P: compare again"]);
+    }
+    if !app.shown_diffs().is_empty() {
+        hints.push("]/[: next/prev difference");
+    }
+    if app.text_mode {
+        hints.push("T: back to hex");
+    }
+    if app.isolated_section().is_some() {
+        hints.push("Backspace: whole file");
+    }
+    if app.dirty {
+        hints.push(":w: save edits");
+    }
+    if hints.is_empty() {
+        hints.extend(["/: search", "x: hex search", ":: go to offset", "t: theme"]);
+        if app.editable() {
+            hints.push("Ins: edit bytes");
+        }
+    }
+    hints.extend(["h: help", "q: quit"]);
+    hints.join(", ")
+}
 
-    // Handle the case where no data is returned
-    if data.is_empty() {
-        let empty_message = Paragraph::new("No data to display.")
+fn render_content(app: &mut App, visible_height: usize, width: usize) -> Paragraph<'_> {
+    // Once reads have failed there is nothing trustworthy to show
+    if let Some(reason) = &app.unavailable {
+        return Paragraph::new(format!("{}. Press 'q' to quit.", reason))
             .block(Block::default().borders(Borders::ALL).title("Content"))
-            .style(match app.theme {
-                Theme::Light => Style::default().fg(Color::Black).bg(Color::White),
-                Theme::Dark => Style::default().fg(Color::White).bg(Color::Black),
-            });
-        return empty_message;
+            .style(app.theme_colors().style().fg(Color::Red));
     }
 
-    let content = format_hex_dump(
-        &data,
-        app.scroll_offset,
-        visible_height,
-        app.bytes_per_line,
-        &app.search_results,
-    );
+    // A file made of one repeated byte is summarized instead of dumped
+    if let (Some(byte), false) = (app.uniform_byte, app.show_raw) {
+        let qualifier = match app.parsed_file {
+            ParsedFile::Lazy(_) | ParsedFile::Mmap { .. } | ParsedFile::Window { .. } => " (sampled)",
+            _ => "",
+        };
+        let summary = format!(
+            "File is {} bytes of 0x{}{}. Press 'r' to view the raw bytes.",
+            app.file_size, app.hex(byte as usize, 2), qualifier
+        );
+        return Paragraph::new(summary)
+            .block(Block::default().borders(Borders::ALL).title("Content"))
+            .style(app.theme_colors().style());
+    }
+
+    let key = app.render_key(visible_height);
+    let content = match &app.render_cache {
+        Some((cached_key, lines)) if *cached_key == key => lines.clone(),
+        _ => {
+            let lines = if app.filter_lines.is_some() {
+                format_filtered_view(app, visible_height)
+            } else if app.text_mode {
+                let data = app.get_display_data(visible_height);
+                format_text_records(
+                    &data,
+                    app.scroll_offset,
+                    visible_height,
+                    app.bytes_per_line,
+                    app.highlights(),
+                    app.display_options(),
+                )
+            } else {
+                let data = app.get_display_data(visible_height);
+                // The overlay shows the second file's bytes in this file's cells
+                let overlay = app.display_options().overlay.then(|| app.second_file_data(app.scroll_offset, visible_height));
+                let mut lines = format_hex_dump(
+                    &data,
+                    app.scroll_offset,
+                    visible_height,
+                    app.bytes_per_line,
+                    Highlights { overlay: overlay.as_deref(), ..app.highlights() },
+                    app.display_options(),
+                );
+                if !lines.is_empty() {
+                    let addr = app.scroll_offset * app.bytes_per_line;
+                    lines.insert(0, format_ruler(addr, app.bytes_per_line, app.display_options()));
+                }
+                lines
+            };
+            app.render_cache = Some((key, lines.clone()));
+            lines
+        }
+    };
+    let title = match &app.filter_lines {
+        Some(lines) => format!("Content (filtered: {} lines, 'f' for full view)", lines.len()),
+        None if app.text_mode => format!("Content (text records, {} columns)", app.bytes_per_line),
+        None if app.second_file.is_some() => app.file_path.clone(),
+        None => match app.isolated_section() {
+            Some((name, offset)) => format!("Content (section {} at 0x{}, Backspace: whole file)", name, app.hex(offset, 1)),
+            None => "Content".to_string(),
+        },
+    };
 
-    // Handle the case where format_hex_dump returns empty content
+    // Handle the case where no data is returned or format_hex_dump returns empty content
     if content.is_empty() {
         let empty_message = Paragraph::new("No data to display.")
-            .block(Block::default().borders(Borders::ALL).title("Content"))
-            .style(match app.theme {
-                Theme::Light => Style::default().fg(Color::Black).bg(Color::White),
-                Theme::Dark => Style::default().fg(Color::White).bg(Color::Black),
-            });
+            .block(Block::default().borders(Borders::ALL).title(title.clone()))
+            .style(app.theme_colors().style());
         return empty_message;
     }
 
+    // A fixed content width wider than the pane falls back to the pane width
+    let align_width = app.content_width.map_or(width, |w| w.min(width));
+    app.content_margin = align_margin(&content, app.content_align, align_width);
+    let content = indent_lines(content, app.content_margin);
     Paragraph::new(Text::from(content))
-        .block(Block::default().borders(Borders::ALL).title("Content"))
-        .style(match app.theme {
-            Theme::Light => Style::default().fg(Color::Black).bg(Color::White),
-            Theme::Dark => Style::default().fg(Color::White).bg(Color::Black),
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .style(app.theme_colors().style())
+}
+
+/// Draws a scrollbar over the content block's right border when the view can scroll.
+/// The thumb spans the visible fraction of the lines, however many there are.
+fn render_scrollbar(f: &mut Frame, app: &App, area: Rect) {
+    let summarized = app.uniform_byte.is_some() && !app.show_raw;
+    if app.unavailable.is_some() || summarized || app.max_scroll_offset() == 0 {
+        return;
+    }
+    let viewport = match app.filter_lines {
+        Some(_) => app.filter_lines_on_screen(app.last_visible_height).len(),
+        None => app.last_visible_height,
+    };
+    let mut state = scrollbar_state(app.scroll_offset, app.max_scroll_offset(), viewport);
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None)
+        .style(app.theme_colors().style())
+        .thumb_style(Style::default().fg(app.display_options().colors.address));
+    f.render_stateful_widget(scrollbar, area.inner(Margin { vertical: 1, horizontal: 0 }), &mut state);
+}
+
+/// Maps the scroll position to the scrollbar: the thumb's size is the fraction of the
+/// lines the `viewport` shows, and it reaches the bottom at `max_scroll_offset`
+fn scrollbar_state(scroll_offset: usize, max_scroll_offset: usize, viewport: usize) -> ScrollbarState {
+    // Scrolling stops with the last line at the top, which puts the thumb at the bottom
+    ScrollbarState::new(max_scroll_offset + 1)
+        .position(scroll_offset)
+        .viewport_content_length(viewport)
+}
+
+/// Renders the second file of a comparison in step with the first, highlighting
+/// the bytes that differ
+fn render_second_file(app: &mut App, area: Rect) -> Paragraph<'_> {
+    // Rows match the first file's, ruler included, so the two dumps line up
+    let rows = (area.height as usize).saturating_sub(2 + app.ruler_rows());
+    let top_line = app.top_line();
+    let data = app.second_file_data(top_line, rows);
+    let highlights = Highlights { diffs: &app.file_diffs, ..Highlights::default() };
+    let mut lines = format_hex_dump(&data, top_line, rows, app.bytes_per_line, highlights, app.display_options());
+    if app.ruler_rows() > 0 {
+        lines.insert(0, format_ruler(top_line * app.bytes_per_line, app.bytes_per_line, app.display_options()));
+    }
+    let title = app.second_file.as_ref().map(|second| second.path.clone()).unwrap_or_default();
+    Paragraph::new(Text::from(lines))
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .style(app.theme_colors().style())
+}
+
+/// Renders the overview pane: one row per equal slice of the file, colored by
+/// its dominant byte class, with `>` marking the rows the detail view covers
+fn render_overview(app: &mut App, area: Rect) -> Paragraph<'_> {
+    let rows = area.height.saturating_sub(2) as usize;
+    let width = area.width.saturating_sub(3) as usize; // Borders and the marker column
+    let cells = app.overview(rows);
+    let bucket = app.overview_bucket_size(rows);
+    let visible = app.visible_range();
+    let first = visible.start / bucket;
+    let last = visible.end.saturating_sub(1) / bucket;
+
+    let lines: Vec<Line> = cells
+        .into_iter()
+        .enumerate()
+        .map(|(row, cell)| {
+            let marker = if (first..=last).contains(&row) { ">" } else { " " };
+            // Color shows the dominant class and shading how little of the row is zero
+            let bar = match cell {
+                Some(cell) => Span::styled(cell.glyph().to_string().repeat(width), Style::default().fg(cell.class.color())),
+                None => Span::raw(""),
+            };
+            Line::from(vec![Span::raw(marker), bar])
         })
+        .collect();
+
+    Paragraph::new(Text::from(lines))
+        .block(Block::default().borders(Borders::ALL).title("Map"))
+        .style(app.theme_colors().style())
+}
+
+/// Renders the byte inspector: the bytes at the cursor read as each integer
+/// and float type, with "—" where the file ends too soon
+fn render_inspector(app: &mut App) -> Paragraph<'_> {
+    let offset = app.cursor;
+    let values = app.inspect_at(offset);
+    fn int(value: Option<impl Display>) -> Option<String> {
+        value.map(|v| v.to_string())
+    }
+    // Very large and very small magnitudes switch to exponent notation to fit the pane
+    fn float<T: Display + LowerExp + Into<f64> + Copy>(value: Option<T>) -> Option<String> {
+        value.map(|v| {
+            let magnitude = v.into().abs();
+            if magnitude == 0.0 || !magnitude.is_finite() || (1e-6..1e15).contains(&magnitude) {
+                v.to_string()
+            } else {
+                format!("{:e}", v)
+            }
+        })
+    }
+    let row = |name: &str, value: Option<String>| {
+        Line::from(format!("{:<4}{}", name, value.unwrap_or_else(|| "—".to_string())))
+    };
+    let lines = vec![
+        row("i8", int(values.i8)),
+        row("u8", int(values.u8)),
+        row("i16", int(values.i16)),
+        row("u16", int(values.u16)),
+        row("i32", int(values.i32)),
+        row("u32", int(values.u32)),
+        row("i64", int(values.i64)),
+        row("u64", int(values.u64)),
+        row("f32", float(values.f32)),
+        row("f64", float(values.f64)),
+    ];
+    let title = format!("Inspector {} 0x{}", app.endianness.name(), app.hex(offset, 1));
+    Paragraph::new(Text::from(lines))
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .style(app.theme_colors().style())
+}
+
+/// Renders the statistics panel for the bytes currently on screen
+fn render_stats(app: &mut App) -> Paragraph<'_> {
+    let (range, stats) = app.visible_stats();
+    let text = if stats.total == 0 {
+        "No bytes visible.".to_string()
+    } else {
+        let most_common = stats
+            .most_common
+            .map(|(byte, count)| format!("0x{} ×{}", app.hex(byte as usize, 2), count))
+            .unwrap_or_default();
+        format!(
+            "0x{}-0x{} | printable {}/{} | zero {} text {} ctrl {} high {} | most common {} | entropy {:.2} bits/byte",
+            app.hex(range.start, 1),
+            app.hex(range.end - 1, 1),
+            stats.printable,
+            stats.total,
+            stats.classes[ByteClass::Zero as usize],
+            stats.classes[ByteClass::Text as usize],
+            stats.classes[ByteClass::Control as usize],
+            stats.classes[ByteClass::High as usize],
+            most_common,
+            stats.entropy,
+        )
+    };
+    Paragraph::new(text)
+        .block(Block::default().borders(Borders::ALL).title("Visible Bytes"))
+        .style(app.theme_colors().style())
+}
+
+/// Returns the indent that sits the lines, as one block, centered or right-aligned
+/// within `width` columns. The margin is the same for every line, so columns stay aligned.
+fn align_margin(lines: &[Line<'static>], align: ContentAlign, width: usize) -> usize {
+    let widest = lines.iter().map(Line::width).max().unwrap_or(0);
+    match align {
+        ContentAlign::Left => 0,
+        ContentAlign::Center => width.saturating_sub(widest) / 2,
+        ContentAlign::Right => width.saturating_sub(widest),
+    }
+}
+
+/// Prefixes every line with `margin` spaces
+fn indent_lines(lines: Vec<Line<'static>>, margin: usize) -> Vec<Line<'static>> {
+    if margin == 0 {
+        return lines;
+    }
+    lines
+        .into_iter()
+        .map(|mut line| {
+            line.spans.insert(0, Span::raw(" ".repeat(margin)));
+            line
+        })
+        .collect()
+}
+
+/// Formats the filter view: only the filtered file lines, with `--` between non-adjacent runs
+fn format_filtered_view(app: &mut App, visible_height: usize) -> Vec<Line<'static>> {
+    let lines = app.filter_lines_on_screen(visible_height).to_vec();
+
+    let mut output = Vec::new();
+    let mut previous: Option<usize> = None;
+    for line in lines {
+        if previous.is_some_and(|p| p + 1 != line) {
+            output.push(Line::styled("--", Style::default().fg(Color::DarkGray)));
+        }
+        let chunk = app.read_bytes(line * app.bytes_per_line, app.bytes_per_line);
+        output.extend(format_hex_dump(
+            &chunk,
+            line,
+            1,
+            app.bytes_per_line,
+            app.highlights(),
+            app.display_options(),
+        ));
+        previous = Some(line);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::buffer::Buffer;
+    use ratatui::widgets::StatefulWidget;
+
+    /// Renders a scrollbar `height` rows tall, returning the first row and the length
+    /// of its thumb
+    fn thumb(scroll_offset: usize, max_scroll_offset: usize, viewport: usize, height: u16) -> (usize, usize) {
+        let area = Rect::new(0, 0, 1, height);
+        let mut buffer = Buffer::empty(area);
+        let mut state = scrollbar_state(scroll_offset, max_scroll_offset, viewport);
+        Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .render(area, &mut buffer, &mut state);
+        let rows: Vec<bool> = (0..height).map(|y| buffer[(0, y)].symbol() == "█").collect();
+        let first = rows.iter().position(|&on| on).unwrap();
+        (first, rows.iter().filter(|&&on| on).count())
+    }
+
+    #[test]
+    fn scrollbar_thumb_follows_the_scroll_position() {
+        assert_eq!(thumb(0, 99, 10, 10).0, 0);
+        let (first, len) = thumb(99, 99, 10, 10);
+        assert_eq!(first + len, 10);
+        let (first, _) = thumb(50, 99, 10, 10);
+        assert!((4..=5).contains(&first));
+    }
+
+    #[test]
+    fn scrollbar_thumb_size_is_the_visible_fraction() {
+        // The last of 109 lines reaches the top at offset 99, so 10 rows show 10/109
+        assert_eq!(thumb(0, 99, 10, 20).1, 2);
+        // and 50 rows show 50/149 of the lines
+        assert_eq!(thumb(0, 99, 50, 20).1, 7);
+        // Huge files still get a thumb at least one row long
+        assert_eq!(thumb(0, usize::MAX / 32, 40, 20).1, 1);
+    }
 }
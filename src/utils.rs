@@ -1,10 +1,672 @@
 // src/utils.rs
 
-use ratatui::style::{Color, Style};
+use md5::Md5;
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Span, Line};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::ops::Range;
 use std::fs::File;
-use std::io::{Seek, SeekFrom, Read};
+use std::io::{self, Seek, SeekFrom, Read};
+use twoway::find_bytes;
+
+/// Which bytes the ASCII column renders as characters rather than dots
+#[derive(Clone, Copy)]
+pub enum PrintablePolicy {
+    /// Graphic ASCII plus space (0x20..=0x7E)
+    Standard,
+    /// Graphic ASCII only; space is shown as a dot so padding stands out
+    Strict,
+    /// Standard plus the printable Latin-1 range (0xA1..=0xFF); NBSP shows as a space
+    Latin1,
+    /// User-provided byte set; accepted bytes are shown as their Latin-1 character
+    Custom([u64; 4]),
+}
+
+impl PrintablePolicy {
+    /// Returns true if the byte is rendered as a character under this policy
+    pub fn is_printable(&self, byte: u8) -> bool {
+        match self {
+            PrintablePolicy::Standard => byte.is_ascii_graphic() || byte == b' ',
+            PrintablePolicy::Strict => byte.is_ascii_graphic(),
+            PrintablePolicy::Latin1 => byte.is_ascii_graphic() || byte == b' ' || byte >= 0xA0,
+            PrintablePolicy::Custom(set) => set[byte as usize / 64] & (1 << (byte % 64)) != 0,
+        }
+    }
+
+    /// Builds a custom policy from a list of hex byte ranges, e.g. `20-7e,a1-ff`
+    pub fn from_ranges(spec: &str) -> Option<Self> {
+        let mut set = [0u64; 4];
+        for part in spec.split(',') {
+            let (start, end) = match part.trim().split_once('-') {
+                Some((start, end)) => (start, end),
+                None => (part.trim(), part.trim()),
+            };
+            let start = u8::from_str_radix(start, 16).ok()?;
+            let end = u8::from_str_radix(end, 16).ok()?;
+            if start > end {
+                return None;
+            }
+            for byte in start..=end {
+                set[byte as usize / 64] |= 1 << (byte % 64);
+            }
+        }
+        Some(PrintablePolicy::Custom(set))
+    }
+}
+
+/// How the ASCII column renders bytes outside the printable set
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AsciiRender {
+    /// A dot for every non-printable byte
+    Dots,
+    /// Unicode control pictures (␀, ␊, ...) for C0 controls and DEL, dots otherwise
+    Glyphs,
+    /// Code page 437 glyphs for every byte
+    Codepage,
+    /// `\xNN` escape notation for every non-printable byte
+    Escape,
+}
+
+impl AsciiRender {
+    /// Returns the next rendering in the cycle
+    pub fn next(self) -> Self {
+        match self {
+            AsciiRender::Dots => AsciiRender::Glyphs,
+            AsciiRender::Glyphs => AsciiRender::Codepage,
+            AsciiRender::Codepage => AsciiRender::Escape,
+            AsciiRender::Escape => AsciiRender::Dots,
+        }
+    }
+
+    /// Short name shown in the status line
+    pub fn name(self) -> &'static str {
+        match self {
+            AsciiRender::Dots => "dots",
+            AsciiRender::Glyphs => "glyphs",
+            AsciiRender::Codepage => "cp437",
+            AsciiRender::Escape => "escape",
+        }
+    }
+}
+
+/// Code page 437 glyphs for bytes 0x00..=0x1F
+const CP437_LOW: [char; 32] = [
+    ' ', '☺', '☻', '♥', '♦', '♣', '♠', '•', '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼',
+    '►', '◄', '↕', '‼', '¶', '§', '▬', '↨', '↑', '↓', '→', '←', '∟', '↔', '▲', '▼',
+];
+
+/// Code page 437 glyphs for bytes 0x80..=0xFF
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+/// How the address gutter shows each line's offset
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AddressStyle {
+    /// Flat linear offset, e.g. `00012345`
+    Linear,
+    /// Real-mode `SSSS:OOOO` with 16-byte paragraphs, e.g. `1234:0005`
+    Segmented,
+}
+
+/// Byte order used to read multi-byte values
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+impl Endianness {
+    /// Short label for panel titles
+    pub fn name(self) -> &'static str {
+        match self {
+            Endianness::Little => "LE",
+            Endianness::Big => "BE",
+        }
+    }
+}
+
+/// Per-line checksum shown after the ASCII column
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LineChecksum {
+    /// XOR of the line's bytes
+    Xor,
+    /// Sum of the line's bytes modulo 256
+    Sum,
+}
+
+impl LineChecksum {
+    /// Computes the checksum of one line
+    pub fn compute(self, bytes: &[u8]) -> u8 {
+        match self {
+            LineChecksum::Xor => bytes.iter().fold(0, |acc, &byte| acc ^ byte),
+            LineChecksum::Sum => bytes.iter().fold(0, |acc: u8, &byte| acc.wrapping_add(byte)),
+        }
+    }
+
+    /// Short name shown in the status line
+    pub fn name(self) -> &'static str {
+        match self {
+            LineChecksum::Xor => "xor",
+            LineChecksum::Sum => "sum",
+        }
+    }
+}
+
+/// Number base of the byte cells in the hex column
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayRadix {
+    #[default]
+    Hex,
+    Octal,
+    Decimal,
+    Binary,
+}
+
+impl DisplayRadix {
+    /// Columns one byte takes in this base, without its trailing space
+    pub fn digits(self) -> usize {
+        match self {
+            DisplayRadix::Hex => 2,
+            DisplayRadix::Octal | DisplayRadix::Decimal => 3,
+            DisplayRadix::Binary => 8,
+        }
+    }
+
+    /// Formats one byte in this base, padded to `digits()` columns
+    pub fn format(self, byte: u8, uppercase: bool) -> String {
+        match self {
+            DisplayRadix::Hex => format_hex(byte as usize, 2, uppercase),
+            DisplayRadix::Octal => format!("{:03o}", byte),
+            DisplayRadix::Decimal => format!("{:3}", byte),
+            DisplayRadix::Binary => format!("{:08b}", byte),
+        }
+    }
+
+    /// Short name shown in messages
+    pub fn name(self) -> &'static str {
+        match self {
+            DisplayRadix::Hex => "hex",
+            DisplayRadix::Octal => "octal",
+            DisplayRadix::Decimal => "decimal",
+            DisplayRadix::Binary => "binary",
+        }
+    }
+}
+
+/// Checksums computed over a selection or the whole file
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgo {
+    /// CRC-32 as used by zip, PNG and zlib
+    Crc32,
+    /// Adler-32 as used by zlib streams
+    Adler32,
+    /// Sum of the bytes modulo 2^32
+    Sum,
+}
+
+impl ChecksumAlgo {
+    /// Computes the checksum of `bytes` in one go
+    pub fn compute(self, bytes: &[u8]) -> u32 {
+        let mut state = ChecksumState::new(self);
+        state.update(bytes);
+        state.finish()
+    }
+
+    /// Short name shown in messages
+    pub fn name(self) -> &'static str {
+        match self {
+            ChecksumAlgo::Crc32 => "CRC32",
+            ChecksumAlgo::Adler32 => "Adler32",
+            ChecksumAlgo::Sum => "sum",
+        }
+    }
+}
+
+/// CRC-32 remainders of each byte value (reflected polynomial 0xEDB88320)
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Modulus of the Adler-32 sums
+const ADLER_MOD: u32 = 65521;
+
+/// A checksum fed a chunk at a time, so large files can be streamed through it
+#[derive(Clone, Copy)]
+pub struct ChecksumState {
+    algo: ChecksumAlgo,
+    a: u32, // The CRC register, the sum, or Adler-32's byte sum
+    b: u32, // Adler-32's sum of sums
+}
+
+impl ChecksumState {
+    pub fn new(algo: ChecksumAlgo) -> Self {
+        let a = match algo {
+            ChecksumAlgo::Crc32 => !0,
+            ChecksumAlgo::Adler32 => 1,
+            ChecksumAlgo::Sum => 0,
+        };
+        Self { algo, a, b: 0 }
+    }
+
+    /// Adds the next bytes of the input
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self.algo {
+            ChecksumAlgo::Crc32 => {
+                for &byte in bytes {
+                    self.a = CRC32_TABLE[((self.a ^ byte as u32) & 0xFF) as usize] ^ (self.a >> 8);
+                }
+            }
+            ChecksumAlgo::Adler32 => {
+                for &byte in bytes {
+                    self.a = (self.a + byte as u32) % ADLER_MOD;
+                    self.b = (self.b + self.a) % ADLER_MOD;
+                }
+            }
+            ChecksumAlgo::Sum => {
+                self.a = bytes.iter().fold(self.a, |acc, &byte| acc.wrapping_add(byte as u32));
+            }
+        }
+    }
+
+    /// Returns the checksum of all the bytes added
+    pub fn finish(self) -> u32 {
+        match self.algo {
+            ChecksumAlgo::Crc32 => !self.a,
+            ChecksumAlgo::Adler32 => (self.b << 16) | self.a,
+            ChecksumAlgo::Sum => self.a,
+        }
+    }
+}
+
+/// Cryptographic digests of the whole file
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DigestAlgo {
+    Sha256,
+    Md5,
+}
+
+impl DigestAlgo {
+    /// Short name shown in messages
+    pub fn name(self) -> &'static str {
+        match self {
+            DigestAlgo::Sha256 => "SHA-256",
+            DigestAlgo::Md5 => "MD5",
+        }
+    }
+}
+
+/// A digest fed a chunk at a time, so large files can be streamed through it
+pub enum DigestState {
+    Sha256(Sha256),
+    Md5(Md5),
+}
+
+impl DigestState {
+    pub fn new(algo: DigestAlgo) -> Self {
+        match algo {
+            DigestAlgo::Sha256 => DigestState::Sha256(Sha256::new()),
+            DigestAlgo::Md5 => DigestState::Md5(Md5::new()),
+        }
+    }
+
+    /// Adds the next bytes of the input
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            DigestState::Sha256(hasher) => hasher.update(bytes),
+            DigestState::Md5(hasher) => hasher.update(bytes),
+        }
+    }
+
+    /// Returns the digest of all the bytes added
+    pub fn finish(self) -> Vec<u8> {
+        match self {
+            DigestState::Sha256(hasher) => hasher.finalize().to_vec(),
+            DigestState::Md5(hasher) => hasher.finalize().to_vec(),
+        }
+    }
+}
+
+/// Text layout for bytes copied to the clipboard
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// Hex digits with no separators, e.g. `deadbeef`
+    Hex,
+    /// Hex digits separated by spaces, e.g. `de ad be ef`
+    HexSpaced,
+    /// Printable ASCII as-is, every other byte as `.`
+    Ascii,
+}
+
+impl CopyFormat {
+    /// Formats bytes as clipboard text
+    pub fn format(self, bytes: &[u8], uppercase: bool) -> String {
+        match self {
+            CopyFormat::Hex => bytes.iter().map(|&b| format_hex(b as usize, 2, uppercase)).collect(),
+            CopyFormat::HexSpaced => bytes
+                .iter()
+                .map(|&b| format_hex(b as usize, 2, uppercase))
+                .collect::<Vec<_>>()
+                .join(" "),
+            CopyFormat::Ascii => bytes
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect(),
+        }
+    }
+
+    /// Name used in confirmation messages
+    pub fn name(self) -> &'static str {
+        match self {
+            CopyFormat::Hex => "hex",
+            CopyFormat::HexSpaced => "spaced hex",
+            CopyFormat::Ascii => "ASCII",
+        }
+    }
+}
+
+/// Formats bytes as a C array definition, `bytes_per_line` values to a line,
+/// preceded by a comment giving the length
+pub fn format_c_array(bytes: &[u8], name: &str, bytes_per_line: usize, uppercase: bool) -> String {
+    let mut out = format!("/* {} bytes */\nunsigned char {}[] = {{\n", bytes.len(), name);
+    for line in bytes.chunks(bytes_per_line.max(1)) {
+        let values: Vec<String> = line.iter().map(|&b| format!("0x{}", format_hex(b as usize, 2, uppercase))).collect();
+        out.push_str(&format!("    {},\n", values.join(", ")));
+    }
+    out.push_str("};\n");
+    out
+}
+
+/// A remembered file offset, optionally named
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub offset: usize,
+    pub name: Option<String>,
+}
+
+/// Background colors given to highlight terms, in the order they're handed out
+pub const TERM_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::LightGreen,
+    Color::LightMagenta,
+    Color::LightBlue,
+    Color::LightRed,
+    Color::Gray,
+];
+
+/// A pattern highlighted in its own color alongside the search matches
+#[derive(Clone)]
+pub struct HighlightTerm {
+    pub label: String,
+    pub ranges: Vec<Range<usize>>, // Sorted and non-overlapping
+    pub color: usize,              // Index into TERM_COLORS
+}
+
+/// Colors of the hex dump, from the theme. A custom theme file may set any of
+/// them; the rest keep these defaults.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorScheme {
+    pub address: Color,
+    pub hex_byte: Color,           // Bytes in no other category
+    pub ascii_printable: Color,    // 0x20..=0x7E
+    pub ascii_nonprintable: Color, // Collapsed runs in the ASCII column
+    pub null_byte: Color,
+    pub ff_byte: Color,
+    pub match_fg: Color,
+    pub match_bg: Color,
+    pub background: Color,
+    pub foreground: Color, // Text outside the dump, such as titles and messages
+}
+
+impl Default for ColorScheme {
+    fn default() -> Self {
+        Self {
+            address: Color::Blue,
+            hex_byte: Color::Cyan,
+            ascii_printable: Color::Green,
+            ascii_nonprintable: Color::DarkGray,
+            null_byte: Color::DarkGray,
+            ff_byte: Color::Red,
+            match_fg: Color::Black,
+            match_bg: Color::Yellow,
+            background: Color::Black,
+            foreground: Color::White,
+        }
+    }
+}
+
+/// Returns the color of a byte in both dump columns: 0x00 and 0xFF each have their
+/// own, printable ASCII another, and all remaining bytes the plain hex color
+pub fn byte_color(byte: u8, scheme: &ColorScheme) -> Color {
+    match byte {
+        0x00 => scheme.null_byte,
+        0xFF => scheme.ff_byte,
+        0x20..=0x7E => scheme.ascii_printable,
+        _ => scheme.hex_byte,
+    }
+}
+
+/// Display settings for the hex dump
+#[derive(Clone, Copy)]
+pub struct DisplayOptions {
+    pub printable: PrintablePolicy,
+    pub ascii_render: AsciiRender,
+    pub reversed_line: Option<usize>, // File line shown in reverse byte order
+    pub uppercase: bool,              // Hex digits in upper case
+    pub checksum: Option<LineChecksum>, // Trailing per-line checksum column
+    pub compact_runs: bool,             // Collapse runs of a low byte in the ASCII column
+    pub address_style: AddressStyle,
+    pub address_width: usize, // Hex digits of a linear address
+    pub base_address: usize,  // Address shown for file offset 0
+    pub group_size: usize, // Bytes per group in the hex column, split by an extra space; 0 doesn't group
+    pub radix: DisplayRadix, // Base the hex column shows bytes in
+    pub overlay: bool, // Each hex cell has room for a second file's differing byte, as `de→ad`
+    pub colors: ColorScheme,
+}
+
+impl DisplayOptions {
+    /// Returns whether the hex column puts an extra space before the `column`th byte of a line
+    pub fn group_break(&self, column: usize) -> bool {
+        self.group_size > 0 && column > 0 && column.is_multiple_of(self.group_size)
+    }
+
+    /// Returns the columns taken by `count` byte cells of the hex column, including
+    /// group separators
+    pub fn hex_width(&self, count: usize) -> usize {
+        let breaks = count.saturating_sub(1).checked_div(self.group_size).unwrap_or(0);
+        self.cell_width() * count + breaks
+    }
+
+    /// Returns the columns one byte's cell takes in the hex column, trailing space included
+    pub fn cell_width(&self) -> usize {
+        let digits = self.radix.digits();
+        if self.overlay {
+            2 * digits + 2 // The byte, '→' and the other file's byte
+        } else {
+            digits + 1
+        }
+    }
+
+    /// Formats one byte's hex column cell. With the overlay, `other` is the second file's
+    /// byte at the same offset, shown after '→' where it differs, or `--` past its end.
+    fn hex_cell(&self, byte: u8, other: Option<Option<u8>>) -> String {
+        let digits = self.radix.format(byte, self.uppercase);
+        match other {
+            Some(Some(other)) if other != byte => format!("{}→{} ", digits, self.radix.format(other, self.uppercase)),
+            Some(None) => format!("{}→{} ", digits, "-".repeat(self.radix.digits())),
+            _ => format!("{:width$}", digits, width = self.cell_width()),
+        }
+    }
+}
+
+/// Shortest run of one low byte that the ASCII column collapses when compacting
+const COMPACT_RUN_MIN: usize = 4;
+
+/// Byte ranges the hex dump highlights, by kind
+#[derive(Clone, Copy, Default)]
+pub struct Highlights<'a> {
+    pub matches: &'a [Range<usize>], // Search results
+    pub groups: &'a [Vec<Range<usize>>], // Capture groups of a regex search, by group; each list sorted
+    pub overlay: Option<&'a [u8]>, // Second file's bytes at the dump's offsets, for the overlay
+    pub diffs: &'a [Range<usize>],   // Bytes that differ from a comparison source
+    pub holes: &'a [Range<usize>],   // Unallocated extents of a sparse file
+    pub inspected: Option<&'a Range<usize>>, // Bytes consumed by the last decode
+    pub agreements: &'a [Range<usize>], // Bytes equal to a comparison source
+    pub selection: Option<&'a Range<usize>>, // Bytes selected with shift+arrows or a mouse drag
+    pub block_width: Option<usize>, // Line width when the selection is a block, which keeps only its columns
+    pub cursor: Option<usize>, // Selected byte, drawn in reverse video over any other highlight
+    pub bookmarks: &'a [Bookmark], // Sorted by offset; marked in the address gutter
+    pub terms: &'a [HighlightTerm], // Highlight terms, in the order they were added
+}
+
+impl Highlights<'_> {
+    /// Returns the highlight style for the byte at `index`, if any applies.
+    /// The selection takes precedence over decoded bytes, those over comparison results,
+    /// those over regex capture groups (a later group, such as one nested in another,
+    /// over an earlier one), groups over the rest of a search match, matches over
+    /// highlight terms (a later term over an earlier one), and terms over holes.
+    /// The cursor reverses whichever style applies.
+    fn style_at(&self, index: usize, colors: &ColorScheme) -> Option<Style> {
+        // Each list is sorted and non-overlapping, so only the last range starting at or
+        // before the byte can hold it; file diffs can run to millions of ranges
+        let contains = |ranges: &[Range<usize>]| {
+            let i = ranges.partition_point(|range| range.start <= index);
+            i > 0 && ranges[i - 1].contains(&index)
+        };
+        let selected = |range: &&Range<usize>| match self.block_width {
+            Some(width) => BlockSelection::from_range(range, width).contains(index, width),
+            None => range.contains(&index),
+        };
+        let style = if self.selection.is_some_and(|range| selected(&range)) {
+            Some(Style::default().bg(Color::Magenta).fg(Color::White))
+        } else if self.inspected.is_some_and(|range| range.contains(&index)) {
+            Some(Style::default().bg(Color::Blue).fg(Color::White))
+        } else if contains(self.diffs) {
+            Some(Style::default().bg(Color::Red).fg(Color::White))
+        } else if contains(self.agreements) {
+            Some(Style::default().bg(Color::Green).fg(Color::Black))
+        } else if let Some(group) = (0..self.groups.len()).rev().find(|&group| contains(&self.groups[group])) {
+            Some(Style::default().bg(TERM_COLORS[group % TERM_COLORS.len()]).fg(Color::Black))
+        } else if contains(self.matches) {
+            Some(Style::default().bg(colors.match_bg).fg(colors.match_fg))
+        } else if let Some(term) = self.terms.iter().rev().find(|term| contains(&term.ranges)) {
+            Some(Style::default().bg(TERM_COLORS[term.color]).fg(Color::Black))
+        } else if contains(self.holes) {
+            Some(Style::default().fg(Color::DarkGray))
+        } else {
+            None
+        };
+        if self.cursor == Some(index) {
+            Some(style.unwrap_or_default().add_modifier(Modifier::REVERSED))
+        } else {
+            style
+        }
+    }
+}
+
+/// Formats a value as zero-padded hex digits (no prefix) in the requested case.
+/// All hex shown in the UI goes through here so the case setting stays consistent.
+pub fn format_hex(value: usize, width: usize, uppercase: bool) -> String {
+    if uppercase {
+        format!("{:0width$X}", value, width = width)
+    } else {
+        format!("{:0width$x}", value, width = width)
+    }
+}
+
+/// Returns the hex digits a linear address needs in a file of `size` bytes: enough for
+/// any offset up to its end, rounded up to whole bytes, and at least 6
+pub fn address_digits(size: usize) -> usize {
+    let digits = (usize::BITS - size.leading_zeros()).div_ceil(4) as usize;
+    digits.next_multiple_of(2).max(6)
+}
+
+/// Highest address a real-mode `SSSS:OOOO` pair can reach, `FFFF:FFFF`
+const SEGMENTED_MAX: usize = 0xFFFF * 16 + 0xFFFF;
+
+/// Formats a line's address for the gutter in the configured style, counting from
+/// the base address
+pub fn format_address(addr: usize, options: DisplayOptions) -> String {
+    let addr = options.base_address.wrapping_add(addr);
+    match options.address_style {
+        AddressStyle::Linear => format_hex(addr, options.address_width, options.uppercase),
+        // Past FFFF:FFFF no segment can reach the address, so it falls back to linear,
+        // padded to the segmented width to keep the gutter aligned
+        AddressStyle::Segmented if addr > SEGMENTED_MAX => format_hex(addr, options.address_width.max(9), options.uppercase),
+        AddressStyle::Segmented => {
+            // The segment tops out at FFFF, carrying the rest of the address into the offset
+            let segment = (addr >> 4).min(0xFFFF);
+            format!(
+                "{}:{}",
+                format_hex(segment, 4, options.uppercase),
+                format_hex(addr - (segment << 4), 4, options.uppercase)
+            )
+        }
+    }
+}
+
+/// Formats bytes as plain `offset: hex  ascii` text lines, numbering from `start`.
+/// Used for exports, so it has no styling, highlights or view-only columns.
+pub fn format_plain_dump(data: &[u8], start: usize, bytes_per_line: usize, options: DisplayOptions) -> String {
+    let mut out = String::new();
+    for (i, chunk) in data.chunks(bytes_per_line).enumerate() {
+        out.push_str(&format_address(start + i * bytes_per_line, options));
+        out.push_str(": ");
+        for (j, &byte) in chunk.iter().enumerate() {
+            if options.group_break(j) {
+                out.push(' ');
+            }
+            out.push_str(&options.radix.format(byte, options.uppercase));
+            out.push(' ');
+        }
+        out.push_str(&" ".repeat(options.hex_width(bytes_per_line) - options.hex_width(chunk.len())));
+        out.push_str("  ");
+        for &byte in chunk {
+            out.push_str(&byte_to_displayable(byte, options.printable, options));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Returns the most bytes per line, as a multiple of 8, whose dump fits in `width`
+/// columns (at least 8). Each byte takes its cell in the hex column (plus group
+/// separators) and one column in the ASCII column, after the address gutter of the widest address.
+pub fn fit_bytes_per_line(width: usize, last_address: usize, options: DisplayOptions) -> usize {
+    // Address and ": ", then the gap before the ASCII column
+    let mut overhead = format_address(last_address, options).len() + 2 + 2;
+    if options.checksum.is_some() {
+        overhead += 6; // "  = xx"
+    }
+    let line_width = |count: usize| overhead + options.hex_width(count) + count;
+    let mut fit = 8;
+    while line_width(fit + 8) <= width {
+        fit += 8;
+    }
+    fit
+}
 
 /// Formats the hex dump with color coding and highlights search results.
 /// Returns a vector of Lines that can be directly displayed in the Paragraph widget.
@@ -13,7 +675,8 @@ pub fn format_hex_dump(
     scroll_offset: usize,
     lines: usize,
     bytes_per_line: usize,
-    search_results: &Vec<Range<usize>>,
+    highlights: Highlights,
+    options: DisplayOptions,
 ) -> Vec<Line<'static>> {
     let mut output = Vec::new();
     let start_addr = scroll_offset * bytes_per_line;
@@ -22,44 +685,88 @@ pub fn format_hex_dump(
         let addr = start_addr + i * bytes_per_line;
         let mut spans = Vec::new();
 
-        // Address
+        // A reversed line shows its bytes last-to-first, marked by '<' after the address
+        let reversed = options.reversed_line == Some(scroll_offset + i);
+        let order: Vec<usize> = if reversed {
+            (0..chunk.len()).rev().collect()
+        } else {
+            (0..chunk.len()).collect()
+        };
+
+        // Address, then a marker in place of the space when a bookmark falls on the line
         spans.push(Span::styled(
-            format!("{:08x}: ", addr),
-            Style::default().fg(Color::Blue),
+            format!("{}{}", format_address(addr, options), if reversed { '<' } else { ':' }),
+            Style::default().fg(options.colors.address),
         ));
+        let first = highlights.bookmarks.partition_point(|bookmark| bookmark.offset < addr);
+        let marked = highlights.bookmarks.get(first).is_some_and(|bookmark| bookmark.offset < addr + chunk.len());
+        if marked {
+            spans.push(Span::styled("*", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)));
+        } else {
+            spans.push(Span::raw(" "));
+        }
 
         // Hexadecimal representation
-        for (j, byte) in chunk.iter().enumerate() {
-            let global_index = addr + j;
-            let is_match = search_results.iter().any(|range| range.contains(&global_index));
-            let style = if is_match {
-                Style::default().bg(Color::Yellow).fg(Color::Black)
-            } else {
-                Style::default().fg(Color::Cyan)
-            };
-            spans.push(Span::styled(format!("{:02x} ", byte), style));
+        let mut previous: Option<Style> = None; // Highlight of the cell before, minus the cursor
+        for (column, &j) in order.iter().enumerate() {
+            let byte = chunk[j];
+            let highlight = highlights.style_at(addr + j, &options.colors);
+            let unreversed = highlight.map(|style| style.remove_modifier(Modifier::REVERSED));
+            if options.group_break(column) {
+                // A highlight running across a group boundary spans the separator too
+                match previous.filter(|_| previous == unreversed) {
+                    Some(style) => spans.push(Span::styled(" ", style)),
+                    None => spans.push(Span::raw(" ")),
+                }
+            }
+            previous = unreversed;
+            let style = highlight.unwrap_or_else(|| Style::default().fg(byte_color(byte, &options.colors)));
+            let other = highlights.overlay.map(|other| other.get(i * bytes_per_line + j).copied());
+            spans.push(Span::styled(options.hex_cell(byte, other), style));
         }
 
         // Padding for incomplete lines
         if chunk.len() < bytes_per_line {
-            spans.push(Span::raw("   ".repeat(bytes_per_line - chunk.len())));
+            let missing = options.hex_width(bytes_per_line) - options.hex_width(chunk.len());
+            spans.push(Span::raw(" ".repeat(missing)));
         }
 
         spans.push(Span::raw("  "));
 
         // ASCII representation
-        for (j, byte) in chunk.iter().enumerate() {
-            let global_index = addr + j;
-            let is_match = search_results.iter().any(|range| range.contains(&global_index));
-            let display_char = byte_to_displayable(*byte);
-            let style = if is_match {
-                Style::default().bg(Color::Yellow).fg(Color::Black)
-            } else if display_char == '.' {
-                Style::default().fg(Color::DarkGray)
+        let ascii_start = spans.len();
+        let mut k = 0;
+        while k < order.len() {
+            let (j, byte) = (order[k], chunk[order[k]]);
+            let plain = |i: usize| chunk[i] == byte && highlights.style_at(addr + i, &options.colors).is_none();
+            let run = if options.compact_runs && byte < 0x20 && plain(j) {
+                order[k..]
+                    .iter()
+                    .take_while(|&&i| plain(i))
+                    .count()
             } else {
-                Style::default().fg(Color::Green)
+                1
             };
-            spans.push(Span::styled(display_char.to_string(), style));
+            if run >= COMPACT_RUN_MIN {
+                // A run of one low byte collapses to e.g. `.×12`; the hex column keeps every byte
+                let glyph = byte_to_displayable(byte, options.printable, options);
+                let style = Style::default().fg(options.colors.ascii_nonprintable);
+                spans.push(Span::styled(format!("{}×{}", glyph, run), style));
+                k += run;
+            } else {
+                spans.push(ascii_span(byte, addr + j, highlights, options));
+                k += 1;
+            }
+        }
+        let ascii_width: usize = spans[ascii_start..].iter().map(Span::width).sum();
+
+        // Checksum of the line's bytes, padded past a short or compacted ASCII column
+        if let Some(checksum) = options.checksum {
+            spans.push(Span::raw(" ".repeat(bytes_per_line.saturating_sub(ascii_width) + 2)));
+            spans.push(Span::styled(
+                format!("= {}", format_hex(checksum.compute(chunk) as usize, 2, options.uppercase)),
+                Style::default().fg(Color::Magenta),
+            ));
         }
 
         output.push(Line::from(spans));
@@ -68,33 +775,1059 @@ pub fn format_hex_dump(
     output
 }
 
-/// Converts a byte to a displayable character.
-/// Printable ASCII characters are displayed as-is, others are represented by a dot.
-fn byte_to_displayable(byte: u8) -> char {
-    if byte.is_ascii_graphic() || byte == b' ' {
-        byte as char
-    } else {
-        '.'
+/// Formats the column ruler drawn above the hex dump: each column's index within the
+/// line (its low byte, past 0xff) over its hex cell, and the index's last hex digit
+/// over its ASCII cell. `addr`
+/// is the address of a line below it, whose gutter the ruler leaves blank.
+pub fn format_ruler(addr: usize, bytes_per_line: usize, options: DisplayOptions) -> Line<'static> {
+    let mut text = " ".repeat(format_address(addr, options).len() + 2);
+    let digits = options.radix.digits();
+    for column in 0..bytes_per_line {
+        if options.group_break(column) {
+            text.push(' ');
+        }
+        // Two digits fit every radix's cell, so wide lines wrap the label rather than the ruler
+        let label = format!("{:>digits$} ", format_hex(column & 0xFF, 2, options.uppercase));
+        text.push_str(&format!("{:<width$}", label, width = options.cell_width()));
+    }
+    text.push_str("  ");
+    for column in 0..bytes_per_line {
+        text.push_str(&format_hex(column & 0xF, 1, options.uppercase));
     }
+    Line::styled(text, Style::default().fg(options.colors.address))
+}
+
+/// Formats the data as fixed-width text records: one row of `record_width`
+/// decoded characters per line with no hex column, for card-image style files.
+/// Characters are decoded with the same printable set and ASCII rendering as the
+/// hex dump's ASCII column; the final record may be short.
+pub fn format_text_records(
+    data: &[u8],
+    scroll_offset: usize,
+    lines: usize,
+    record_width: usize,
+    highlights: Highlights,
+    options: DisplayOptions,
+) -> Vec<Line<'static>> {
+    let start_addr = scroll_offset * record_width;
+    data.chunks(record_width)
+        .enumerate()
+        .take(lines)
+        .map(|(i, record)| {
+            let addr = start_addr + i * record_width;
+            let mut spans = vec![Span::styled(
+                format!("{}: ", format_address(addr, options)),
+                Style::default().fg(options.colors.address),
+            )];
+            for (j, &byte) in record.iter().enumerate() {
+                spans.push(ascii_span(byte, addr + j, highlights, options));
+            }
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Builds the styled ASCII-column cell for one byte
+fn ascii_span(
+    byte: u8,
+    global_index: usize,
+    highlights: Highlights,
+    options: DisplayOptions,
+) -> Span<'static> {
+    let display = byte_to_displayable(byte, options.printable, options);
+    let style = highlights
+        .style_at(global_index, &options.colors)
+        .unwrap_or_else(|| Style::default().fg(byte_color(byte, &options.colors)));
+    Span::styled(display, style)
 }
 
-/// Reads the file in chunks for lazy loading.
-/// Returns an empty vector if seeking fails or no bytes are read.
-pub fn read_file_chunk(file: &mut File, offset: usize, bytes_per_line: usize, lines: usize) -> Vec<u8> {
-    let mut buffer = vec![0; bytes_per_line * lines];
-    let seek_position = (offset * bytes_per_line) as u64;
-    if let Err(e) = file.seek(SeekFrom::Start(seek_position)) {
-        eprintln!("Error seeking to position {:#x}: {}", seek_position, e);
-        return Vec::new();
+/// Converts a byte to its ASCII-column text.
+/// Bytes accepted by the policy are displayed as-is; the others are rendered
+/// according to the selected `AsciiRender`.
+fn byte_to_displayable(byte: u8, printable: PrintablePolicy, options: DisplayOptions) -> String {
+    // Control bytes are never written raw, whatever the policy says
+    if printable.is_printable(byte) && !(byte as char).is_control() {
+        return if byte == 0xA0 {
+            ' '.to_string() // NBSP renders as a plain space so the column stays one cell wide
+        } else {
+            (byte as char).to_string()
+        };
     }
-    match file.read(&mut buffer) {
-        Ok(bytes_read) => {
-            buffer.truncate(bytes_read);
-            buffer
+    match options.ascii_render {
+        AsciiRender::Dots => '.'.to_string(),
+        AsciiRender::Glyphs => match byte {
+            0x00..=0x1F => char::from_u32(0x2400 + byte as u32).unwrap_or('.').to_string(),
+            0x20 => '␠'.to_string(),
+            0x7F => '␡'.to_string(),
+            _ => '.'.to_string(),
+        },
+        AsciiRender::Codepage => match byte {
+            0x00..=0x1F => CP437_LOW[byte as usize].to_string(),
+            0x7F => '⌂'.to_string(),
+            0x80..=0xFF => CP437_HIGH[byte as usize - 0x80].to_string(),
+            _ => (byte as char).to_string(),
+        },
+        AsciiRender::Escape => format!("\\x{}", format_hex(byte as usize, 2, options.uppercase)),
+    }
+}
+
+/// Broad category of a byte, used to color summaries of large regions
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ByteClass {
+    Zero,
+    Text,    // Printable ASCII and common whitespace
+    Control, // Other bytes below 0x80
+    High,    // 0x80..=0xFF
+}
+
+impl ByteClass {
+    /// Color used for this class in the overview pane
+    pub fn color(self) -> Color {
+        match self {
+            ByteClass::Zero => Color::DarkGray,
+            ByteClass::Text => Color::Green,
+            ByteClass::Control => Color::Yellow,
+            ByteClass::High => Color::Magenta,
+        }
+    }
+}
+
+/// Classifies a single byte
+pub fn classify_byte(byte: u8) -> ByteClass {
+    match byte {
+        0x00 => ByteClass::Zero,
+        b'\t' | b'\n' | b'\r' | 0x20..=0x7E => ByteClass::Text,
+        0x01..=0x7F => ByteClass::Control,
+        _ => ByteClass::High,
+    }
+}
+
+/// Returns the most common byte class in `data`, or None if it is empty
+pub fn dominant_class(data: &[u8]) -> Option<ByteClass> {
+    let mut counts = [0usize; 4];
+    for &byte in data {
+        counts[classify_byte(byte) as usize] += 1;
+    }
+    let classes = [ByteClass::Zero, ByteClass::Text, ByteClass::Control, ByteClass::High];
+    classes
+        .into_iter()
+        .zip(counts)
+        .filter(|&(_, count)| count > 0)
+        .max_by_key(|&(_, count)| count)
+        .map(|(class, _)| class)
+}
+
+/// One row of the overview map: the most common byte class in a slice of the file,
+/// and how much of the slice isn't zero
+#[derive(Clone, Copy)]
+pub struct OverviewCell {
+    pub class: ByteClass,
+    pub density: f64, // Fraction of non-zero bytes, 0.0 to 1.0
+}
+
+impl OverviewCell {
+    /// Shade the cell is drawn with, from light for mostly zeros to solid for none
+    pub fn glyph(self) -> char {
+        match self.density {
+            d if d < 0.25 => '░',
+            d if d < 0.5 => '▒',
+            d if d < 0.75 => '▓',
+            _ => '█',
+        }
+    }
+}
+
+/// Summarizes `data` as an overview row, or None if it is empty
+pub fn overview_cell(data: &[u8]) -> Option<OverviewCell> {
+    let class = dominant_class(data)?;
+    let nonzero = data.iter().filter(|&&byte| byte != 0).count();
+    Some(OverviewCell { class, density: nonzero as f64 / data.len() as f64 })
+}
+
+/// Finds all non-overlapping occurrences of a pattern with `??` wildcards (None)
+/// that start at a multiple of `alignment`. Patterns without wildcards use the
+/// fast exact search.
+pub fn find_pattern(data: &[u8], pattern: &[Option<u8>], alignment: usize) -> Vec<Range<usize>> {
+    if let Some(exact) = pattern.iter().copied().collect::<Option<Vec<u8>>>() {
+        return find_all(data, &exact, alignment);
+    }
+    let mut results = Vec::new();
+    if pattern.is_empty() {
+        return results;
+    }
+    let alignment = alignment.max(1);
+    let mut pos = 0;
+    while pos + pattern.len() <= data.len() {
+        let window = &data[pos..pos + pattern.len()];
+        let matched = pattern
+            .iter()
+            .zip(window)
+            .all(|(expected, byte)| expected.is_none_or(|expected| expected == *byte));
+        if matched {
+            results.push(pos..pos + pattern.len());
+            pos += pattern.len();
+            pos = pos.next_multiple_of(alignment);
+        } else {
+            pos += alignment;
+        }
+    }
+    results
+}
+
+/// Byte statistics for a region of the file
+#[derive(Clone, Copy, Default)]
+pub struct RegionStats {
+    pub total: usize,
+    pub printable: usize,    // Bytes the ASCII column shows as characters
+    pub classes: [usize; 4], // Counts indexed by ByteClass
+    pub most_common: Option<(u8, usize)>, // Most frequent byte and its count
+    pub entropy: f64, // Shannon entropy in bits per byte, 0.0 to 8.0
+}
+
+/// Returns the Shannon entropy of bytes with the given value counts, in bits per
+/// byte: 0.0 for one repeated byte up to 8.0 for evenly spread values
+pub fn shannon_entropy(histogram: &[usize; 256]) -> f64 {
+    let total: usize = histogram.iter().sum();
+    histogram
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+/// Counts printable bytes, byte classes and the most common byte value in `data`,
+/// and measures its entropy
+pub fn region_stats(data: &[u8], printable: PrintablePolicy) -> RegionStats {
+    let mut stats = RegionStats { total: data.len(), ..Default::default() };
+    let mut histogram = [0usize; 256];
+    for &byte in data {
+        histogram[byte as usize] += 1;
+        stats.classes[classify_byte(byte) as usize] += 1;
+        if printable.is_printable(byte) {
+            stats.printable += 1;
+        }
+    }
+    // Ties go to the lowest byte value
+    stats.most_common = (0..=255u8)
+        .map(|byte| (byte, histogram[byte as usize]))
+        .filter(|&(_, count)| count > 0)
+        .max_by_key(|&(byte, count)| (count, std::cmp::Reverse(byte)));
+    stats.entropy = shannon_entropy(&histogram);
+    stats
+}
+
+/// Finds all non-overlapping occurrences of `needle` in `data` that start at a
+/// multiple of `alignment` (1 for any offset). Unaligned hits don't consume bytes,
+/// so an aligned match that overlaps an earlier unaligned one is still found.
+pub fn find_all(data: &[u8], needle: &[u8], alignment: usize) -> Vec<Range<usize>> {
+    let mut results = Vec::new();
+    if needle.is_empty() {
+        return results;
+    }
+    let alignment = alignment.max(1);
+
+    // Use twoway for efficient searching
+    let mut pos = 0;
+    while pos + needle.len() <= data.len() {
+        if let Some(idx) = find_bytes(&data[pos..], needle) {
+            let absolute_start = pos + idx;
+            if absolute_start % alignment != 0 {
+                pos = absolute_start + 1;
+                continue;
+            }
+            let absolute_end = absolute_start + needle.len();
+            results.push(absolute_start..absolute_end);
+            pos = absolute_end;
+        } else {
+            break;
+        }
+    }
+    results
+}
+
+/// Returns the runs of bytes that differ between two buffers. If one is longer,
+/// its extra bytes count as one differing run.
+pub fn diff_ranges(a: &[u8], b: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (i, (x, y)) in a.iter().zip(b).enumerate() {
+        if x != y {
+            match ranges.last_mut() {
+                Some(range) if range.end == i => range.end += 1,
+                _ => ranges.push(i..i + 1),
+            }
+        }
+    }
+    let (common, longest) = (a.len().min(b.len()), a.len().max(b.len()));
+    if common < longest {
+        match ranges.last_mut() {
+            Some(range) if range.end == common => range.end = longest,
+            _ => ranges.push(common..longest),
+        }
+    }
+    ranges
+}
+
+/// Returns the bytes between two positions, both included, in either order
+pub fn selection_between(a: usize, b: usize) -> Range<usize> {
+    usize::min(a, b)..usize::max(a, b) + 1
+}
+
+/// A rectangular selection: the same columns on each of a run of lines
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockSelection {
+    pub rows: Range<usize>, // Lines the block spans
+    pub cols: Range<usize>, // Columns selected on each of those lines
+}
+
+impl BlockSelection {
+    /// Returns the block with the first and last bytes of a linear selection as
+    /// opposite corners, on lines `bytes_per_line` wide
+    pub fn from_range(range: &Range<usize>, bytes_per_line: usize) -> Self {
+        let last = range.end.saturating_sub(1).max(range.start);
+        let (first_col, last_col) = (range.start % bytes_per_line, last % bytes_per_line);
+        BlockSelection {
+            rows: range.start / bytes_per_line..last / bytes_per_line + 1,
+            cols: first_col.min(last_col)..first_col.max(last_col) + 1,
+        }
+    }
+
+    /// Returns whether the byte at `offset` lies in the block
+    pub fn contains(&self, offset: usize, bytes_per_line: usize) -> bool {
+        self.rows.contains(&(offset / bytes_per_line)) && self.cols.contains(&(offset % bytes_per_line))
+    }
+
+    /// Returns the block's bytes on each line, top to bottom (row-major order)
+    pub fn row_ranges(&self, bytes_per_line: usize) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.rows.clone().map(move |row| row * bytes_per_line + self.cols.start..row * bytes_per_line + self.cols.end)
+    }
+}
+
+/// The bytes at one offset read as each fixed-size integer and float type.
+/// A field is None when the data ends before that type's width.
+#[derive(Clone, Copy, Default)]
+pub struct Inspection {
+    pub i8: Option<i8>,
+    pub u8: Option<u8>,
+    pub i16: Option<i16>,
+    pub u16: Option<u16>,
+    pub i32: Option<i32>,
+    pub u32: Option<u32>,
+    pub i64: Option<i64>,
+    pub u64: Option<u64>,
+    pub f32: Option<f32>,
+    pub f64: Option<f64>,
+}
+
+/// Interprets the start of `data` as each integer and float type in the given byte order
+pub fn inspect_bytes(data: &[u8], endianness: Endianness) -> Inspection {
+    fn take<const N: usize>(data: &[u8]) -> Option<[u8; N]> {
+        data.get(..N)?.try_into().ok()
+    }
+    let big = endianness == Endianness::Big;
+    Inspection {
+        i8: take(data).map(i8::from_le_bytes),
+        u8: take(data).map(u8::from_le_bytes),
+        i16: take(data).map(if big { i16::from_be_bytes } else { i16::from_le_bytes }),
+        u16: take(data).map(if big { u16::from_be_bytes } else { u16::from_le_bytes }),
+        i32: take(data).map(if big { i32::from_be_bytes } else { i32::from_le_bytes }),
+        u32: take(data).map(if big { u32::from_be_bytes } else { u32::from_le_bytes }),
+        i64: take(data).map(if big { i64::from_be_bytes } else { i64::from_le_bytes }),
+        u64: take(data).map(if big { u64::from_be_bytes } else { u64::from_le_bytes }),
+        f32: take(data).map(if big { f32::from_be_bytes } else { f32::from_le_bytes }),
+        f64: take(data).map(if big { f64::from_be_bytes } else { f64::from_le_bytes }),
+    }
+}
+
+/// Decodes an unsigned or signed LEB128 value starting at `offset`, returning the
+/// value and the number of bytes consumed. Returns None if the sequence is cut off
+/// by the end of `data` or doesn't fit in 128 bits.
+pub fn decode_leb128(data: &[u8], offset: usize, signed: bool) -> Option<(i128, usize)> {
+    let mut result: u128 = 0;
+    let mut shift = 0;
+    for (i, &byte) in data.get(offset..)?.iter().enumerate() {
+        if shift >= 128 {
+            return None;
+        }
+        let bits = (byte & 0x7F) as u128;
+        if shift > 121 {
+            // Payload bits past bit 127 must be zero, or sign extension for signed values
+            let excess = bits >> (128 - shift);
+            if excess != 0 && !(signed && excess == 0x7F >> (128 - shift)) {
+                return None;
+            }
+        }
+        result |= bits << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            let value = if signed && shift < 128 && byte & 0x40 != 0 {
+                (result | (!0u128 << shift)) as i128 // Sign-extend from the last byte
+            } else {
+                result as i128
+            };
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Parses a decimal or 0x-prefixed hexadecimal number
+pub fn parse_number(text: &str) -> Result<u64, std::num::ParseIntError> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => text.parse(),
+    }
+}
+
+/// Parses a hex byte pattern such as `48 8b ?? 00`, where `??` matches any byte.
+/// Whitespace is ignored, so `488b??00` is equivalent.
+pub fn parse_hex_pattern(text: &str) -> Result<Vec<Option<u8>>, String> {
+    let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err("Hex pattern has an incomplete byte.".to_string());
+    }
+    digits
+        .chunks(2)
+        .map(|pair| match pair {
+            ['?', '?'] => Ok(None),
+            [high, low] if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() => {
+                let byte: String = pair.iter().collect();
+                u8::from_str_radix(&byte, 16).map(Some).map_err(|e| e.to_string())
+            }
+            _ => Err(format!(
+                "Invalid hex pattern token '{}' (use two hex digits or ??).",
+                pair.iter().collect::<String>()
+            )),
+        })
+        .collect()
+}
+
+/// Interprets clipboard text as bytes. Text that is entirely hex digits (ignoring
+/// whitespace, commas and `0x` prefixes) is decoded as hex; anything else is taken
+/// as its raw UTF-8 bytes.
+pub fn parse_clipboard_bytes(text: &str) -> Vec<u8> {
+    let digits: String = text
+        .replace("0x", "")
+        .replace("0X", "")
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ',')
+        .collect();
+    match hex::decode(&digits) {
+        Ok(bytes) if !bytes.is_empty() => bytes,
+        _ => text.as_bytes().to_vec(),
+    }
+}
+
+/// Reads up to `len` bytes starting at an absolute file position, for lazy loading.
+/// Short reads are retried, so fewer bytes are returned only at the end of the file;
+/// seek and read errors are returned to the caller rather than printed, since stderr
+/// would corrupt the TUI.
+pub fn read_file_range(file: &mut File, position: u64, len: usize) -> io::Result<Vec<u8>> {
+    let mut buffer = Vec::with_capacity(len);
+    file.seek(SeekFrom::Start(position))?;
+    file.take(len as u64).read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+/// Lists the unallocated extents (holes) of a sparse file within `base..base + len`,
+/// as ranges relative to `base`. Uses `SEEK_HOLE`/`SEEK_DATA`; filesystems without
+/// sparse support report no holes.
+#[cfg(target_os = "linux")]
+pub fn sparse_holes(file: &File, base: u64, len: u64) -> Vec<Range<usize>> {
+    use std::os::unix::io::AsRawFd;
+
+    let fd = file.as_raw_fd();
+    let end = base + len;
+    let mut holes = Vec::new();
+    let mut pos = base;
+    while pos < end {
+        // SAFETY: lseek only moves the file position of a descriptor we own
+        let hole = unsafe { libc::lseek(fd, pos as libc::off_t, libc::SEEK_HOLE) };
+        if hole < 0 || hole as u64 >= end {
+            break; // Unsupported, or only the implicit hole at end of file remains
         }
-        Err(e) => {
-            eprintln!("Error reading from file: {}", e);
-            Vec::new()
+        // SAFETY: as above; ENXIO means the hole runs to the end of the file
+        let data = unsafe { libc::lseek(fd, hole, libc::SEEK_DATA) };
+        let hole_end = if data < 0 { end } else { u64::min(data as u64, end) };
+        holes.push((hole as u64 - base) as usize..(hole_end - base) as usize);
+        pos = hole_end;
+    }
+    holes
+}
+
+/// Sparse file holes can't be queried on this platform
+#[cfg(not(target_os = "linux"))]
+pub fn sparse_holes(_file: &File, _base: u64, _len: u64) -> Vec<Range<usize>> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Display options with every view setting at its default
+    fn options() -> DisplayOptions {
+        DisplayOptions {
+            printable: PrintablePolicy::Standard,
+            ascii_render: AsciiRender::Dots,
+            reversed_line: None,
+            uppercase: false,
+            checksum: None,
+            compact_runs: false,
+            address_style: AddressStyle::Linear,
+            address_width: 6,
+            base_address: 0,
+            group_size: 0,
+            radix: DisplayRadix::Hex,
+            overlay: false,
+            colors: ColorScheme::default(),
+        }
+    }
+
+    /// Joins a line's spans into plain text
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn segmented_addresses_keep_four_digit_segments() {
+        let options = DisplayOptions { address_style: AddressStyle::Segmented, ..options() };
+        assert_eq!(format_address(0x12345, options), "1234:0005");
+        assert_eq!(format_address(0xFFFFF, options), "ffff:000f");
+        assert_eq!(format_address(0x10FFEF, options), "ffff:ffff");
+        assert_eq!(format_address(0x10FFF0, options), "00010fff0");
+        let based = DisplayOptions { base_address: 0x100000, ..options };
+        assert_eq!(format_address(0x10, based), "ffff:0020");
+    }
+
+    #[test]
+    fn gutter_adds_the_base_address_to_each_line() {
+        let options = DisplayOptions { base_address: 0x400000, ..options() };
+        let data = [0u8; 32];
+        let lines = format_hex_dump(&data, 3, 2, 16, Highlights::default(), options);
+        assert!(text(&lines[0]).starts_with("400030"));
+        assert!(text(&lines[1]).starts_with("400040"));
+        // Addresses wider than the gutter keep all their digits
+        assert_eq!(format_address(0x10, DisplayOptions { base_address: 0xffffff0, ..options }), "10000000");
+    }
+
+    #[test]
+    fn ruler_has_a_label_over_every_hex_cell() {
+        for (bytes_per_line, group_size, radix) in [
+            (16, 0, DisplayRadix::Hex),
+            (24, 4, DisplayRadix::Hex),
+            (12, 0, DisplayRadix::Octal),
+            (8, 2, DisplayRadix::Binary),
+            (300, 8, DisplayRadix::Hex),
+        ] {
+            let options = DisplayOptions { group_size, radix, ..options() };
+            let ruler = text(&format_ruler(0, bytes_per_line, options));
+            // One label per column, then the ASCII column's digits
+            assert_eq!(ruler.split_whitespace().count(), bytes_per_line + 1);
+            let data = vec![0x41; bytes_per_line];
+            let dump = text(&format_hex_dump(&data, 0, 1, bytes_per_line, Highlights::default(), options)[0]);
+            assert_eq!(ruler.chars().count(), dump.chars().count());
+            // Each label ends where its cell's digits end
+            let label_ends: Vec<usize> = ruler
+                .char_indices()
+                .filter(|&(i, c)| c != ' ' && ruler[i + 1..].starts_with(' '))
+                .map(|(i, _)| i)
+                .collect();
+            let cell_ends: Vec<usize> = dump
+                .char_indices()
+                .filter(|&(i, c)| c != ' ' && c != ':' && dump[i + 1..].starts_with(' '))
+                .map(|(i, _)| i)
+                .collect();
+            assert_eq!(label_ends[..bytes_per_line], cell_ends[..bytes_per_line]);
+        }
+    }
+
+    #[test]
+    fn checksums_match_their_check_values() {
+        assert_eq!(ChecksumAlgo::Crc32.compute(b"123456789"), 0xCBF4_3926);
+        assert_eq!(ChecksumAlgo::Adler32.compute(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(ChecksumAlgo::Sum.compute(&[0xFF; 3]), 0x2FD);
+        // Streaming in chunks gives the same result as one pass
+        for algo in [ChecksumAlgo::Crc32, ChecksumAlgo::Adler32, ChecksumAlgo::Sum] {
+            let mut state = ChecksumState::new(algo);
+            for chunk in b"123456789".chunks(4) {
+                state.update(chunk);
+            }
+            assert_eq!(state.finish(), algo.compute(b"123456789"));
+        }
+    }
+
+    #[test]
+    fn leb128_decodes_unsigned_and_signed_values() {
+        assert_eq!(decode_leb128(&[0xE5, 0x8E, 0x26], 0, false), Some((624_485, 3)));
+        assert_eq!(decode_leb128(&[0xC0, 0xBB, 0x78], 0, true), Some((-123_456, 3)));
+        assert_eq!(decode_leb128(&[0x7F], 0, false), Some((127, 1)));
+        assert_eq!(decode_leb128(&[0x7F], 0, true), Some((-1, 1)));
+        // Decoding starts at the offset and stops at the first byte without the high bit
+        assert_eq!(decode_leb128(&[0xFF, 0x80, 0x01, 0x05], 1, false), Some((128, 2)));
+        // Cut off by the end of the data
+        assert_eq!(decode_leb128(&[0x80, 0x80], 0, false), None);
+        assert_eq!(decode_leb128(&[0x01], 1, false), None);
+    }
+
+    #[test]
+    fn leb128_rejects_values_past_128_bits() {
+        let mut max = vec![0xFF; 18];
+        max.push(0x03);
+        let (value, len) = decode_leb128(&max, 0, false).unwrap();
+        assert_eq!((value as u128, len), (u128::MAX, 19));
+        *max.last_mut().unwrap() = 0x04;
+        assert_eq!(decode_leb128(&max, 0, false), None);
+        // -1 padded with sign-extension bytes still fits
+        let mut minus_one = vec![0xFF; 18];
+        minus_one.push(0x7F);
+        assert_eq!(decode_leb128(&minus_one, 0, true), Some((-1, 19)));
+        assert_eq!(decode_leb128(&[0x80; 20], 0, false), None);
+    }
+
+    #[test]
+    fn inspection_reads_both_byte_orders() {
+        let data = [0x00, 0x00, 0x80, 0x3F, 0x01, 0x02, 0x03, 0xFE];
+        let little = inspect_bytes(&data, Endianness::Little);
+        assert_eq!(little.u8, Some(0x00));
+        assert_eq!(little.u16, Some(0x0000));
+        assert_eq!(little.u32, Some(0x3F80_0000));
+        assert_eq!(little.f32, Some(1.0));
+        assert_eq!(little.u64, Some(0xFE03_0201_3F80_0000));
+        assert_eq!(little.i64, Some(0xFE03_0201_3F80_0000_u64 as i64));
+        let big = inspect_bytes(&data, Endianness::Big);
+        assert_eq!(big.u32, Some(0x0000_803F));
+        assert_eq!(big.u64, Some(0x0000_803F_0102_03FE));
+        let big = inspect_bytes(&[0xFF, 0xFE, 0x3F, 0xF0, 0, 0, 0, 0], Endianness::Big);
+        assert_eq!((big.i8, big.u8), (Some(-1), Some(0xFF)));
+        assert_eq!((big.i16, big.u16), (Some(-2), Some(0xFFFE)));
+        let big = inspect_bytes(&[0x3F, 0xF0, 0, 0, 0, 0, 0, 0], Endianness::Big);
+        assert_eq!(big.f64, Some(1.0));
+    }
+
+    #[test]
+    fn inspection_leaves_types_wider_than_the_data_empty() {
+        let short = inspect_bytes(&[0x34, 0x12, 0x00], Endianness::Little);
+        assert_eq!(short.u16, Some(0x1234));
+        assert_eq!((short.u32, short.f32, short.u64, short.f64), (None, None, None, None));
+        assert!(inspect_bytes(&[], Endianness::Big).u8.is_none());
+    }
+
+    /// Hashes `bytes` fed in `chunk_len`-byte pieces, returning the digest as hex
+    fn digest_hex(algo: DigestAlgo, bytes: &[u8], chunk_len: usize) -> String {
+        let mut state = DigestState::new(algo);
+        for chunk in bytes.chunks(chunk_len) {
+            state.update(chunk);
+        }
+        hex::encode(state.finish())
+    }
+
+    #[test]
+    fn digests_match_known_vectors() {
+        assert_eq!(
+            digest_hex(DigestAlgo::Sha256, b"abc", 3),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            digest_hex(DigestAlgo::Sha256, b"", 1),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(digest_hex(DigestAlgo::Md5, b"abc", 3), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(digest_hex(DigestAlgo::Md5, b"", 1), "d41d8cd98f00b204e9800998ecf8427e");
+        // Streaming in chunks gives the same digest as one pass
+        let text = b"The quick brown fox jumps over the lazy dog";
+        assert_eq!(digest_hex(DigestAlgo::Md5, text, 5), "9e107d9d372bb6826bd81d3542a419d6");
+        assert_eq!(
+            digest_hex(DigestAlgo::Sha256, text, 5),
+            "d7a8fbb307d7809469ca9abcb0082e4f8d5651e46d3cdb762d02d0bf37c9e592"
+        );
+    }
+
+    #[test]
+    fn overview_cells_take_the_dominant_class_and_shade_by_density() {
+        assert!(overview_cell(&[]).is_none());
+        let zeros = overview_cell(&[0; 64]).unwrap();
+        assert!(zeros.class == ByteClass::Zero);
+        assert_eq!((zeros.density, zeros.glyph()), (0.0, '░'));
+        let text = overview_cell(b"hello\0\0\0").unwrap();
+        assert!(text.class == ByteClass::Text);
+        assert_eq!((text.density, text.glyph()), (0.625, '▓'));
+        let mixed = overview_cell(&[0, 0, 0, 0, 0x90, 0x01]).unwrap();
+        assert!(mixed.class == ByteClass::Zero);
+        assert_eq!(mixed.glyph(), '▒');
+        let high = overview_cell(&[0xFF; 8]).unwrap();
+        assert!(high.class == ByteClass::High);
+        assert_eq!(high.glyph(), '█');
+    }
+
+    #[test]
+    fn entropy_spans_zero_to_eight_bits_per_byte() {
+        let mut histogram = [0; 256];
+        assert_eq!(shannon_entropy(&histogram), 0.0);
+        histogram[0x41] = 1000;
+        assert_eq!(shannon_entropy(&histogram), 0.0);
+        histogram[0x42] = 1000;
+        assert_eq!(shannon_entropy(&histogram), 1.0);
+        assert_eq!(shannon_entropy(&[7; 256]), 8.0);
+    }
+
+    #[test]
+    fn region_stats_measure_the_entropy_of_the_bytes() {
+        let every_byte: Vec<u8> = (0..=255).collect();
+        let stats = region_stats(&every_byte, PrintablePolicy::Standard);
+        assert_eq!((stats.total, stats.entropy), (256, 8.0));
+        assert_eq!(stats.most_common, Some((0, 1)));
+        let stats = region_stats(&[0xCC; 100], PrintablePolicy::Standard);
+        assert_eq!((stats.entropy, stats.most_common), (0.0, Some((0xCC, 100))));
+        assert_eq!(region_stats(&[], PrintablePolicy::Standard).entropy, 0.0);
+    }
+
+    #[test]
+    fn block_selection_spans_the_corner_columns_on_each_line() {
+        // Corners 0x12 and 0x35 on 16-byte lines, in either order
+        for range in [selection_between(0x12, 0x35), selection_between(0x35, 0x12)] {
+            let block = BlockSelection::from_range(&range, 16);
+            assert_eq!(block, BlockSelection { rows: 1..4, cols: 2..6 });
+            assert_eq!(block.row_ranges(16).collect::<Vec<_>>(), [0x12..0x16, 0x22..0x26, 0x32..0x36]);
+        }
+        // The bottom-left and top-right corners give the same block
+        assert_eq!(BlockSelection::from_range(&(0x15..0x33), 16), BlockSelection { rows: 1..4, cols: 2..6 });
+        assert_eq!(BlockSelection::from_range(&(7..8), 16), BlockSelection { rows: 0..1, cols: 7..8 });
+
+        // Only the block's cells are highlighted, not the bytes between its rows
+        let data: Vec<u8> = (0..32).collect();
+        let selection = 0x02..0x14;
+        let highlights = Highlights { selection: Some(&selection), block_width: Some(16), ..Highlights::default() };
+        let lines = format_hex_dump(&data, 0, 2, 16, highlights, options());
+        for (row, line) in lines.iter().enumerate() {
+            let selected: Vec<String> = line
+                .spans
+                .iter()
+                .filter(|span| span.style.bg == Some(Color::Magenta) && span.content.ends_with(' '))
+                .map(|span| span.content.trim().to_string())
+                .collect();
+            let expected: Vec<String> = (2..4).map(|col| format!("{:02x}", row * 16 + col)).collect();
+            assert_eq!(selected, expected);
+        }
+    }
+
+    #[test]
+    fn matches_across_a_group_boundary_stay_highlighted_and_aligned() {
+        let data: Vec<u8> = (0..16).collect();
+        let options = DisplayOptions { group_size: 4, ..options() };
+        let matched = 3..5; // The last byte of the first group and the first of the second
+        let highlights = Highlights { matches: std::slice::from_ref(&matched), ..Highlights::default() };
+        let line = format_hex_dump(&data, 0, 1, 16, highlights, options).remove(0);
+        let plain = format_hex_dump(&data, 0, 1, 16, Highlights::default(), options).remove(0);
+        // Highlighting changes no text, so every column stays where it was
+        assert_eq!(text(&line), text(&plain));
+        let highlighted: String = line
+            .spans
+            .iter()
+            .filter(|span| span.style.bg == Some(options.colors.match_bg))
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(highlighted, "03  04 .."); // Both hex cells and the separator, then the ASCII column
+        // Separators next to a single highlighted byte stay plain
+        let matched = 4..5;
+        let highlights = Highlights { matches: std::slice::from_ref(&matched), ..Highlights::default() };
+        let line = format_hex_dump(&data, 0, 1, 16, highlights, options).remove(0);
+        let highlighted: Vec<&str> = line
+            .spans
+            .iter()
+            .filter(|span| span.style.bg == Some(options.colors.match_bg))
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(highlighted, ["04 ", "."]); // The hex cell, then the ASCII column's
+    }
+
+    #[test]
+    fn overlay_cells_show_the_other_files_differing_bytes() {
+        let data = [0xde, 0x01, 0x02, 0x03];
+        let other = [0xad, 0x01]; // Ends two bytes early
+        let options = DisplayOptions { overlay: true, ..options() };
+        assert_eq!(options.cell_width(), 6);
+        assert_eq!(options.hex_width(4), 24);
+        let highlights = Highlights { overlay: Some(&other), ..Highlights::default() };
+        let line = format_hex_dump(&data, 0, 1, 4, highlights, options).remove(0);
+        assert!(text(&line).contains("de→ad 01    02→-- 03→-- "), "{}", text(&line));
+        // Without the second file's bytes the cells keep the same width, so columns line up
+        let plain = format_hex_dump(&data, 0, 1, 4, Highlights::default(), options).remove(0);
+        assert!(text(&plain).contains("de    01    02    03    "));
+        assert_eq!(text(&plain).chars().count(), text(&line).chars().count());
+        // The ruler's labels are spaced to the wider cells
+        assert!(text(&format_ruler(0, 4, options)).ends_with("00    01    02    03      0123"));
+    }
+
+    #[test]
+    fn uppercase_applies_to_the_gutter_and_the_byte_cells() {
+        let data = [0xab, 0xcd, 0x0e, 0xf0];
+        let render = |uppercase| {
+            let options = DisplayOptions { uppercase, ..options() };
+            let line = format_hex_dump(&data, 0xabc, 1, 4, Highlights::default(), options).remove(0);
+            line.spans.iter().map(|span| span.content.to_string()).collect::<Vec<_>>()
+        };
+        let hex_cells = |spans: &[String]| -> Vec<String> {
+            spans.iter().map(|cell| cell.trim().to_string()).filter(|cell| cell.len() == 2).collect()
+        };
+        let (lower, upper) = (render(false), render(true));
+        assert!(lower[0].contains("002af0"), "{:?}", lower);
+        assert!(upper[0].contains("002AF0"), "{:?}", upper);
+        assert_eq!(hex_cells(&lower), ["ab", "cd", "0e", "f0"]);
+        assert_eq!(hex_cells(&upper), ["AB", "CD", "0E", "F0"]);
+    }
+
+    #[test]
+    fn selections_include_both_positions_in_either_order() {
+        assert_eq!(selection_between(3, 7), 3..8);
+        assert_eq!(selection_between(7, 3), 3..8);
+        assert_eq!(selection_between(5, 5), 5..6); // A single byte, never empty
+        assert_eq!(selection_between(0, 0).len(), 1);
+    }
+
+    #[test]
+    fn printable_policies_at_space_del_and_nbsp() {
+        let custom = PrintablePolicy::from_ranges("20,7f-a0").unwrap();
+        let cases = [
+            (PrintablePolicy::Standard, [" ", ".", "."]),
+            (PrintablePolicy::Strict, [".", ".", "."]),
+            (PrintablePolicy::Latin1, [" ", ".", " "]),
+            (custom, [" ", ".", " "]), // DEL is accepted but never written raw
+        ];
+        for (policy, expected) in cases {
+            let shown = [0x20, 0x7F, 0xA0].map(|byte| byte_to_displayable(byte, policy, options()));
+            assert_eq!(shown, expected);
+        }
+        assert!(custom.is_printable(0x7F) && !custom.is_printable(0x21));
+        assert!(!PrintablePolicy::Standard.is_printable(0x7F) && !PrintablePolicy::Latin1.is_printable(0x7F));
+    }
+
+    #[test]
+    fn printable_ranges_parse_single_bytes_and_spans() {
+        let set = |spec| match PrintablePolicy::from_ranges(spec) {
+            Some(policy) => (0..=255u8).filter(|&byte| policy.is_printable(byte)).collect::<Vec<_>>(),
+            None => panic!("{} didn't parse", spec),
+        };
+        assert_eq!(set("41"), [0x41]);
+        assert_eq!(set("30-32, 7e"), [0x30, 0x31, 0x32, 0x7e]);
+        assert_eq!(set("FE-ff").len(), 2);
+        assert_eq!(set("00-ff").len(), 256);
+        for spec in ["", "7e-20", "100", "zz", "20-", "20-30-40"] {
+            assert!(PrintablePolicy::from_ranges(spec).is_none(), "{:?}", spec);
+        }
+    }
+
+    #[test]
+    fn escape_rendering_shows_control_bytes_as_hex_escapes() {
+        let options = DisplayOptions { ascii_render: AsciiRender::Escape, ..options() };
+        assert_eq!(byte_to_displayable(0x1b, options.printable, options), "\\x1b");
+        assert_eq!(byte_to_displayable(0x00, options.printable, options), "\\x00");
+        assert_eq!(byte_to_displayable(b'A', options.printable, options), "A"); // Printable bytes are unchanged
+        let upper = DisplayOptions { uppercase: true, ..options };
+        assert_eq!(byte_to_displayable(0x7f, upper.printable, upper), "\\x7F");
+        // The cycle visits every rendering and comes back to dots
+        let mut render = AsciiRender::Dots;
+        for expected in [AsciiRender::Glyphs, AsciiRender::Codepage, AsciiRender::Escape, AsciiRender::Dots] {
+            render = render.next();
+            assert!(render == expected);
+        }
+        let line = format_hex_dump(&[b'a', 0x07], 0, 1, 2, Highlights::default(), options).remove(0);
+        assert!(text(&line).ends_with("a\\x07"), "{}", text(&line));
+    }
+
+    #[test]
+    fn line_checksums_cover_each_line_and_stay_in_one_column() {
+        assert_eq!(LineChecksum::Xor.compute(&[0x12, 0x34, 0x56, 0x78]), 0x08);
+        assert_eq!(LineChecksum::Sum.compute(&[0x12, 0x34, 0x56, 0x78]), 0x14); // 0x114 mod 256
+        assert_eq!(LineChecksum::Xor.compute(&[]), 0);
+        let data = [0x12, 0x34, 0x56, 0x78, 0xff, 0x01];
+        for (checksum, expected) in [(LineChecksum::Xor, ["= 08", "= fe"]), (LineChecksum::Sum, ["= 14", "= 00"])] {
+            let options = DisplayOptions { checksum: Some(checksum), ..options() };
+            let lines = format_hex_dump(&data, 0, 2, 4, Highlights::default(), options);
+            let texts: Vec<String> = lines.iter().map(text).collect();
+            assert!(texts[0].ends_with(expected[0]) && texts[1].ends_with(expected[1]), "{:?}", texts);
+            // The short last line is padded so both checksums start in the same column
+            assert_eq!(texts[0].find('='), texts[1].find('='));
+            // The column starts past the ruler's ASCII labels
+            let ruler = text(&format_ruler(0, 4, options));
+            assert!(texts[0].find('=').unwrap() > ruler.trim_end().len());
+        }
+    }
+
+    #[test]
+    fn long_null_runs_are_compacted_in_the_ascii_column_only() {
+        let mut data = b"AB".to_vec();
+        data.extend_from_slice(&[0; 14]);
+        let compact = DisplayOptions { compact_runs: true, ..options() };
+        let line = text(&format_hex_dump(&data, 0, 1, 16, Highlights::default(), compact)[0]);
+        let plain = text(&format_hex_dump(&data, 0, 1, 16, Highlights::default(), options())[0]);
+        assert!(line.ends_with("AB.×14"), "{}", line);
+        assert!(plain.ends_with("AB.............."));
+        // The hex column still shows every byte, at the same columns
+        assert_eq!(line[..line.find("AB.×").unwrap()], plain[..plain.find("AB...").unwrap()]);
+        // Runs shorter than the minimum are left alone
+        let short = text(&format_hex_dump(b"A\0\0\0B", 0, 1, 16, Highlights::default(), compact)[0]);
+        assert!(short.ends_with("A...B"));
+    }
+
+    #[test]
+    fn copy_formats_render_the_bytes_as_clipboard_text() {
+        let bytes = [0xde, 0xad, b'H', b'i', b' ', 0x0a];
+        assert_eq!(CopyFormat::Hex.format(&bytes, false), "dead4869200a");
+        assert_eq!(CopyFormat::Hex.format(&bytes, true), "DEAD4869200A");
+        assert_eq!(CopyFormat::HexSpaced.format(&bytes, false), "de ad 48 69 20 0a");
+        assert_eq!(CopyFormat::Ascii.format(&bytes, true), "..Hi .");
+        assert_eq!(CopyFormat::HexSpaced.format(&[], false), "");
+    }
+
+    #[test]
+    fn c_arrays_wrap_at_the_line_width_and_note_the_length() {
+        let bytes: Vec<u8> = (0..14).collect();
+        let expected = "\
+/* 14 bytes */
+unsigned char blob[] = {
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b,
+    0x0c, 0x0d,
+};
+";
+        assert_eq!(format_c_array(&bytes, "blob", 12, false), expected);
+        assert_eq!(format_c_array(&[0xab], "x", 12, true), "/* 1 bytes */\nunsigned char x[] = {\n    0xAB,\n};\n");
+    }
+
+    #[test]
+    fn diff_ranges_join_runs_and_count_a_longer_tail_as_differing() {
+        let none: Vec<Range<usize>> = Vec::new();
+        assert_eq!(diff_ranges(b"same", b"same"), none);
+        assert_eq!(diff_ranges(b"", b""), none);
+        assert_eq!(diff_ranges(b"abcdef", b"aXYdeZ"), [1..3, 5..6]);
+        assert_eq!(diff_ranges(b"abc", b"abcde"), std::slice::from_ref(&(3..5)));
+        assert_eq!(diff_ranges(b"abcde", b"ab"), std::slice::from_ref(&(2..5)));
+        // A difference running into the tail joins it
+        assert_eq!(diff_ranges(b"abX", b"abcde"), std::slice::from_ref(&(2..5)));
+        assert_eq!(diff_ranges(b"", b"xy"), std::slice::from_ref(&(0..2)));
+    }
+
+    #[test]
+    fn fit_bytes_per_line_solves_for_the_widest_multiple_of_eight() {
+        // A 6-digit address and ": " plus the gap before the ASCII column take 10
+        // columns; each byte then takes 3 in hex and 1 in ASCII
+        let fit = |width| fit_bytes_per_line(width, 0xff, options());
+        assert_eq!(fit(40), 8); // Never below 8, even when nothing fits
+        assert_eq!(fit(73), 8);
+        assert_eq!(fit(74), 16);
+        assert_eq!(fit(80), 16);
+        assert_eq!(fit(106), 24);
+        assert_eq!(fit(200), 40);
+        // The solved width matches the lines actually printed
+        let line = format_plain_dump(&[0x41; 16], 0, 16, options());
+        assert_eq!(line.trim_end_matches('\n').chars().count(), 74);
+        // Group separators take a column each
+        let grouped = DisplayOptions { group_size: 8, ..options() };
+        assert_eq!(fit_bytes_per_line(74, 0xff, grouped), 8);
+        assert_eq!(fit_bytes_per_line(75, 0xff, grouped), 16);
+    }
+
+    #[test]
+    fn group_sizes_split_the_hex_column_and_keep_the_ascii_column_aligned() {
+        let data: Vec<u8> = (0x41..0x4b).collect();
+        let expected = [
+            (1, "000000: 41  42  43  44  45  46  47  48   ABCDEFGH"),
+            (2, "000000: 41 42  43 44  45 46  47 48   ABCDEFGH"),
+            (4, "000000: 41 42 43 44  45 46 47 48   ABCDEFGH"),
+            (8, "000000: 41 42 43 44 45 46 47 48   ABCDEFGH"), // One group per line adds nothing
+        ];
+        for (group_size, full) in expected {
+            let options = DisplayOptions { group_size, ..options() };
+            assert_eq!(8 + options.hex_width(8) + 2, full.find('A').unwrap(), "group size {group_size}");
+            let lines: Vec<String> = format_hex_dump(&data, 0, 2, 8, Highlights::default(), options)
+                .iter()
+                .map(text)
+                .collect();
+            assert_eq!(lines[0], full, "group size {group_size}");
+            // The short final line pads out to the same ASCII column
+            assert_eq!(lines[1].find("IJ"), full.find("ABCDEFGH"), "group size {group_size}");
+        }
+    }
+
+    #[test]
+    fn each_radix_renders_bytes_at_its_own_width() {
+        let cases = [
+            (DisplayRadix::Hex, "05 c8 41 ", 2),
+            (DisplayRadix::Octal, "005 310 101 ", 3),
+            (DisplayRadix::Decimal, "  5 200  65 ", 3),
+            (DisplayRadix::Binary, "00000101 11001000 01000001 ", 8),
+        ];
+        for (radix, cells, digits) in cases {
+            assert_eq!(radix.digits(), digits);
+            assert_eq!(radix.format(0xff, false).len(), digits);
+            let options = DisplayOptions { radix, ..options() };
+            assert_eq!(options.hex_width(4), 4 * (digits + 1));
+            let line = text(&format_hex_dump(&[5, 200, 65], 0, 1, 4, Highlights::default(), options)[0]);
+            assert!(line.starts_with(&format!("000000: {cells}")), "{line}");
+            // The ASCII column starts after a full line's cells, whatever the radix
+            assert_eq!(line.find("..A"), Some(8 + options.hex_width(4) + 2), "{line}");
+            // A match highlights the byte's whole cell in any radix
+            let matched = 1..2;
+            let highlights = Highlights { matches: std::slice::from_ref(&matched), ..Highlights::default() };
+            let highlighted: Vec<String> = format_hex_dump(&[5, 200, 65], 0, 1, 4, highlights, options)[0]
+                .spans
+                .iter()
+                .filter(|span| span.style.bg == Some(options.colors.match_bg))
+                .map(|span| span.content.to_string())
+                .collect();
+            assert_eq!(highlighted, [format!("{} ", radix.format(200, false)), ".".to_string()]);
+        }
+    }
+
+    #[test]
+    fn address_digits_fit_the_file_size() {
+        assert_eq!(address_digits(100), 6);
+        assert_eq!(address_digits(0xff_ffff), 6);
+        assert_eq!(address_digits(0x100_0000), 8);
+        assert_eq!(address_digits(5_000_000_000), 10); // 5 GB, past 32 bits
+        let options = DisplayOptions { address_width: address_digits(5_000_000_000), ..options() };
+        assert_eq!(format_address(0x1_2a05_f1ff, options), "012a05f1ff");
+    }
+
+    #[test]
+    fn byte_color_maps_each_category() {
+        let scheme = ColorScheme::default();
+        assert_eq!(byte_color(0x00, &scheme), scheme.null_byte);
+        for byte in [0x20, b'A', b'z', b'~'] {
+            assert_eq!(byte_color(byte, &scheme), scheme.ascii_printable, "{byte:02x}");
+        }
+        for byte in [0x01, 0x1f, 0x7f, 0x80, 0xfe] {
+            assert_eq!(byte_color(byte, &scheme), scheme.hex_byte, "{byte:02x}");
+        }
+        assert_eq!(byte_color(0xff, &scheme), scheme.ff_byte);
+        // The four categories are told apart by the default scheme
+        let colors = [scheme.null_byte, scheme.ff_byte, scheme.ascii_printable, scheme.hex_byte];
+        assert_eq!(colors.iter().collect::<std::collections::HashSet<_>>().len(), 4);
+    }
+
+    #[test]
+    fn byte_colors_reach_both_columns_and_matches_override_them() {
+        let options = options();
+        let scheme = options.colors;
+        let data = [0x00, 0xff, b'A', 0x80];
+        let matched = 2..3;
+        let highlights = Highlights { matches: std::slice::from_ref(&matched), ..Highlights::default() };
+        let line = format_hex_dump(&data, 0, 1, 4, highlights, options).remove(0);
+        let style_of = |content: &str| {
+            line.spans.iter().filter(|span| span.content == content).map(|span| span.style).collect::<Vec<_>>()
+        };
+        assert_eq!(style_of("00 ")[0].fg, Some(scheme.null_byte));
+        assert_eq!(style_of("ff ")[0].fg, Some(scheme.ff_byte));
+        assert_eq!(style_of("80 ")[0].fg, Some(scheme.hex_byte));
+        // The matched byte takes the match colors in both columns
+        let matched_styles = style_of("41 ").into_iter().chain(style_of("A"));
+        for style in matched_styles {
+            assert_eq!((style.fg, style.bg), (Some(scheme.match_fg), Some(scheme.match_bg)));
         }
+        // Unmatched ASCII cells keep their byte's color
+        let dots: Vec<_> = style_of(".").iter().map(|style| style.fg).collect();
+        assert_eq!(dots, [Some(scheme.null_byte), Some(scheme.ff_byte), Some(scheme.hex_byte)]);
     }
 }